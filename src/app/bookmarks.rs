@@ -0,0 +1,82 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::stored_path;
+
+pub(crate) const BOOKMARKS_VERSION: u32 = 1;
+
+/// A new bookmark this close to an existing one replaces it.
+const BOOKMARK_MERGE_SECONDS: f64 = 1.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Bookmark {
+    #[serde(with = "stored_path")]
+    pub(crate) path: PathBuf,
+    pub(crate) position: f64,
+}
+
+/// Saved positions within files, added from the timeline's context menu.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Bookmarks {
+    pub(crate) version: u32,
+    pub(crate) bookmarks: Vec<Bookmark>,
+}
+
+impl Default for Bookmarks {
+    fn default() -> Self {
+        Self {
+            version: BOOKMARKS_VERSION,
+            bookmarks: Vec::new(),
+        }
+    }
+}
+
+impl Bookmarks {
+    /// Bookmarked positions in `path`, earliest first.
+    pub(crate) fn for_path(&self, path: &Path) -> Vec<f64> {
+        let mut positions: Vec<f64> = self
+            .bookmarks
+            .iter()
+            .filter(|bookmark| bookmark.path == path)
+            .map(|bookmark| bookmark.position)
+            .collect();
+        positions.sort_by(f64::total_cmp);
+        positions
+    }
+
+    /// Bookmarks `position` in `path`, returning whether anything changed.
+    pub(crate) fn add(&mut self, path: &Path, position: f64) -> bool {
+        let duplicate = self.bookmarks.iter().any(|bookmark| {
+            bookmark.path == path && (bookmark.position - position).abs() < BOOKMARK_MERGE_SECONDS
+        });
+        if duplicate {
+            return false;
+        }
+
+        self.bookmarks.push(Bookmark {
+            path: path.to_path_buf(),
+            position: position.max(0.0),
+        });
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bookmarks_are_listed_in_order_without_near_duplicates() {
+        let path = Path::new("/music/suite.prot");
+        let mut bookmarks = Bookmarks::default();
+
+        assert!(bookmarks.add(path, 90.0));
+        assert!(bookmarks.add(path, 30.0));
+        assert!(!bookmarks.add(path, 30.5));
+        assert!(bookmarks.add(Path::new("/music/other.prot"), 10.0));
+
+        assert_eq!(bookmarks.for_path(path), vec![30.0, 90.0]);
+    }
+}
@@ -0,0 +1,18 @@
+use std::path::PathBuf;
+
+use crate::app::bookmarks::Bookmarks;
+use crate::app::storage;
+
+const BOOKMARKS_NAME: &str = "bookmarks.json";
+
+pub(crate) fn load() -> Result<Bookmarks, String> {
+    storage::read_json(&storage_path()?)
+}
+
+pub(crate) fn save(bookmarks: &Bookmarks) -> Result<(), String> {
+    storage::write_json(&storage_path()?, bookmarks)
+}
+
+fn storage_path() -> Result<PathBuf, String> {
+    storage::data_file_path(BOOKMARKS_NAME)
+}
@@ -0,0 +1,180 @@
+use std::path::PathBuf;
+
+use iced::window;
+
+use crate::app::helpers::format_time;
+use crate::app::keybindings::Action;
+use crate::native_menu::MenuAction;
+
+/// How many matches the palette lists at once.
+const MAX_MATCHES: usize = 50;
+
+#[derive(Debug, Clone)]
+pub(crate) enum PaletteCommand {
+    Action(Action),
+    Menu(MenuAction),
+    /// Jumps to a bookmark, opening its file first when it is not playing.
+    Bookmark {
+        path: PathBuf,
+        position: f64,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct PaletteItem {
+    pub(crate) title: String,
+    /// Secondary text, such as a file's folder. Also searched, at lower weight.
+    pub(crate) detail: String,
+    pub(crate) shortcut: Option<String>,
+    pub(crate) command: PaletteCommand,
+}
+
+impl PaletteItem {
+    pub(crate) fn file(title: String, path: PathBuf) -> Self {
+        Self {
+            title,
+            detail: path
+                .parent()
+                .map(|parent| parent.display().to_string())
+                .unwrap_or_default(),
+            shortcut: None,
+            command: PaletteCommand::Menu(MenuAction::OpenRecent(path)),
+        }
+    }
+
+    /// A bookmark listed as "file @ time".
+    pub(crate) fn bookmark(path: PathBuf, position: f64) -> Self {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Self {
+            title: format!("{name} @ {}", format_time(position)),
+            detail: path
+                .parent()
+                .map(|parent| parent.display().to_string())
+                .unwrap_or_default(),
+            shortcut: None,
+            command: PaletteCommand::Bookmark { path, position },
+        }
+    }
+}
+
+/// An open palette, tied to the window its commands run in.
+pub(crate) struct CommandPalette {
+    pub(crate) window_id: window::Id,
+    pub(crate) query: String,
+    pub(crate) selected: usize,
+    pub(crate) matches: Vec<PaletteItem>,
+}
+
+impl CommandPalette {
+    pub(crate) fn new(window_id: window::Id) -> Self {
+        Self {
+            window_id,
+            query: String::new(),
+            selected: 0,
+            matches: Vec::new(),
+        }
+    }
+
+    /// Ranks `items` against `query`, keeping the best matches. An empty query
+    /// keeps the items in the order given.
+    pub(crate) fn search(&mut self, query: String, items: Vec<PaletteItem>) {
+        let mut scored: Vec<(i64, PaletteItem)> = items
+            .into_iter()
+            .filter_map(|item| {
+                let score = fuzzy_score(&query, &item.title)
+                    .or_else(|| fuzzy_score(&query, &item.detail).map(|score| score - 100))?;
+                Some((score, item))
+            })
+            .collect();
+        // A stable sort keeps actions ahead of files on equal scores.
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+        self.query = query;
+        self.selected = 0;
+        self.matches = scored
+            .into_iter()
+            .take(MAX_MATCHES)
+            .map(|(_, item)| item)
+            .collect();
+    }
+
+    pub(crate) fn move_selection(&mut self, down: bool) {
+        if self.matches.is_empty() {
+            return;
+        }
+
+        self.selected = if down {
+            (self.selected + 1).min(self.matches.len() - 1)
+        } else {
+            self.selected.saturating_sub(1)
+        };
+    }
+}
+
+/// Scores `candidate` when every character of `query` appears in it in order,
+/// ignoring case. Consecutive matches and matches at word starts score higher.
+pub(crate) fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|character| !character.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let mut score = 0;
+    let mut query_index = 0;
+    let mut previous_matched = false;
+    let mut previous: Option<char> = None;
+
+    for character in candidate.chars() {
+        let word_start = previous.is_none_or(|previous| !previous.is_alphanumeric());
+        let matched = query_index < query.len()
+            && character
+                .to_lowercase()
+                .eq(std::iter::once(query[query_index]));
+
+        if matched {
+            score += 1;
+            if previous_matched {
+                score += 5;
+            }
+            if word_start {
+                score += 10;
+            }
+            query_index += 1;
+        } else if query_index < query.len() {
+            // Gaps before the last matched character cost a little.
+            score -= 1;
+        }
+
+        previous_matched = matched;
+        previous = Some(character);
+    }
+
+    (query_index == query.len()).then_some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_matching_prefers_word_starts_and_runs() {
+        assert!(fuzzy_score("zi", "Zoom In").is_some());
+        assert!(fuzzy_score("zoom in", "Zoom In").is_some());
+        assert!(fuzzy_score("nz", "Zoom In").is_none());
+
+        let word_starts = fuzzy_score("nw", "New Window").unwrap();
+        let scattered = fuzzy_score("nw", "Unknown").unwrap();
+        assert!(word_starts > scattered);
+
+        let run = fuzzy_score("shuf", "Shuffle").unwrap();
+        let spread = fuzzy_score("shuf", "Show Help Unfolded").unwrap();
+        assert!(run > spread);
+    }
+}
@@ -0,0 +1,63 @@
+use iced::widget::{column, container, mouse_area, row, scrollable, text, text_input};
+use iced::{Alignment, Element, Length};
+
+use crate::app::command_palette::CommandPalette;
+use crate::app::messages::Message;
+use crate::app::state::ProteusApp;
+use crate::app::styles::{background_style, library_row_style};
+
+pub(crate) const COMMAND_PALETTE_INPUT: &str = "command-palette";
+
+pub(crate) fn command_palette_overlay<'a>(
+    state: &'a ProteusApp,
+    palette: &'a CommandPalette,
+) -> Element<'a, Message> {
+    let colors = state.palette;
+
+    let input = text_input("Type a command or file name…", &palette.query)
+        .id(COMMAND_PALETTE_INPUT)
+        .on_input(Message::CommandPaletteChanged)
+        .on_submit(Message::CommandPaletteSubmitted)
+        .size(13)
+        .padding([4, 8]);
+
+    let matches =
+        palette
+            .matches
+            .iter()
+            .enumerate()
+            .fold(column![].spacing(1), |matches, (index, item)| {
+                let title = column![text(&item.title).size(12)]
+                    .push(
+                        (!item.detail.is_empty())
+                            .then(|| text(&item.detail).size(10).color(colors.accent_text)),
+                    )
+                    .width(Length::Fill);
+                let shortcut = text(item.shortcut.clone().unwrap_or_default())
+                    .size(11)
+                    .color(colors.accent_text);
+
+                matches.push(
+                    mouse_area(
+                        container(row![title, shortcut].spacing(8).align_y(Alignment::Center))
+                            .padding([2, 8])
+                            .width(Length::Fill)
+                            .style(library_row_style(colors, index == palette.selected)),
+                    )
+                    .on_press(Message::CommandPaletteItemPressed(index)),
+                )
+            });
+
+    let content = if palette.matches.is_empty() {
+        column![input, text("No matches").size(11).color(colors.accent_text)]
+    } else {
+        column![input, scrollable(matches).height(Length::Fill)]
+    };
+
+    container(content.spacing(6))
+        .padding([8, 12])
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(background_style(colors))
+        .into()
+}
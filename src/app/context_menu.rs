@@ -0,0 +1,31 @@
+use iced::Point;
+
+/// Which part of the player window was right-clicked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ContextMenuKind {
+    Surface,
+    Timeline,
+}
+
+/// An open context menu, placed where the window was right-clicked.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ContextMenu {
+    pub(crate) kind: ContextMenuKind,
+    pub(crate) position: Point,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ContextAction {
+    PlayPause,
+    CopyTime,
+    SetLoopIn,
+    SetLoopOut,
+    ClearLoop,
+    AddBookmark,
+    GoToBookmark(f64),
+    CopyCombination,
+    RevealInFileManager,
+    ShowProperties,
+    ToggleMiniPlayer,
+    OpenInNewWindow,
+}
@@ -0,0 +1,170 @@
+use iced::widget::{
+    Column, Space, button, column, container, mouse_area, responsive, rule, scrollable, stack, text,
+};
+use iced::{Element, Length, Padding, window};
+
+use crate::app::context_menu::{ContextAction, ContextMenu, ContextMenuKind};
+use crate::app::helpers::format_time;
+use crate::app::messages::Message;
+use crate::app::state::{PlayerWindowState, ProteusApp};
+use crate::app::styles::menu_surface_style;
+
+const PANEL_WIDTH: f32 = 210.0;
+/// Space kept below the cursor before the menu moves up to fit.
+const MIN_PANEL_HEIGHT: f32 = 120.0;
+
+/// The open context menu, placed at the cursor over a layer that closes it
+/// when clicked elsewhere.
+pub(crate) fn context_menu_overlay<'a>(
+    state: &'a ProteusApp,
+    window: &'a PlayerWindowState,
+    window_id: window::Id,
+    menu: ContextMenu,
+) -> Element<'a, Message> {
+    let dismiss = mouse_area(Space::new().width(Length::Fill).height(Length::Fill))
+        .on_press(Message::ContextMenuDismissed(window_id))
+        .on_right_press(Message::ContextMenuDismissed(window_id));
+
+    let panel = responsive(move |size| {
+        let left = menu.position.x.min(size.width - PANEL_WIDTH).max(0.0);
+        let top = menu.position.y.min(size.height - MIN_PANEL_HEIGHT).max(0.0);
+
+        container(
+            container(scrollable(menu_items(state, window, window_id, menu.kind)))
+                .padding(4)
+                .width(Length::Fixed(PANEL_WIDTH))
+                .max_height(size.height - top)
+                .style(menu_surface_style(state.palette)),
+        )
+        .padding(Padding {
+            top,
+            left,
+            ..Padding::ZERO
+        })
+        .into()
+    });
+
+    stack![dismiss, panel].into()
+}
+
+fn menu_items<'a>(
+    state: &'a ProteusApp,
+    window: &'a PlayerWindowState,
+    window_id: window::Id,
+    kind: ContextMenuKind,
+) -> Column<'a, Message> {
+    let loaded = window.playback.current_path().is_some();
+    let item = |label: String, action: ContextAction, enabled: bool| {
+        menu_item(label, window_id, action, enabled)
+    };
+
+    let file_items = column![
+        item(
+            "Copy Combination Code".to_owned(),
+            ContextAction::CopyCombination,
+            loaded
+        ),
+        item(
+            "Reveal in File Manager".to_owned(),
+            ContextAction::RevealInFileManager,
+            loaded
+        ),
+        item(
+            "Properties…".to_owned(),
+            ContextAction::ShowProperties,
+            loaded
+        ),
+        item(
+            "Open in New Window".to_owned(),
+            ContextAction::OpenInNewWindow,
+            loaded
+        ),
+    ]
+    .spacing(1);
+
+    match kind {
+        ContextMenuKind::Surface => {
+            let play_pause = if window.playing { "Pause" } else { "Play" };
+            column![
+                item(play_pause.to_owned(), ContextAction::PlayPause, loaded),
+                rule::horizontal(1),
+                file_items,
+                rule::horizontal(1),
+                item(
+                    "Mini Player".to_owned(),
+                    ContextAction::ToggleMiniPlayer,
+                    true
+                ),
+            ]
+        }
+        ContextMenuKind::Timeline => {
+            let time = format_time(window.current_time);
+            let bookmarks = state.window_bookmarks(window).into_iter().fold(
+                column![].spacing(1),
+                |bookmarks, position| {
+                    bookmarks.push(item(
+                        format!("Go to Bookmark {}", format_time(position)),
+                        ContextAction::GoToBookmark(position),
+                        true,
+                    ))
+                },
+            );
+
+            column![
+                item(
+                    format!("Copy Current Time ({time})"),
+                    ContextAction::CopyTime,
+                    loaded
+                ),
+                rule::horizontal(1),
+                item(
+                    marker_label("Set Loop In", window.loop_in),
+                    ContextAction::SetLoopIn,
+                    loaded
+                ),
+                item(
+                    marker_label("Set Loop Out", window.loop_out),
+                    ContextAction::SetLoopOut,
+                    loaded
+                ),
+                item(
+                    "Clear Loop".to_owned(),
+                    ContextAction::ClearLoop,
+                    window.loop_in.is_some() || window.loop_out.is_some()
+                ),
+                rule::horizontal(1),
+                item(
+                    "Add Bookmark".to_owned(),
+                    ContextAction::AddBookmark,
+                    loaded
+                ),
+                bookmarks,
+                rule::horizontal(1),
+                file_items,
+            ]
+        }
+    }
+    .spacing(1)
+}
+
+/// Shows where a loop marker currently sits, e.g. `Set Loop In (01:30)`.
+fn marker_label(label: &str, marker: Option<f64>) -> String {
+    match marker {
+        Some(position) => format!("{label} ({})", format_time(position)),
+        None => label.to_owned(),
+    }
+}
+
+fn menu_item<'a>(
+    label: String,
+    window_id: window::Id,
+    action: ContextAction,
+    enabled: bool,
+) -> Element<'a, Message> {
+    button(text(label).size(12).width(Length::Fill))
+        .style(button::text)
+        .padding([2, 6])
+        .width(Length::Fill)
+        .on_press_maybe(enabled.then_some(Message::ContextMenuAction { window_id, action }))
+        .into()
+}
@@ -0,0 +1,228 @@
+use iced::widget::{Column, Row, Space, button, column, container, row, scrollable, text};
+use iced::{Alignment, Element, Length, window};
+
+use crate::app::memory::{ExportFormat, Sample, bytes_to_mb};
+use crate::app::messages::Message;
+use crate::app::state::ProteusApp;
+use crate::app::styles::{background_style, level_bar_style};
+use crate::app::theme::Palette;
+
+const LABEL_WIDTH: f32 = 110.0;
+const GRAPH_HEIGHT: f32 = 40.0;
+/// Samples shown in each graph, the last two minutes or so.
+const GRAPH_SAMPLES: usize = 120;
+
+/// Memory, threads, timing and output buffers, sampled once a second for as
+/// long as the app has been running.
+pub(crate) fn diagnostics_overlay(
+    state: &ProteusApp,
+    window_id: window::Id,
+) -> Element<'_, Message> {
+    let palette = state.palette;
+    let sampler = &state.memory_sampler;
+
+    let header = row![
+        text("Diagnostics").size(13).width(Length::Fill),
+        button(text("Export CSV…").size(11))
+            .style(button::text)
+            .padding([0, 4])
+            .on_press(Message::ExportDiagnosticsPressed(ExportFormat::Csv)),
+        button(text("Export JSON…").size(11))
+            .style(button::text)
+            .padding([0, 4])
+            .on_press(Message::ExportDiagnosticsPressed(ExportFormat::Json)),
+        button(text("Close").size(11))
+            .style(button::text)
+            .padding([0, 4])
+            .on_press(Message::ToggleDiagnostics(window_id)),
+    ]
+    .spacing(4)
+    .align_y(Alignment::Center);
+
+    let Some(latest) = sampler.samples().back() else {
+        return panel(
+            palette,
+            column![
+                header,
+                text("Collecting the first sample…")
+                    .size(11)
+                    .color(palette.accent_text)
+            ],
+        );
+    };
+
+    let fields = [
+        (
+            "Memory",
+            format!(
+                "{:.1} MB resident, peak {:.1} MB",
+                bytes_to_mb(latest.rss_bytes),
+                bytes_to_mb(sampler.peak_rss)
+            ),
+        ),
+        (
+            "Virtual",
+            format!(
+                "{:.1} MB, peak {:.1} MB",
+                bytes_to_mb(latest.virtual_bytes),
+                bytes_to_mb(sampler.peak_virtual)
+            ),
+        ),
+        (
+            "Threads",
+            latest
+                .threads
+                .map_or_else(|| "n/a".to_owned(), |threads| threads.to_string()),
+        ),
+        (
+            "Players",
+            format!(
+                "{} loaded in {} windows",
+                latest.loaded_players, latest.windows
+            ),
+        ),
+        (
+            "Tick",
+            timing(
+                latest.tick_interval_ms,
+                latest.tick_interval_max_ms,
+                latest.tick_work_ms,
+            ),
+        ),
+        (
+            "Frame",
+            timing(latest.frame_interval_ms, latest.frame_interval_max_ms, None),
+        ),
+        (
+            "Output buffer",
+            format!(
+                "{} chunks queued, {} underruns",
+                latest.queued_chunks, latest.underruns
+            ),
+        ),
+        ("Samples", sampler.samples().len().to_string()),
+    ];
+    let fields = fields
+        .into_iter()
+        .fold(Column::new().spacing(2), |fields, (label, value)| {
+            fields.push(field(palette, label, value))
+        });
+
+    let recent: Vec<&Sample> = sampler
+        .samples()
+        .iter()
+        .rev()
+        .take(GRAPH_SAMPLES)
+        .rev()
+        .collect();
+    let graphs = column![
+        text("Resident memory").size(11),
+        graph(
+            palette,
+            recent
+                .iter()
+                .map(|sample| sample.rss_bytes as f32)
+                .collect()
+        ),
+        // Threads are only counted while the panel is open, so the graph
+        // skips the samples taken before it.
+        text("Threads").size(11),
+        graph(
+            palette,
+            recent
+                .iter()
+                .filter_map(|sample| sample.threads.map(|threads| threads as f32))
+                .collect()
+        ),
+    ]
+    .spacing(4);
+
+    let windows = state.windows.values().fold(
+        column![text("Windows").size(11)].spacing(2),
+        |windows, window| {
+            let value = match window.playback.buffer_stats() {
+                Some(stats) => format!(
+                    "{}, {} chunks / {:.0} ms queued, {} underruns, longest overrun {:.1} ms",
+                    if window.playing { "playing" } else { "paused" },
+                    stats.queued_chunks,
+                    stats.queued_ms,
+                    stats.underruns,
+                    stats.max_overrun_ms
+                ),
+                None => "no player".to_owned(),
+            };
+            let title = if window.window_title.is_empty() {
+                "Empty window"
+            } else {
+                window.window_title.as_str()
+            };
+            windows.push(field(palette, title, value))
+        },
+    );
+
+    panel(
+        palette,
+        column![
+            header,
+            scrollable(column![fields, graphs, windows].spacing(10)).height(Length::Fill)
+        ],
+    )
+}
+
+fn panel(palette: Palette, content: Column<'_, Message>) -> Element<'_, Message> {
+    container(content.spacing(6))
+        .padding([8, 12])
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(background_style(palette))
+        .into()
+}
+
+fn field(palette: Palette, label: &str, value: String) -> Element<'_, Message> {
+    row![
+        text(label).size(11).width(Length::Fixed(LABEL_WIDTH)),
+        text(value).size(11).color(palette.accent_text),
+    ]
+    .spacing(8)
+    .into()
+}
+
+fn timing(average: Option<f64>, max: Option<f64>, work: Option<f64>) -> String {
+    match (average, max) {
+        (Some(average), Some(max)) => {
+            let mut timing = format!("every {average:.1} ms, longest {max:.1} ms");
+            if let Some(work) = work {
+                timing.push_str(&format!(", {work:.2} ms of work"));
+            }
+            timing
+        }
+        _ => "not measured".to_owned(),
+    }
+}
+
+/// A bar per sample, scaled to the largest value shown.
+fn graph<'a>(palette: Palette, values: Vec<f32>) -> Element<'a, Message> {
+    let max = values.iter().copied().fold(0.0, f32::max);
+    values
+        .into_iter()
+        .fold(
+            Row::new()
+                .spacing(1)
+                .height(Length::Fixed(GRAPH_HEIGHT))
+                .align_y(Alignment::End),
+            |bars, value| {
+                let height = if max > 0.0 {
+                    value / max * GRAPH_HEIGHT
+                } else {
+                    0.0
+                };
+                bars.push(
+                    container(Space::new())
+                        .width(Length::Fill)
+                        .height(Length::Fixed(height.max(1.0)))
+                        .style(level_bar_style(palette)),
+                )
+            },
+        )
+        .into()
+}
@@ -1,10 +1,28 @@
 use iced::task::Task;
-use iced::window;
-use std::path::PathBuf;
-
+use iced::{Point, window};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::app::bookmarks::Bookmarks;
+use crate::app::keybindings::KeybindingsFile;
+use crate::app::library::{self, FolderContents, LibraryEntry};
+use crate::app::memory::ExportFormat;
 use crate::app::messages::Message;
-use crate::app::recent_files_store;
-use crate::app::styles::{WINDOW_HEIGHT, WINDOW_WIDTH};
+use crate::app::properties::FileProperties;
+use crate::app::resume::ResumePositions;
+use crate::app::session::Session;
+use crate::app::settings::Settings;
+use crate::app::styles::{
+    LIBRARY_WINDOW_HEIGHT, LIBRARY_WINDOW_WIDTH, MENU_BAR_HEIGHT, MINI_PLAYER_SIZE,
+    PREFERENCES_WINDOW_HEIGHT, PREFERENCES_WINDOW_WIDTH, WINDOW_HEIGHT, WINDOW_WIDTH,
+};
+use crate::app::theme;
+use crate::app::{
+    bookmarks_store, keybindings_store, library_store, recent_files_store, resume_store,
+    session_store, settings_store,
+};
+use crate::waveform::{self, Waveform};
 
 #[cfg(not(target_os = "macos"))]
 pub(crate) fn request_open_dialog(generation: u64) -> Task<Message> {
@@ -127,6 +145,172 @@ pub(crate) fn persist_recent_files(generation: u64, files: Vec<PathBuf>) -> Task
     )
 }
 
+pub(crate) fn load_library() -> Task<Message> {
+    Task::perform(async move { library_store::load() }, Message::LibraryLoaded)
+}
+
+pub(crate) fn persist_library(generation: u64, index: library::LibraryIndex) -> Task<Message> {
+    Task::perform(async move { library_store::save(&index) }, move |result| {
+        Message::LibraryPersisted { generation, result }
+    })
+}
+
+pub(crate) fn persist_settings(generation: u64, settings: Settings) -> Task<Message> {
+    Task::perform(
+        async move { settings_store::save(&settings) },
+        move |result| Message::SettingsPersisted { generation, result },
+    )
+}
+
+pub(crate) fn persist_keybindings(generation: u64, file: KeybindingsFile) -> Task<Message> {
+    Task::perform(
+        async move { keybindings_store::save(&file) },
+        move |result| Message::KeybindingsPersisted { generation, result },
+    )
+}
+
+pub(crate) fn find_sibling_file(path: PathBuf, forward: bool) -> Task<Option<PathBuf>> {
+    Task::perform(
+        async move { library::sibling_file(&path, forward) },
+        std::convert::identity,
+    )
+}
+
+pub(crate) fn persist_session(session: Session) -> Task<Message> {
+    Task::perform(
+        async move { session_store::save(&session) },
+        Message::SessionPersisted,
+    )
+}
+
+pub(crate) fn persist_resume_positions(
+    generation: u64,
+    positions: ResumePositions,
+) -> Task<Message> {
+    Task::perform(
+        async move { resume_store::save(&positions) },
+        move |result| Message::ResumePositionsPersisted { generation, result },
+    )
+}
+
+pub(crate) fn persist_bookmarks(generation: u64, bookmarks: Bookmarks) -> Task<Message> {
+    Task::perform(
+        async move { bookmarks_store::save(&bookmarks) },
+        move |result| Message::BookmarksPersisted { generation, result },
+    )
+}
+
+pub(crate) fn scan_folder(path: PathBuf) -> Task<FolderContents> {
+    Task::perform(
+        async move { library::folder_contents(&path) },
+        std::convert::identity,
+    )
+}
+
+pub(crate) fn read_file_properties(path: PathBuf) -> Task<Result<FileProperties, String>> {
+    Task::perform(async move { FileProperties::read(&path) }, |result| result)
+}
+
+pub(crate) fn read_waveform(path: PathBuf) -> Task<Result<Waveform, String>> {
+    Task::perform(async move { waveform::read(&path) }, |result| result)
+}
+
+/// Shows the file selected in the platform's file manager. Linux has no
+/// portable way to select a file, so its folder is opened instead.
+pub(crate) fn reveal_in_file_manager(path: &Path) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = Command::new("open");
+        command.arg("-R").arg(path);
+        command
+    };
+
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut argument = std::ffi::OsString::from("/select,");
+        argument.push(path);
+        let mut command = Command::new("explorer");
+        command.arg(argument);
+        command
+    };
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut command = {
+        let folder = path.parent().unwrap_or(path);
+        let mut command = Command::new("xdg-open");
+        command.arg(folder);
+        command
+    };
+
+    let mut child = command.spawn().map_err(|error| error.to_string())?;
+    // The opener exits as soon as it has handed the path over. Waiting on a
+    // thread of its own reaps it without holding up the UI thread.
+    std::thread::Builder::new()
+        .name("reveal-in-file-manager".to_owned())
+        .spawn(move || {
+            let _ = child.wait();
+        })
+        .map(|_| ())
+        .map_err(|error| error.to_string())
+}
+
+pub(crate) fn load_user_themes() -> Task<Message> {
+    Task::perform(
+        async move { theme::load_user_themes(&theme::themes_file_path()?) },
+        Message::UserThemesLoaded,
+    )
+}
+
+pub(crate) fn scan_library(
+    generation: u64,
+    folders: Vec<PathBuf>,
+    previous: Vec<LibraryEntry>,
+) -> Task<Message> {
+    Task::perform(
+        async move { library::scan_folders(&folders, previous) },
+        move |entries| Message::LibraryScanned {
+            generation,
+            entries,
+        },
+    )
+}
+
+pub(crate) fn request_library_folder_dialog() -> Task<Message> {
+    // Like the open dialog, the picker must be created on the main thread.
+    let picker = rfd::AsyncFileDialog::new()
+        .set_title("Add Folder to Library")
+        .pick_folder();
+
+    Task::perform(
+        async move { picker.await.map(|folder| folder.path().to_owned()) },
+        Message::LibraryFolderPicked,
+    )
+}
+
+/// Asks where to save the diagnostics history, then writes it there.
+pub(crate) fn export_diagnostics(format: ExportFormat, contents: String) -> Task<Message> {
+    // Like the open dialog, the picker must be created on the main thread.
+    let extension = format.extension();
+    let picker = rfd::AsyncFileDialog::new()
+        .set_title("Export Diagnostics")
+        .set_file_name(format!("proteus-player-diagnostics.{extension}"))
+        .add_filter(extension.to_uppercase(), &[extension])
+        .save_file();
+
+    Task::perform(
+        async move {
+            let Some(file) = picker.await else {
+                return Ok(None);
+            };
+            let path = file.path().to_owned();
+            fs::write(&path, contents)
+                .map(|()| Some(path.clone()))
+                .map_err(|error| format!("could not write {}: {error}", path.display()))
+        },
+        Message::DiagnosticsExported,
+    )
+}
+
 pub(crate) fn show_about_dialog() -> Task<Message> {
     let version = env!("CARGO_PKG_VERSION").to_owned();
     Task::perform(
@@ -181,8 +365,31 @@ pub(crate) fn set_window_title_tooltip(_window_id: window::Id, _title: String) -
     Task::none()
 }
 
-pub(crate) fn open_player_window() -> (window::Id, Task<window::Id>) {
-    window::open(player_window_settings())
+/// Restores a window if minimized and gives it focus.
+pub(crate) fn raise_window(window_id: window::Id) -> Task<Message> {
+    window::minimize(window_id, false).chain(window::gain_focus(window_id))
+}
+
+pub(crate) fn open_player_window(size: Option<iced::Size>) -> (window::Id, Task<window::Id>) {
+    window::open(player_window_settings(size))
+}
+
+pub(crate) fn open_library_window() -> (window::Id, Task<window::Id>) {
+    window::open(window::Settings {
+        size: iced::Size::new(LIBRARY_WINDOW_WIDTH, LIBRARY_WINDOW_HEIGHT),
+        min_size: Some(iced::Size::new(420.0, 260.0)),
+        icon: load_window_icon(),
+        ..window::Settings::default()
+    })
+}
+
+pub(crate) fn open_preferences_window() -> (window::Id, Task<window::Id>) {
+    window::open(window::Settings {
+        size: iced::Size::new(PREFERENCES_WINDOW_WIDTH, PREFERENCES_WINDOW_HEIGHT),
+        resizable: false,
+        icon: load_window_icon(),
+        ..window::Settings::default()
+    })
 }
 
 #[cfg(target_os = "macos")]
@@ -376,20 +583,62 @@ pub(crate) fn set_macos_app_icon_from_bytes() -> Result<(), String> {
     Ok(())
 }
 
-fn player_window_settings() -> window::Settings {
+/// Settings for a player window, opened at `size` or else at the compact size,
+/// which is also the smallest it can be resized to.
+fn player_window_settings(size: Option<iced::Size>) -> window::Settings {
+    let compact = compact_player_size();
+
+    window::Settings {
+        size: size.map_or(compact, |size| size.max(compact)),
+        min_size: Some(compact),
+        icon: load_window_icon(),
+        ..window::Settings::default()
+    }
+}
+
+fn compact_player_size() -> iced::Size {
     let window_height = if cfg!(target_os = "macos") {
         WINDOW_HEIGHT
     } else {
-        WINDOW_HEIGHT + 26.0
+        WINDOW_HEIGHT + 26.0 + MENU_BAR_HEIGHT
     };
+    iced::Size::new(WINDOW_WIDTH, window_height)
+}
 
-    window::Settings {
-        size: iced::Size::new(WINDOW_WIDTH, window_height),
-        min_size: Some(iced::Size::new(WINDOW_WIDTH, window_height)),
-        max_size: Some(iced::Size::new(WINDOW_WIDTH, window_height)),
-        resizable: false,
-        icon: load_window_icon(),
-        ..window::Settings::default()
+/// Turns a player window into the mini player: borderless, fixed at the mini
+/// size and kept above other windows, at `position` when it has one.
+pub(crate) fn enter_mini_player(id: window::Id, position: Option<Point>) -> Task<Message> {
+    let task = window::toggle_decorations(id)
+        .chain(window::set_resizable(id, false))
+        .chain(window::set_min_size(id, Some(MINI_PLAYER_SIZE)))
+        .chain(window::resize(id, MINI_PLAYER_SIZE))
+        .chain(window::set_level(id, window::Level::AlwaysOnTop));
+
+    match position {
+        Some(position) => task.chain(window::move_to(id, position)),
+        None => task,
+    }
+}
+
+/// Restores a mini player to a normal window of `size`, back at `position`.
+pub(crate) fn leave_mini_player(
+    id: window::Id,
+    size: Option<iced::Size>,
+    position: Option<Point>,
+) -> Task<Message> {
+    let compact = compact_player_size();
+    let task = window::set_level(id, window::Level::Normal)
+        .chain(window::toggle_decorations(id))
+        .chain(window::set_min_size(id, Some(compact)))
+        .chain(window::set_resizable(id, true))
+        .chain(window::resize(
+            id,
+            size.map_or(compact, |size| size.max(compact)),
+        ));
+
+    match position {
+        Some(position) => task.chain(window::move_to(id, position)),
+        None => task,
     }
 }
 
@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+
+use crate::playback::PlaybackLoadError;
+
+/// A failure to open or play a file, as the window's error panel shows it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ErrorReport {
+    pub(crate) error: PlaybackLoadError,
+    /// The file involved, when the failure is tied to one.
+    pub(crate) path: Option<PathBuf>,
+}
+
+impl ErrorReport {
+    pub(crate) fn new(error: PlaybackLoadError, path: Option<PathBuf>) -> Self {
+        Self { error, path }
+    }
+
+    /// The report as plain text, for pasting into a bug report.
+    pub(crate) fn details_text(&self) -> String {
+        let mut lines = vec![
+            format!("Proteus Player {}", env!("CARGO_PKG_VERSION")),
+            format!(
+                "Platform: {} {}",
+                std::env::consts::OS,
+                std::env::consts::ARCH
+            ),
+            format!("Error: {}", self.error),
+            format!("Kind: {}", self.error.kind()),
+        ];
+        if let Some(path) = &self.path {
+            lines.push(format!("File: {}", path.display()));
+        }
+        if let Some(detail) = self.error.detail() {
+            lines.push(format!("Details: {detail}"));
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn details_name_the_file_and_what_the_decoder_said() {
+        let report = ErrorReport::new(
+            PlaybackLoadError::DecoderFailure {
+                file_name: "Etude.mka".to_owned(),
+                detail: "track 2 unsupported audio codec: alac".to_owned(),
+            },
+            Some(PathBuf::from("/music/Etude.mka")),
+        );
+
+        let details = report.details_text();
+        assert!(details.starts_with("Proteus Player "));
+        assert!(details.contains("Error: The audio in Etude.mka could not be decoded\n"));
+        assert!(details.contains("Kind: decoder failure\n"));
+        assert!(details.contains("File: /music/Etude.mka\n"));
+        assert!(details.ends_with("Details: track 2 unsupported audio codec: alac"));
+    }
+}
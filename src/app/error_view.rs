@@ -0,0 +1,66 @@
+use iced::widget::{Column, button, column, container, row, scrollable, text};
+use iced::{Alignment, Element, Length, window};
+
+use crate::app::error_report::ErrorReport;
+use crate::app::messages::Message;
+use crate::app::state::ProteusApp;
+use crate::app::styles::background_style;
+
+/// Why the window's file could not be opened or played, what to try about
+/// it, and a way to copy the details into a bug report.
+pub(crate) fn error_overlay<'a>(
+    state: &'a ProteusApp,
+    report: &'a ErrorReport,
+    window_id: window::Id,
+) -> Element<'a, Message> {
+    let palette = state.palette;
+
+    let mut body = Column::new().spacing(6);
+    if let Some(path) = &report.path {
+        body = body.push(
+            text(path.display().to_string())
+                .size(11)
+                .color(palette.accent_text),
+        );
+    }
+    if let Some(detail) = report.error.detail() {
+        body = body.push(text(detail).size(11));
+    }
+
+    let suggestions = report.error.suggestions();
+    if !suggestions.is_empty() {
+        body = body.push(text("Try:").size(11));
+        body = suggestions.iter().fold(body, |body, suggestion| {
+            body.push(
+                row![text("•").size(11), text(*suggestion).size(11)]
+                    .spacing(6)
+                    .padding([0, 8]),
+            )
+        });
+    }
+
+    let actions = row![
+        button(text("Copy Details").size(11))
+            .style(button::secondary)
+            .on_press(Message::CopyErrorDetailsPressed(window_id)),
+        button(text("Dismiss").size(11)).on_press(Message::ErrorDismissed(window_id)),
+    ]
+    .spacing(8)
+    .align_y(Alignment::Center);
+
+    container(
+        column![
+            text(report.error.to_string())
+                .size(13)
+                .color(palette.error_text),
+            scrollable(body).height(Length::Fill),
+            actions,
+        ]
+        .spacing(8),
+    )
+    .padding([8, 12])
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .style(background_style(palette))
+    .into()
+}
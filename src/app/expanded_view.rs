@@ -0,0 +1,208 @@
+use iced::widget::{Column, button, column, container, image, row, scrollable, slider, text};
+use iced::{Alignment, ContentFit, Element, Length, window};
+
+use crate::app::messages::Message;
+use crate::app::state::{PlayerWindowState, ProteusApp};
+use crate::app::styles::{library_row_style, volume_slider_style};
+use crate::app::theme::Palette;
+use crate::app::widgets::{level_graph, slider_with_handle_cursor};
+
+const ARTWORK_SIZE: f32 = 160.0;
+const QUEUE_WIDTH: f32 = 220.0;
+const WAVEFORM_HEIGHT: f32 = 64.0;
+const PART_LABEL_WIDTH: f32 = 56.0;
+
+/// The player laid out for a large window: artwork beside the waveform and
+/// part mixer, the folder's queue, and the transport underneath.
+pub(crate) fn expanded_view<'a>(
+    state: &'a ProteusApp,
+    window: &'a PlayerWindowState,
+    window_id: window::Id,
+    transport: Column<'a, Message>,
+) -> Element<'a, Message> {
+    let palette = state.palette;
+
+    let details = column![
+        artwork(state, window),
+        text(window.window_title.clone()).size(14),
+        text(
+            window
+                .playback
+                .combination_code()
+                .map(|code| format!("Combination {code}"))
+                .unwrap_or_default()
+        )
+        .size(11)
+        .color(palette.accent_text),
+    ]
+    .spacing(6)
+    .width(Length::Fixed(ARTWORK_SIZE));
+
+    let mixer = column![
+        section_label(palette, "Waveform"),
+        waveform(palette, window),
+        section_label(palette, "Parts"),
+        scrollable(part_mixer(palette, window, window_id)).height(Length::Fill),
+    ]
+    .spacing(6)
+    .width(Length::Fill);
+
+    let queue = column![
+        section_label(palette, "Queue"),
+        scrollable(queue_list(palette, window, window_id)).height(Length::Fill),
+    ]
+    .spacing(6)
+    .width(Length::Fixed(QUEUE_WIDTH));
+
+    column![
+        row![details, mixer, queue].spacing(16).height(Length::Fill),
+        container(transport).center_x(Length::Fill),
+    ]
+    .spacing(12)
+    .padding(16)
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .into()
+}
+
+fn section_label<'a>(palette: Palette, label: &'a str) -> Element<'a, Message> {
+    text(label).size(11).color(palette.accent_text).into()
+}
+
+/// Cover art found next to the file, or the app icon when there is none.
+fn artwork<'a>(state: &'a ProteusApp, window: &'a PlayerWindowState) -> Element<'a, Message> {
+    let handle = window
+        .folder
+        .as_ref()
+        .and_then(|folder| folder.artwork.as_ref())
+        .map_or_else(
+            || state.icons.artwork_placeholder.clone(),
+            image::Handle::from_path,
+        );
+
+    image(handle)
+        .width(Length::Fixed(ARTWORK_SIZE))
+        .height(Length::Fixed(ARTWORK_SIZE))
+        .content_fit(ContentFit::Cover)
+        .into()
+}
+
+/// The whole file's levels as mixed in the part mixer, with the playhead.
+fn waveform<'a>(palette: Palette, window: &'a PlayerWindowState) -> Element<'a, Message> {
+    let graph = match &window.waveform {
+        Some(Ok(waveform)) => {
+            let duration = window.duration.unwrap_or(waveform.duration);
+            let playhead = if duration > 0.0 {
+                (window.current_time / duration).clamp(0.0, 1.0) as f32
+            } else {
+                0.0
+            };
+            let levels = waveform.mix(&window.playback.combination(), |slot| {
+                window.part_level(slot)
+            });
+            level_graph(levels, Some(playhead), palette)
+        }
+        Some(Err(_)) => placeholder(palette, "Waveform unavailable"),
+        None if window.is_empty() => placeholder(palette, "Nothing loaded"),
+        None => placeholder(palette, "Measuring levels…"),
+    };
+
+    container(graph)
+        .width(Length::Fill)
+        .height(Length::Fixed(WAVEFORM_HEIGHT))
+        .into()
+}
+
+fn placeholder<'a>(palette: Palette, label: &'a str) -> Element<'a, Message> {
+    container(text(label).size(12).color(palette.accent_text))
+        .center(Length::Fill)
+        .into()
+}
+
+/// A level slider for each part of the playing combination.
+fn part_mixer<'a>(
+    palette: Palette,
+    window: &'a PlayerWindowState,
+    window_id: window::Id,
+) -> Column<'a, Message> {
+    let ids = window.playback.combination();
+    if ids.is_empty() {
+        return column![text("No parts loaded").size(12).color(palette.accent_text)];
+    }
+
+    ids.into_iter()
+        .enumerate()
+        .fold(Column::new().spacing(4), |parts, (slot, id)| {
+            let percent = window.part_level(slot) * 100.0;
+            parts.push(
+                row![
+                    text(format!("Part {}", slot + 1))
+                        .size(12)
+                        .width(Length::Fixed(PART_LABEL_WIDTH)),
+                    text(id)
+                        .size(11)
+                        .color(palette.accent_text)
+                        .width(Length::Fixed(PART_LABEL_WIDTH)),
+                    slider_with_handle_cursor(
+                        slider(0.0..=100.0, percent, move |percent| {
+                            Message::PartLevelChanged {
+                                window_id,
+                                slot,
+                                level: percent / 100.0,
+                            }
+                        })
+                        .step(1.0)
+                        .width(Length::Fill)
+                        .style(volume_slider_style(palette)),
+                        f64::from(percent),
+                        0.0..=100.0,
+                        5.0,
+                    ),
+                ]
+                .align_y(Alignment::Center)
+                .spacing(8),
+            )
+        })
+}
+
+/// The other playable files in the loaded file's folder.
+fn queue_list<'a>(
+    palette: Palette,
+    window: &'a PlayerWindowState,
+    window_id: window::Id,
+) -> Column<'a, Message> {
+    let Some(folder) = &window.folder else {
+        let placeholder = if window.is_empty() {
+            "Nothing loaded"
+        } else {
+            "Reading folder…"
+        };
+        return column![text(placeholder).size(12).color(palette.accent_text)];
+    };
+
+    let current = window.playback.current_path();
+    folder
+        .queue
+        .iter()
+        .fold(Column::new().spacing(1), |queue, path| {
+            let selected = current == Some(path.as_path());
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            queue.push(
+                container(
+                    button(text(name).size(12).width(Length::Fill))
+                        .style(button::text)
+                        .padding([2, 6])
+                        .width(Length::Fill)
+                        .on_press_maybe((!selected).then(|| Message::QueueEntryPressed {
+                            window_id,
+                            path: path.clone(),
+                        })),
+                )
+                .style(library_row_style(palette, selected)),
+            )
+        })
+}
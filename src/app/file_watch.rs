@@ -0,0 +1,162 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::SystemTime;
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+/// What a file looked like on disk when it was last checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl FileStamp {
+    /// The file's current stamp, or `None` while it does not exist, as
+    /// happens mid-export when an editor replaces the file.
+    pub(crate) fn read(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        })
+    }
+}
+
+/// Notices when a loaded file is rewritten on disk. A change is only
+/// reported once the file has held still between two checks, so a file that
+/// is still being exported is not reloaded half written.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct FileWatch {
+    loaded: Option<FileStamp>,
+    changed: Option<FileStamp>,
+    /// Set by a change event, and kept until the file settles.
+    pending: bool,
+}
+
+impl FileWatch {
+    pub(crate) fn loaded(stamp: Option<FileStamp>) -> Self {
+        Self {
+            loaded: stamp,
+            changed: None,
+            pending: false,
+        }
+    }
+
+    /// Notes that the file system reported a change, so the file is checked
+    /// until it settles.
+    pub(crate) fn mark_changed(&mut self) {
+        self.pending = true;
+    }
+
+    pub(crate) fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    /// Compares the file's current stamp with the loaded one, returning
+    /// whether it should be reloaded. A change is reported once, so a file
+    /// that fails to reload is not retried until it changes again.
+    pub(crate) fn check(&mut self, current: Option<FileStamp>) -> bool {
+        let Some(current) = current else {
+            self.changed = None;
+            return false;
+        };
+        if self.loaded.is_none_or(|loaded| loaded == current) {
+            self.changed = None;
+            self.pending = false;
+            return false;
+        }
+        if self.changed != Some(current) {
+            self.changed = Some(current);
+            return false;
+        }
+
+        self.loaded = Some(current);
+        self.changed = None;
+        self.pending = false;
+        true
+    }
+}
+
+/// Watches the folders of loaded files through the platform's file events
+/// (inotify, FSEvents or ReadDirectoryChangesW). Folders are watched rather
+/// than files because an exporter that replaces a file would otherwise leave
+/// the watch on the old one.
+pub(crate) struct FolderWatcher {
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    folders: HashSet<PathBuf>,
+}
+
+impl FolderWatcher {
+    pub(crate) fn new() -> Result<Self, String> {
+        let (sender, events) = mpsc::channel();
+        let watcher = notify::recommended_watcher(sender)
+            .map_err(|error| format!("could not watch for file changes: {error}"))?;
+        Ok(Self {
+            watcher,
+            events,
+            folders: HashSet::new(),
+        })
+    }
+
+    /// Watches exactly `folders`, dropping the watches no longer needed.
+    pub(crate) fn watch_folders(&mut self, folders: HashSet<PathBuf>) {
+        for folder in self.folders.difference(&folders) {
+            let _ = self.watcher.unwatch(folder);
+        }
+        for folder in folders.difference(&self.folders) {
+            if let Err(error) = self.watcher.watch(folder, RecursiveMode::NonRecursive) {
+                log::warn!("could not watch folder path={folder:?} error={error}");
+            }
+        }
+        self.folders = folders;
+    }
+
+    /// Paths created, written, renamed or removed since the last call.
+    pub(crate) fn changed_paths(&self) -> HashSet<PathBuf> {
+        self.events
+            .try_iter()
+            .filter_map(Result::ok)
+            .filter(|event| !event.kind.is_access())
+            .flat_map(|event| event.paths)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn stamp(seconds: u64, len: u64) -> Option<FileStamp> {
+        Some(FileStamp {
+            modified: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)),
+            len,
+        })
+    }
+
+    #[test]
+    fn changes_are_reported_once_the_file_holds_still() {
+        let mut watch = FileWatch::loaded(stamp(1, 100));
+        watch.mark_changed();
+        assert!(!watch.check(stamp(1, 100)));
+        assert!(!watch.is_pending());
+
+        // Removed and rewritten in pieces by the exporter.
+        watch.mark_changed();
+        assert!(!watch.check(None));
+        assert!(!watch.check(stamp(2, 40)));
+        assert!(!watch.check(stamp(3, 120)));
+        assert!(watch.is_pending());
+        assert!(watch.check(stamp(3, 120)));
+        assert!(!watch.is_pending());
+
+        // Reported once, even if the reload fails.
+        assert!(!watch.check(stamp(3, 120)));
+        assert!(!watch.check(stamp(3, 120)));
+    }
+}
@@ -1,35 +1,67 @@
-use iced::keyboard::{Key, Modifiers, key::Named};
+use iced::keyboard::{Key, Modifiers};
 use iced::window;
 
+use crate::app::keybindings::{Action, KeyChord, Keybindings};
 use crate::app::messages::Message;
 
 pub(crate) fn handle_key_press(
+    keybindings: &Keybindings,
     window_id: window::Id,
-    key: Key,
+    key: &Key,
     modifiers: Modifiers,
 ) -> Option<Message> {
-    match key {
-        Key::Named(Named::Space) => Some(Message::PlayPauseShortcut(window_id)),
-        Key::Named(Named::ArrowRight) => Some(Message::SeekByShortcut {
+    let chord = KeyChord::from_key_press(key, modifiers)?;
+    keybindings
+        .action_for(&chord)
+        .map(|action| action_message(action, window_id))
+}
+
+/// The message that performs `action` in a window.
+pub(crate) fn action_message(action: Action, window_id: window::Id) -> Message {
+    match action {
+        Action::PlayPause => Message::PlayPauseShortcut(window_id),
+        Action::SeekForward => Message::SeekStepShortcut {
+            window_id,
+            forward: true,
+        },
+        Action::SeekBackward => Message::SeekStepShortcut {
+            window_id,
+            forward: false,
+        },
+        Action::VolumeUp => Message::VolumeStepShortcut {
             window_id,
-            offset: 5.0,
-        }),
-        Key::Named(Named::ArrowLeft) => Some(Message::SeekByShortcut {
+            up: true,
+        },
+        Action::VolumeDown => Message::VolumeStepShortcut {
             window_id,
-            offset: -5.0,
-        }),
-        Key::Character(value) if modifiers.command() => {
-            let value = value.to_lowercase();
-            match value.as_str() {
-                "n" => Some(Message::NewWindowShortcut(window_id)),
-                "o" => Some(Message::OpenShortcut(window_id)),
-                "w" => Some(Message::CloseWindowShortcut(window_id)),
-                "+" | "=" => Some(Message::ZoomInShortcut(window_id)),
-                "-" => Some(Message::ZoomOutShortcut(window_id)),
-                _ => None,
-            }
-        }
-        _ => None,
+            up: false,
+        },
+        Action::Mute => Message::MuteShortcut(window_id),
+        Action::ToggleLoop => Message::LoopShortcut(window_id),
+        Action::Shuffle => Message::ShufflePressed(window_id),
+        Action::Reset => Message::ResetPressed(window_id),
+        Action::Stop => Message::StopShortcut(window_id),
+        Action::Restart => Message::RestartShortcut(window_id),
+        Action::NextFile => Message::SiblingFileShortcut {
+            window_id,
+            forward: true,
+        },
+        Action::PreviousFile => Message::SiblingFileShortcut {
+            window_id,
+            forward: false,
+        },
+        Action::NewWindow => Message::NewWindowShortcut(window_id),
+        Action::Open => Message::OpenShortcut(window_id),
+        Action::CloseWindow => Message::CloseWindowShortcut(window_id),
+        Action::ShowLibrary => Message::ShowLibrary,
+        Action::ShowPreferences => Message::ShowPreferences,
+        Action::ZoomIn => Message::ZoomInShortcut(window_id),
+        Action::ZoomOut => Message::ZoomOutShortcut(window_id),
+        Action::MiniPlayer => Message::ToggleMiniPlayer(window_id),
+        Action::Visualizer => Message::ToggleVisualizer(window_id),
+        Action::ShowShortcuts => Message::ToggleShortcuts(window_id),
+        Action::ShowNotifications => Message::ToggleNotifications(window_id),
+        Action::CommandPalette => Message::ToggleCommandPalette(window_id),
     }
 }
 
@@ -39,3 +71,23 @@ pub(crate) fn format_time(time: f64) -> String {
     let seconds = safe_time.round() as i64 % 60;
     format!("{minutes:02}:{seconds:02}")
 }
+
+/// Converts days since 1970-01-01 to a proleptic Gregorian date, after
+/// Howard Hinnant's `civil_from_days`.
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -1,3 +1,4 @@
+use iced::widget::image;
 use iced::widget::svg::Handle;
 
 pub(crate) struct IconSet {
@@ -5,6 +6,8 @@ pub(crate) struct IconSet {
     pub(crate) pause: Handle,
     pub(crate) reset: Handle,
     pub(crate) shuffle: Handle,
+    /// Shown in place of artwork when a file's folder has none.
+    pub(crate) artwork_placeholder: image::Handle,
     volume0: Handle,
     volume1: Handle,
     volume2: Handle,
@@ -18,6 +21,9 @@ impl IconSet {
             pause: Handle::from_memory(include_bytes!("../../assets/icons/pause.svg")),
             reset: Handle::from_memory(include_bytes!("../../assets/icons/reset.svg")),
             shuffle: Handle::from_memory(include_bytes!("../../assets/icons/shuffle.svg")),
+            artwork_placeholder: image::Handle::from_bytes(
+                include_bytes!("../../assets/app-icon/128x128.png").as_slice(),
+            ),
             volume0: Handle::from_memory(include_bytes!("../../assets/icons/volume0.svg")),
             volume1: Handle::from_memory(include_bytes!("../../assets/icons/volume1.svg")),
             volume2: Handle::from_memory(include_bytes!("../../assets/icons/volume2.svg")),
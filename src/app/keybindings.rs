@@ -0,0 +1,633 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use iced::keyboard::{Key, Modifiers, key::Named};
+use serde::{Deserialize, Serialize};
+
+use crate::native_menu::{self, MenuAction};
+
+/// Everything that can be bound to a key chord.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Action {
+    PlayPause,
+    SeekForward,
+    SeekBackward,
+    VolumeUp,
+    VolumeDown,
+    Mute,
+    ToggleLoop,
+    Shuffle,
+    Reset,
+    Stop,
+    Restart,
+    NextFile,
+    PreviousFile,
+    NewWindow,
+    Open,
+    CloseWindow,
+    ShowLibrary,
+    ShowPreferences,
+    ZoomIn,
+    ZoomOut,
+    MiniPlayer,
+    Visualizer,
+    ShowShortcuts,
+    ShowNotifications,
+    CommandPalette,
+}
+
+impl Action {
+    pub(crate) const ALL: [Self; 25] = [
+        Self::PlayPause,
+        Self::SeekForward,
+        Self::SeekBackward,
+        Self::VolumeUp,
+        Self::VolumeDown,
+        Self::Mute,
+        Self::ToggleLoop,
+        Self::Shuffle,
+        Self::Reset,
+        Self::Stop,
+        Self::Restart,
+        Self::NextFile,
+        Self::PreviousFile,
+        Self::NewWindow,
+        Self::Open,
+        Self::CloseWindow,
+        Self::ShowLibrary,
+        Self::ShowPreferences,
+        Self::ZoomIn,
+        Self::ZoomOut,
+        Self::MiniPlayer,
+        Self::Visualizer,
+        Self::ShowShortcuts,
+        Self::ShowNotifications,
+        Self::CommandPalette,
+    ];
+
+    /// The name used for the action in `keybindings.toml`.
+    pub(crate) fn id(self) -> &'static str {
+        match self {
+            Self::PlayPause => "play-pause",
+            Self::SeekForward => "seek-forward",
+            Self::SeekBackward => "seek-backward",
+            Self::VolumeUp => "volume-up",
+            Self::VolumeDown => "volume-down",
+            Self::Mute => "mute",
+            Self::ToggleLoop => "toggle-loop",
+            Self::Shuffle => "shuffle",
+            Self::Reset => "reset",
+            Self::Stop => "stop",
+            Self::Restart => "restart",
+            Self::NextFile => "next-file",
+            Self::PreviousFile => "previous-file",
+            Self::NewWindow => "new-window",
+            Self::Open => "open",
+            Self::CloseWindow => "close-window",
+            Self::ShowLibrary => "show-library",
+            Self::ShowPreferences => "show-preferences",
+            Self::ZoomIn => "zoom-in",
+            Self::ZoomOut => "zoom-out",
+            Self::MiniPlayer => "mini-player",
+            Self::Visualizer => "visualizer",
+            Self::ShowShortcuts => "show-shortcuts",
+            Self::ShowNotifications => "show-notifications",
+            Self::CommandPalette => "command-palette",
+        }
+    }
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::PlayPause => "Play / Pause",
+            Self::SeekForward => "Seek Forward",
+            Self::SeekBackward => "Seek Backward",
+            Self::VolumeUp => "Volume Up",
+            Self::VolumeDown => "Volume Down",
+            Self::Mute => "Mute",
+            Self::ToggleLoop => "Loop",
+            Self::Shuffle => "Shuffle",
+            Self::Reset => "Reset",
+            Self::Stop => "Stop",
+            Self::Restart => "Restart",
+            Self::NextFile => "Next File",
+            Self::PreviousFile => "Previous File",
+            Self::NewWindow => "New Window",
+            Self::Open => "Open…",
+            Self::CloseWindow => "Close Window",
+            Self::ShowLibrary => "Library",
+            Self::ShowPreferences => "Settings…",
+            Self::ZoomIn => "Zoom In",
+            Self::ZoomOut => "Zoom Out",
+            Self::MiniPlayer => "Mini Player",
+            Self::Visualizer => "Visualizer",
+            Self::ShowShortcuts => "Keyboard Shortcuts",
+            Self::ShowNotifications => "Notifications",
+            Self::CommandPalette => "Command Palette",
+        }
+    }
+
+    /// Whether a native menu item runs this action and shows its shortcut.
+    pub(crate) fn in_menu(self) -> bool {
+        !matches!(self, Self::Reset | Self::PreviousFile)
+    }
+
+    fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.id() == id)
+    }
+
+    fn default_chords(self) -> &'static [&'static str] {
+        match self {
+            Self::PlayPause => &["Space", "CmdOrCtrl+P"],
+            Self::SeekForward => &["Right", "Alt+Right"],
+            Self::SeekBackward => &["Left", "Alt+Left"],
+            Self::VolumeUp => &["Up", "CmdOrCtrl+Up"],
+            Self::VolumeDown => &["Down", "CmdOrCtrl+Down"],
+            Self::Mute => &["M", "CmdOrCtrl+Alt+Down"],
+            Self::ToggleLoop => &["L", "CmdOrCtrl+Shift+L"],
+            Self::Shuffle => &["S", "CmdOrCtrl+Shift+S"],
+            Self::Reset => &["Home"],
+            Self::Stop => &["CmdOrCtrl+."],
+            Self::Restart => &["CmdOrCtrl+R"],
+            Self::NextFile => &["CmdOrCtrl+Right"],
+            Self::PreviousFile => &["CmdOrCtrl+Left"],
+            Self::NewWindow => &["CmdOrCtrl+N"],
+            Self::Open => &["CmdOrCtrl+O"],
+            Self::CloseWindow => &["CmdOrCtrl+W"],
+            Self::ShowLibrary => &["CmdOrCtrl+L"],
+            Self::ShowPreferences => &["CmdOrCtrl+,"],
+            Self::ZoomIn => &["CmdOrCtrl+=", "CmdOrCtrl+Shift+=", "CmdOrCtrl+Plus"],
+            Self::ZoomOut => &["CmdOrCtrl+-"],
+            Self::MiniPlayer => &["CmdOrCtrl+Shift+M"],
+            Self::Visualizer => &["F11", "CmdOrCtrl+Shift+F"],
+            Self::ShowShortcuts => &["F1", "Shift+/"],
+            Self::ShowNotifications => &["CmdOrCtrl+Shift+N"],
+            Self::CommandPalette => &["CmdOrCtrl+Shift+P"],
+        }
+    }
+
+    /// The table action behind a menu item.
+    pub(crate) fn for_menu(action: &MenuAction) -> Option<Self> {
+        match action {
+            MenuAction::NewWindow => Some(Self::NewWindow),
+            MenuAction::Open => Some(Self::Open),
+            MenuAction::ShowLibrary => Some(Self::ShowLibrary),
+            MenuAction::ShowPreferences => Some(Self::ShowPreferences),
+            MenuAction::ZoomIn => Some(Self::ZoomIn),
+            MenuAction::ZoomOut => Some(Self::ZoomOut),
+            MenuAction::ToggleMiniPlayer => Some(Self::MiniPlayer),
+            MenuAction::ToggleVisualizer => Some(Self::Visualizer),
+            MenuAction::ShowShortcuts => Some(Self::ShowShortcuts),
+            MenuAction::ShowNotifications => Some(Self::ShowNotifications),
+            MenuAction::ShowCommandPalette => Some(Self::CommandPalette),
+            MenuAction::CloseWindow => Some(Self::CloseWindow),
+            MenuAction::PlayPause => Some(Self::PlayPause),
+            MenuAction::Stop => Some(Self::Stop),
+            MenuAction::Restart => Some(Self::Restart),
+            MenuAction::NewCombination => Some(Self::Shuffle),
+            MenuAction::NextFile => Some(Self::NextFile),
+            MenuAction::SeekForward => Some(Self::SeekForward),
+            MenuAction::SeekBackward => Some(Self::SeekBackward),
+            MenuAction::VolumeUp => Some(Self::VolumeUp),
+            MenuAction::VolumeDown => Some(Self::VolumeDown),
+            MenuAction::Mute => Some(Self::Mute),
+            MenuAction::ToggleLoop => Some(Self::ToggleLoop),
+            MenuAction::About
+            | MenuAction::ShowDiagnostics
+            | MenuAction::RevealLogs
+            | MenuAction::OpenRecent(_)
+            | MenuAction::Minimize
+            | MenuAction::Quit
+            | MenuAction::BringAllToFront
+            | MenuAction::PauseAll
+            | MenuAction::CloseAll
+            | MenuAction::FocusWindow(_) => None,
+        }
+    }
+}
+
+/// Named keys that can be bound, with the name used in the config file.
+const NAMED_KEYS: &[(Named, &str)] = &[
+    (Named::Space, "Space"),
+    (Named::ArrowLeft, "Left"),
+    (Named::ArrowRight, "Right"),
+    (Named::ArrowUp, "Up"),
+    (Named::ArrowDown, "Down"),
+    (Named::Home, "Home"),
+    (Named::End, "End"),
+    (Named::PageUp, "PageUp"),
+    (Named::PageDown, "PageDown"),
+    (Named::Enter, "Enter"),
+    (Named::Tab, "Tab"),
+    (Named::Escape, "Escape"),
+    (Named::Backspace, "Backspace"),
+    (Named::Delete, "Delete"),
+    (Named::Insert, "Insert"),
+    (Named::F1, "F1"),
+    (Named::F2, "F2"),
+    (Named::F3, "F3"),
+    (Named::F4, "F4"),
+    (Named::F5, "F5"),
+    (Named::F6, "F6"),
+    (Named::F7, "F7"),
+    (Named::F8, "F8"),
+    (Named::F9, "F9"),
+    (Named::F10, "F10"),
+    (Named::F11, "F11"),
+    (Named::F12, "F12"),
+    (Named::MediaPlayPause, "MediaPlayPause"),
+    (Named::MediaTrackNext, "MediaTrackNext"),
+    (Named::MediaTrackPrevious, "MediaTrackPrevious"),
+    (Named::AudioVolumeUp, "VolumeUp"),
+    (Named::AudioVolumeDown, "VolumeDown"),
+    (Named::AudioVolumeMute, "VolumeMute"),
+];
+
+/// A key plus modifiers, written like `CmdOrCtrl+Shift+N`.
+///
+/// `CmdOrCtrl` is Command on macOS and Control elsewhere. Character keys are
+/// matched as typed without modifiers on the active layout, so bindings work
+/// on non-US layouts by naming the character the key produces.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub(crate) struct KeyChord {
+    command: bool,
+    alt: bool,
+    shift: bool,
+    /// A name from `NAMED_KEYS`, `Plus`, or a single lowercase character.
+    key: String,
+}
+
+impl KeyChord {
+    /// Builds the chord for a key press, or `None` for bare modifier keys and
+    /// keys that cannot be bound.
+    pub(crate) fn from_key_press(key: &Key, modifiers: Modifiers) -> Option<Self> {
+        let key = match key {
+            Key::Named(named) => NAMED_KEYS
+                .iter()
+                .find(|(candidate, _)| candidate == named)
+                .map(|(_, name)| (*name).to_owned())?,
+            Key::Character(value) => character_key(value)?,
+            Key::Unidentified => return None,
+        };
+
+        Some(Self {
+            command: modifiers.command(),
+            alt: modifiers.alt(),
+            shift: modifiers.shift(),
+            key,
+        })
+    }
+
+    pub(crate) fn is_escape(&self) -> bool {
+        !self.command && !self.alt && !self.shift && self.key == "Escape"
+    }
+
+    /// Native menu accelerators fire before the window sees the key, so only
+    /// chords that cannot type or move the cursor in a text field are used.
+    fn suits_menu(&self) -> bool {
+        self.command
+            || self.alt
+            || self
+                .key
+                .strip_prefix('F')
+                .is_some_and(|number| number.parse::<u8>().is_ok())
+    }
+
+    /// A chord that suits a menu but that menu items cannot show, so it only
+    /// works while a window has focus.
+    pub(crate) fn missing_from_menu(&self) -> bool {
+        self.suits_menu() && !native_menu::accelerator_supported(&self.accelerator())
+    }
+
+    /// The chord in the format `muda` parses for menu accelerators.
+    pub(crate) fn accelerator(&self) -> String {
+        self.join("CmdOrCtrl", "Alt", &self.key)
+    }
+
+    fn join(&self, command: &str, alt: &str, key: &str) -> String {
+        let mut parts = Vec::with_capacity(4);
+        if self.command {
+            parts.push(command);
+        }
+        if self.alt {
+            parts.push(alt);
+        }
+        if self.shift {
+            parts.push("Shift");
+        }
+        parts.push(key);
+        parts.join("+")
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        // A trailing `+` is the plus key itself, as in `CmdOrCtrl++`.
+        let (modifiers, key) = match value.strip_suffix("++") {
+            Some(modifiers) => (modifiers, "Plus"),
+            None if value == "+" => ("", "Plus"),
+            None => value.rsplit_once('+').unwrap_or(("", value)),
+        };
+
+        let mut chord = Self {
+            command: false,
+            alt: false,
+            shift: false,
+            key: String::new(),
+        };
+
+        for modifier in modifiers.split('+').filter(|modifier| !modifier.is_empty()) {
+            match modifier.to_ascii_lowercase().as_str() {
+                "cmdorctrl" | "commandorcontrol" | "cmd" | "command" | "ctrl" | "control" => {
+                    chord.command = true;
+                }
+                "alt" | "option" => chord.alt = true,
+                "shift" => chord.shift = true,
+                _ => return Err(format!("unknown modifier \"{modifier}\" in \"{value}\"")),
+            }
+        }
+
+        chord.key = if key.eq_ignore_ascii_case("plus") {
+            "Plus".to_owned()
+        } else if let Some((_, name)) = NAMED_KEYS
+            .iter()
+            .find(|(_, name)| name.eq_ignore_ascii_case(key))
+        {
+            (*name).to_owned()
+        } else {
+            character_key(key).ok_or_else(|| format!("unknown key \"{key}\" in \"{value}\""))?
+        };
+
+        Ok(chord)
+    }
+}
+
+impl TryFrom<String> for KeyChord {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<KeyChord> for String {
+    fn from(chord: KeyChord) -> Self {
+        chord.accelerator()
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (command, alt) = if cfg!(target_os = "macos") {
+            ("Cmd", "Option")
+        } else {
+            ("Ctrl", "Alt")
+        };
+        let key = match self.key.as_str() {
+            "Plus" => "+".to_owned(),
+            key if key.chars().count() == 1 => key.to_uppercase(),
+            key => key.to_owned(),
+        };
+        f.write_str(&self.join(command, alt, &key))
+    }
+}
+
+fn character_key(value: &str) -> Option<String> {
+    let mut chars = value.chars();
+    let character = chars.next()?;
+    if chars.next().is_some() || character.is_whitespace() || character.is_control() {
+        return None;
+    }
+
+    Some(if character == '+' {
+        "Plus".to_owned()
+    } else {
+        character.to_lowercase().collect()
+    })
+}
+
+/// Two or more actions sharing a chord.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Conflict {
+    pub(crate) chord: KeyChord,
+    pub(crate) actions: Vec<Action>,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let actions = self
+            .actions
+            .iter()
+            .map(|action| action.label())
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "{} is bound to {actions}", self.chord)
+    }
+}
+
+/// The keybinding table read by both the key handler and the menus.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Keybindings {
+    /// Chords per action, in `Action::ALL` order.
+    bindings: Vec<(Action, Vec<KeyChord>)>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL
+                .into_iter()
+                .map(|action| (action, default_chords(action)))
+                .collect(),
+        }
+    }
+}
+
+impl Keybindings {
+    /// Applies the overrides from `keybindings.toml` on top of the defaults.
+    pub(crate) fn from_file(file: KeybindingsFile) -> Result<Self, String> {
+        let mut keybindings = Self::default();
+        for (id, chords) in file.bindings {
+            let action = Action::from_id(&id).ok_or_else(|| format!("unknown action \"{id}\""))?;
+            keybindings.set(action, chords);
+        }
+        Ok(keybindings)
+    }
+
+    /// Returns only the bindings that differ from the defaults.
+    pub(crate) fn to_file(&self) -> KeybindingsFile {
+        KeybindingsFile {
+            bindings: self
+                .bindings
+                .iter()
+                .filter(|(action, chords)| *chords != default_chords(*action))
+                .map(|(action, chords)| (action.id().to_owned(), chords.clone()))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn chords(&self, action: Action) -> &[KeyChord] {
+        self.bindings
+            .iter()
+            .find(|(candidate, _)| *candidate == action)
+            .map(|(_, chords)| chords.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Finds the action bound to a chord. When several actions share it, the
+    /// first in table order wins.
+    pub(crate) fn action_for(&self, chord: &KeyChord) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, chords)| chords.contains(chord))
+            .map(|(action, _)| *action)
+    }
+
+    /// The accelerator shown on a native menu item: the action's first chord
+    /// that suits a menu. Bare keys stay with the window's key handler.
+    pub(crate) fn menu_accelerator(&self, action: &MenuAction) -> Option<String> {
+        let action = Action::for_menu(action)?;
+        self.chords(action)
+            .iter()
+            .find(|chord| chord.suits_menu() && !chord.missing_from_menu())
+            .map(KeyChord::accelerator)
+    }
+
+    /// The same chord in display form, for the in-window menu bar.
+    pub(crate) fn menu_shortcut(&self, action: &MenuAction) -> Option<String> {
+        let action = Action::for_menu(action)?;
+        self.chords(action).first().map(ToString::to_string)
+    }
+
+    /// Binds an extra chord, refusing one already used by another action.
+    pub(crate) fn add(&mut self, action: Action, chord: KeyChord) -> Result<(), Conflict> {
+        match self.action_for(&chord) {
+            Some(existing) if existing == action => Ok(()),
+            Some(existing) => Err(Conflict {
+                chord,
+                actions: vec![existing],
+            }),
+            None => {
+                self.chords_mut(action).push(chord);
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) fn remove(&mut self, action: Action, chord: &KeyChord) {
+        self.chords_mut(action)
+            .retain(|candidate| candidate != chord);
+    }
+
+    pub(crate) fn reset(&mut self, action: Action) {
+        *self.chords_mut(action) = default_chords(action);
+    }
+
+    /// Lists every chord bound to more than one action.
+    pub(crate) fn conflicts(&self) -> Vec<Conflict> {
+        let mut conflicts: Vec<Conflict> = Vec::new();
+        for (action, chords) in &self.bindings {
+            for chord in chords {
+                match conflicts
+                    .iter_mut()
+                    .find(|conflict| &conflict.chord == chord)
+                {
+                    Some(conflict) => conflict.actions.push(*action),
+                    None => conflicts.push(Conflict {
+                        chord: chord.clone(),
+                        actions: vec![*action],
+                    }),
+                }
+            }
+        }
+        conflicts.retain(|conflict| conflict.actions.len() > 1);
+        conflicts
+    }
+
+    fn set(&mut self, action: Action, chords: Vec<KeyChord>) {
+        *self.chords_mut(action) = chords;
+    }
+
+    fn chords_mut(&mut self, action: Action) -> &mut Vec<KeyChord> {
+        let index = self
+            .bindings
+            .iter()
+            .position(|(candidate, _)| *candidate == action)
+            .expect("every action has a row in the keybinding table");
+        &mut self.bindings[index].1
+    }
+}
+
+fn default_chords(action: Action) -> Vec<KeyChord> {
+    action
+        .default_chords()
+        .iter()
+        .map(|chord| chord.parse().expect("default key chords are valid"))
+        .collect()
+}
+
+/// The on-disk form of the keybinding table: overrides keyed by action id.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct KeybindingsFile {
+    bindings: BTreeMap<String, Vec<KeyChord>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chords_round_trip_through_the_config_format() {
+        for value in [
+            "CmdOrCtrl+Shift+N",
+            "Space",
+            "CmdOrCtrl+Plus",
+            "Alt+F5",
+            "ü",
+        ] {
+            let chord: KeyChord = value.parse().expect("the chord should parse");
+            assert_eq!(chord.accelerator().parse::<KeyChord>(), Ok(chord));
+        }
+
+        assert_eq!("Ctrl++".parse::<KeyChord>(), "CmdOrCtrl+Plus".parse());
+        assert!("Hyper+K".parse::<KeyChord>().is_err());
+    }
+
+    #[test]
+    fn overrides_replace_defaults_and_report_conflicts() {
+        let mut file = KeybindingsFile::default();
+        file.bindings
+            .insert("zoom-in".to_owned(), vec!["CmdOrCtrl+I".parse().unwrap()]);
+        file.bindings
+            .insert("mute".to_owned(), vec!["S".parse().unwrap()]);
+
+        let keybindings = Keybindings::from_file(file).expect("the overrides should apply");
+        let zoom_in = "CmdOrCtrl+I".parse().unwrap();
+
+        assert_eq!(keybindings.action_for(&zoom_in), Some(Action::ZoomIn));
+        assert_eq!(
+            keybindings.menu_accelerator(&MenuAction::ZoomIn).as_deref(),
+            Some("CmdOrCtrl+i")
+        );
+        assert_eq!(keybindings.conflicts().len(), 1);
+        assert_eq!(keybindings.to_file().bindings.len(), 2);
+
+        let mut keybindings = Keybindings::default();
+        assert!(keybindings.conflicts().is_empty());
+        assert_eq!(
+            keybindings
+                .menu_accelerator(&MenuAction::PlayPause)
+                .as_deref(),
+            Some("CmdOrCtrl+p")
+        );
+        assert!(
+            keybindings
+                .add(Action::Mute, "Space".parse().unwrap())
+                .is_err()
+        );
+    }
+}
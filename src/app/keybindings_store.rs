@@ -0,0 +1,20 @@
+use std::path::PathBuf;
+
+use crate::app::keybindings::{Keybindings, KeybindingsFile};
+use crate::app::storage;
+
+const KEYBINDINGS_NAME: &str = "keybindings.toml";
+
+pub(crate) fn load() -> Result<Keybindings, String> {
+    let path = storage_path()?;
+    let file = storage::read_toml(&path)?;
+    Keybindings::from_file(file).map_err(|error| format!("{}: {error}", path.display()))
+}
+
+pub(crate) fn save(file: &KeybindingsFile) -> Result<(), String> {
+    storage::write_toml(&storage_path()?, file)
+}
+
+fn storage_path() -> Result<PathBuf, String> {
+    storage::config_file_path(KEYBINDINGS_NAME)
+}
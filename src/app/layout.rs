@@ -0,0 +1,45 @@
+use iced::Size;
+
+/// Smallest content area, below the menu bar, that fits the expanded layout.
+const EXPANDED_MIN_WIDTH: f32 = 520.0;
+const EXPANDED_MIN_HEIGHT: f32 = 300.0;
+
+/// How a player window arranges itself for the space it has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LayoutMode {
+    /// The transport bar alone, as in the window's default size.
+    Compact,
+    /// Artwork, part mixer, output level trace and folder queue around the transport.
+    Expanded,
+}
+
+impl LayoutMode {
+    pub(crate) fn for_size(size: Size) -> Self {
+        if size.width >= EXPANDED_MIN_WIDTH && size.height >= EXPANDED_MIN_HEIGHT {
+            Self::Expanded
+        } else {
+            Self::Compact
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_expand_only_once_both_dimensions_fit() {
+        assert_eq!(
+            LayoutMode::for_size(Size::new(350.0, 110.0)),
+            LayoutMode::Compact
+        );
+        assert_eq!(
+            LayoutMode::for_size(Size::new(900.0, 110.0)),
+            LayoutMode::Compact
+        );
+        assert_eq!(
+            LayoutMode::for_size(Size::new(640.0, 420.0)),
+            LayoutMode::Expanded
+        );
+    }
+}
@@ -0,0 +1,453 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::stored_path;
+use crate::playback::{extension_of, is_supported_path, probe_media};
+
+pub(crate) const LIBRARY_INDEX_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct LibraryEntry {
+    #[serde(with = "stored_path")]
+    pub(crate) path: PathBuf,
+    pub(crate) title: String,
+    pub(crate) format: String,
+    pub(crate) duration: Option<f64>,
+    pub(crate) part_count: Option<usize>,
+    pub(crate) size_bytes: u64,
+    pub(crate) modified: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct LibraryIndex {
+    pub(crate) version: u32,
+    #[serde(with = "stored_path::list")]
+    pub(crate) folders: Vec<PathBuf>,
+    pub(crate) entries: Vec<LibraryEntry>,
+}
+
+impl Default for LibraryIndex {
+    fn default() -> Self {
+        Self {
+            version: LIBRARY_INDEX_VERSION,
+            folders: Vec::new(),
+            entries: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum LibrarySort {
+    #[default]
+    Title,
+    Format,
+    Parts,
+    Duration,
+    Modified,
+}
+
+pub(crate) struct LibraryState {
+    pub(crate) index: LibraryIndex,
+    pub(crate) search: String,
+    pub(crate) sort: LibrarySort,
+    pub(crate) sort_descending: bool,
+    pub(crate) selected: Option<PathBuf>,
+    pub(crate) loaded: bool,
+    scan_generation: u64,
+    scan_in_flight: bool,
+    persist_generation: u64,
+    persist_requested: bool,
+    persist_in_flight: bool,
+}
+
+impl LibraryState {
+    pub(crate) fn new() -> Self {
+        Self {
+            index: LibraryIndex::default(),
+            search: String::new(),
+            sort: LibrarySort::default(),
+            sort_descending: false,
+            selected: None,
+            loaded: false,
+            scan_generation: 0,
+            scan_in_flight: false,
+            persist_generation: 0,
+            persist_requested: false,
+            persist_in_flight: false,
+        }
+    }
+
+    pub(crate) fn is_scanning(&self) -> bool {
+        self.scan_in_flight
+    }
+
+    pub(crate) fn load(&mut self, index: LibraryIndex) {
+        let pending_folders = std::mem::take(&mut self.index.folders);
+        self.index = index;
+        self.loaded = true;
+        for folder in pending_folders {
+            self.add_folder(folder);
+        }
+    }
+
+    /// Registers a folder, returning `false` if it is already covered by the library.
+    pub(crate) fn add_folder(&mut self, folder: PathBuf) -> bool {
+        if self
+            .index
+            .folders
+            .iter()
+            .any(|existing| folder.starts_with(existing))
+        {
+            return false;
+        }
+
+        self.index
+            .folders
+            .retain(|existing| !existing.starts_with(&folder));
+        self.index.folders.push(folder);
+        self.index.folders.sort();
+        self.request_persist();
+        true
+    }
+
+    pub(crate) fn remove_folder(&mut self, folder: &Path) {
+        self.index.folders.retain(|existing| existing != folder);
+        self.index
+            .entries
+            .retain(|entry| !entry.path.starts_with(folder));
+        self.request_persist();
+    }
+
+    /// Starts a new scan generation, superseding any scan still in flight.
+    pub(crate) fn begin_scan(&mut self) -> (u64, Vec<PathBuf>, Vec<LibraryEntry>) {
+        self.scan_generation = self.scan_generation.wrapping_add(1);
+        self.scan_in_flight = true;
+        (
+            self.scan_generation,
+            self.index.folders.clone(),
+            self.index.entries.clone(),
+        )
+    }
+
+    pub(crate) fn finish_scan(&mut self, generation: u64, entries: Vec<LibraryEntry>) {
+        if generation != self.scan_generation {
+            return;
+        }
+
+        self.scan_in_flight = false;
+        // Folders removed while the scan was running must not reappear.
+        let folders = &self.index.folders;
+        self.index.entries = entries
+            .into_iter()
+            .filter(|entry| folders.iter().any(|folder| entry.path.starts_with(folder)))
+            .collect();
+        self.request_persist();
+    }
+
+    pub(crate) fn take_index_to_persist(&mut self) -> Option<(u64, LibraryIndex)> {
+        if !self.loaded || !self.persist_requested || self.persist_in_flight {
+            return None;
+        }
+
+        self.persist_requested = false;
+        self.persist_in_flight = true;
+        Some((self.persist_generation, self.index.clone()))
+    }
+
+    pub(crate) fn index_persisted(&mut self, generation: u64) {
+        self.persist_in_flight = false;
+        if generation != self.persist_generation {
+            self.persist_requested = true;
+        }
+    }
+
+    pub(crate) fn set_sort(&mut self, sort: LibrarySort) {
+        if self.sort == sort {
+            self.sort_descending = !self.sort_descending;
+        } else {
+            self.sort = sort;
+            self.sort_descending = false;
+        }
+    }
+
+    pub(crate) fn visible_entries(&self) -> Vec<&LibraryEntry> {
+        let query = self.search.trim().to_lowercase();
+        let mut entries: Vec<&LibraryEntry> = self
+            .index
+            .entries
+            .iter()
+            .filter(|entry| query.is_empty() || entry_matches(entry, &query))
+            .collect();
+
+        entries.sort_by(|left, right| {
+            let ordering = compare_entries(left, right, self.sort);
+            if self.sort_descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        entries
+    }
+
+    fn request_persist(&mut self) {
+        self.persist_generation = self.persist_generation.wrapping_add(1);
+        self.persist_requested = true;
+    }
+}
+
+fn entry_matches(entry: &LibraryEntry, query: &str) -> bool {
+    entry.title.to_lowercase().contains(query)
+        || entry.path.to_string_lossy().to_lowercase().contains(query)
+}
+
+fn compare_entries(left: &LibraryEntry, right: &LibraryEntry, sort: LibrarySort) -> Ordering {
+    let primary = match sort {
+        LibrarySort::Title => Ordering::Equal,
+        LibrarySort::Format => left.format.cmp(&right.format),
+        LibrarySort::Parts => left.part_count.cmp(&right.part_count),
+        LibrarySort::Duration => left
+            .duration
+            .partial_cmp(&right.duration)
+            .unwrap_or(Ordering::Equal),
+        LibrarySort::Modified => left.modified.cmp(&right.modified),
+    };
+
+    primary
+        .then_with(|| left.title.to_lowercase().cmp(&right.title.to_lowercase()))
+        .then_with(|| left.path.cmp(&right.path))
+}
+
+/// Walks every registered folder and indexes supported audio files.
+///
+/// Entries whose size and modification time are unchanged since the previous
+/// scan are reused as-is, so rescans only probe files that actually changed.
+pub(crate) fn scan_folders(folders: &[PathBuf], previous: Vec<LibraryEntry>) -> Vec<LibraryEntry> {
+    let mut previous: HashMap<PathBuf, LibraryEntry> = previous
+        .into_iter()
+        .map(|entry| (entry.path.clone(), entry))
+        .collect();
+    let mut entries = Vec::new();
+    let mut pending = folders.to_vec();
+
+    while let Some(directory) = pending.pop() {
+        let Ok(read_dir) = fs::read_dir(&directory) else {
+            continue;
+        };
+
+        for dir_entry in read_dir.flatten() {
+            let path = dir_entry.path();
+            let Ok(file_type) = dir_entry.file_type() else {
+                continue;
+            };
+
+            if file_type.is_dir() {
+                let hidden = path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+                if !hidden {
+                    pending.push(path);
+                }
+                continue;
+            }
+
+            if !file_type.is_file() || !is_supported_path(&path) {
+                continue;
+            }
+
+            let Ok(metadata) = dir_entry.metadata() else {
+                continue;
+            };
+            let size_bytes = metadata.len();
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_secs());
+
+            if let Some(entry) = previous.remove(&path)
+                && entry.size_bytes == size_bytes
+                && entry.modified == modified
+            {
+                entries.push(entry);
+                continue;
+            }
+
+            let media = probe_media(&path);
+            entries.push(LibraryEntry {
+                title: path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                format: path
+                    .extension()
+                    .map(|extension| extension.to_string_lossy().to_ascii_uppercase())
+                    .unwrap_or_default(),
+                duration: media.duration,
+                part_count: media.part_count,
+                size_bytes,
+                modified,
+                path,
+            });
+        }
+    }
+
+    entries
+}
+
+/// Image names, without extension, checked in order for a folder's cover art.
+const ARTWORK_NAMES: &[&str] = &["cover", "folder", "front", "artwork"];
+const ARTWORK_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg"];
+
+/// What the expanded player window shows about the folder a file is in.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct FolderContents {
+    /// Supported files in the folder, ordered by file name.
+    pub(crate) queue: Vec<PathBuf>,
+    /// An image named after the file, or a conventional cover image.
+    pub(crate) artwork: Option<PathBuf>,
+}
+
+/// Lists `path`'s folder for the queue and cover art. Blocking; run off the UI
+/// thread.
+pub(crate) fn folder_contents(path: &Path) -> FolderContents {
+    let Some(files) = path.parent().and_then(folder_files) else {
+        return FolderContents::default();
+    };
+
+    let image_stem = |candidate: &PathBuf| {
+        let extension = extension_of(candidate)?;
+        ARTWORK_EXTENSIONS
+            .contains(&extension.as_str())
+            .then(|| candidate.file_stem())
+            .flatten()
+            .map(|stem| stem.to_string_lossy().to_lowercase())
+    };
+    let file_stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase());
+    let artwork = file_stem
+        .iter()
+        .map(String::as_str)
+        .chain(ARTWORK_NAMES.iter().copied())
+        .find_map(|name| {
+            files
+                .iter()
+                .find(|candidate| image_stem(candidate).as_deref() == Some(name))
+        })
+        .cloned();
+
+    FolderContents {
+        queue: files
+            .into_iter()
+            .filter(|candidate| is_supported_path(candidate))
+            .collect(),
+        artwork,
+    }
+}
+
+/// Finds the supported file before or after `path` in its folder, ordered by
+/// file name, without wrapping around at either end.
+pub(crate) fn sibling_file(path: &Path, forward: bool) -> Option<PathBuf> {
+    let siblings: Vec<PathBuf> = folder_files(path.parent()?)?
+        .into_iter()
+        .filter(|candidate| is_supported_path(candidate))
+        .collect();
+
+    let index = siblings.iter().position(|candidate| candidate == path)?;
+    let index = if forward {
+        index.checked_add(1)?
+    } else {
+        index.checked_sub(1)?
+    };
+    siblings.into_iter().nth(index)
+}
+
+/// Files directly inside `directory`, ordered by file name.
+fn folder_files(directory: &Path) -> Option<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(directory)
+        .ok()?
+        .flatten()
+        .map(|dir_entry| dir_entry.path())
+        .filter(|candidate| candidate.is_file())
+        .collect();
+    files.sort_by_cached_key(|candidate| {
+        candidate
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+    });
+    Some(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(title: &str, duration: f64) -> LibraryEntry {
+        LibraryEntry {
+            path: PathBuf::from(format!("/music/{title}.prot")),
+            title: title.to_owned(),
+            format: "PROT".to_owned(),
+            duration: Some(duration),
+            part_count: Some(4),
+            size_bytes: 0,
+            modified: None,
+        }
+    }
+
+    #[test]
+    fn search_matches_titles_case_insensitively_and_sort_toggles_direction() {
+        let mut library = LibraryState::new();
+        // Durations run in neither title order nor its reverse, so each
+        // assertion below fails if the sort is skipped.
+        library.index.entries = vec![
+            entry("Tidewater", 60.0),
+            entry("Tidal", 120.0),
+            entry("Harbour Lights", 90.0),
+            entry("tide pools", 300.0),
+        ];
+        library.search = "TID".to_owned();
+        let visible_titles = |library: &LibraryState| -> Vec<String> {
+            library
+                .visible_entries()
+                .iter()
+                .map(|entry| entry.title.clone())
+                .collect()
+        };
+
+        assert_eq!(
+            visible_titles(&library),
+            ["Tidal", "tide pools", "Tidewater"]
+        );
+
+        library.set_sort(LibrarySort::Duration);
+        assert_eq!(
+            visible_titles(&library),
+            ["Tidewater", "Tidal", "tide pools"]
+        );
+        assert!(!library.sort_descending);
+
+        library.set_sort(LibrarySort::Duration);
+        assert_eq!(
+            visible_titles(&library),
+            ["tide pools", "Tidal", "Tidewater"]
+        );
+        assert!(library.sort_descending);
+    }
+
+    #[test]
+    fn nested_folders_are_not_registered_twice() {
+        let mut library = LibraryState::new();
+
+        assert!(library.add_folder(PathBuf::from("/music/project")));
+        assert!(!library.add_folder(PathBuf::from("/music/project/stems")));
+        assert!(library.add_folder(PathBuf::from("/music")));
+
+        assert_eq!(library.index.folders, [PathBuf::from("/music")]);
+    }
+}
@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+
+use crate::app::library::LibraryIndex;
+use crate::app::storage;
+
+const LIBRARY_NAME: &str = "library.json";
+
+pub(crate) fn load() -> Result<LibraryIndex, String> {
+    storage::read_json(&storage_path()?)
+}
+
+pub(crate) fn save(index: &LibraryIndex) -> Result<(), String> {
+    storage::write_json(&storage_path()?, index)
+}
+
+fn storage_path() -> Result<PathBuf, String> {
+    storage::data_file_path(LIBRARY_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::library::{LIBRARY_INDEX_VERSION, LibraryEntry};
+
+    #[test]
+    fn library_index_round_trips_through_json() {
+        let index = LibraryIndex {
+            version: LIBRARY_INDEX_VERSION,
+            folders: vec![PathBuf::from("/music")],
+            entries: vec![LibraryEntry {
+                path: PathBuf::from("/music/piece.prot"),
+                title: "piece".to_owned(),
+                format: "PROT".to_owned(),
+                duration: Some(1260.5),
+                part_count: Some(5),
+                size_bytes: 4096,
+                modified: Some(1_700_000_000),
+            }],
+        };
+        let serialized = serde_json::to_vec(&index).expect("the index should serialize");
+
+        let restored: LibraryIndex =
+            serde_json::from_slice(&serialized).expect("the index should deserialize");
+
+        assert_eq!(restored, index);
+    }
+}
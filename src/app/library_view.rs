@@ -0,0 +1,208 @@
+use iced::widget::{button, column, container, mouse_area, row, scrollable, text, text_input};
+use iced::{Alignment, Element, Length};
+
+use crate::app::helpers::{civil_from_days, format_time};
+use crate::app::library::{LibraryEntry, LibrarySort};
+use crate::app::messages::Message;
+use crate::app::state::ProteusApp;
+use crate::app::styles::{background_style, library_row_style, menu_header_style};
+use crate::app::theme::Palette;
+
+const PARTS_COLUMN_WIDTH: f32 = 60.0;
+const FORMAT_COLUMN_WIDTH: f32 = 60.0;
+const DURATION_COLUMN_WIDTH: f32 = 70.0;
+
+pub(crate) fn library_view(state: &ProteusApp) -> Element<'_, Message> {
+    let library = &state.library;
+    let palette = state.palette;
+
+    let toolbar = row![
+        text_input("Search library…", &library.search)
+            .on_input(Message::LibrarySearchChanged)
+            .size(13)
+            .padding([4, 8])
+            .width(Length::Fill),
+        button(text("Add Folder…").size(12))
+            .padding([4, 8])
+            .on_press(Message::LibraryAddFolderPressed),
+        button(text("Rescan").size(12))
+            .padding([4, 8])
+            .on_press_maybe(
+                (!library.index.folders.is_empty() && !library.is_scanning())
+                    .then_some(Message::LibraryRescanPressed)
+            ),
+    ]
+    .spacing(8)
+    .align_y(Alignment::Center);
+
+    let folders = library
+        .index
+        .folders
+        .iter()
+        .fold(column![].spacing(2), |folders, folder| {
+            folders.push(
+                row![
+                    text(folder.display().to_string())
+                        .size(11)
+                        .color(palette.accent_text)
+                        .width(Length::Fill),
+                    button(text("Remove").size(11))
+                        .style(button::text)
+                        .padding([0, 4])
+                        .on_press(Message::LibraryRemoveFolderPressed(folder.clone())),
+                ]
+                .align_y(Alignment::Center),
+            )
+        });
+
+    let header = row![
+        sort_header("Title", LibrarySort::Title, state, Length::Fill),
+        sort_header(
+            "Format",
+            LibrarySort::Format,
+            state,
+            Length::Fixed(FORMAT_COLUMN_WIDTH)
+        ),
+        sort_header(
+            "Parts",
+            LibrarySort::Parts,
+            state,
+            Length::Fixed(PARTS_COLUMN_WIDTH)
+        ),
+        sort_header(
+            "Length",
+            LibrarySort::Duration,
+            state,
+            Length::Fixed(DURATION_COLUMN_WIDTH)
+        ),
+        sort_header("Modified", LibrarySort::Modified, state, Length::Shrink),
+    ]
+    .spacing(4)
+    .align_y(Alignment::Center);
+
+    let entries = library.visible_entries();
+    let status = if library.is_scanning() {
+        format!("Scanning… {} items indexed", library.index.entries.len())
+    } else if library.index.folders.is_empty() {
+        "Add a folder to start building your library.".to_owned()
+    } else {
+        format!(
+            "{} of {} items — double-click to play",
+            entries.len(),
+            library.index.entries.len()
+        )
+    };
+
+    let rows = entries
+        .into_iter()
+        .fold(column![].spacing(1), |rows, entry| {
+            rows.push(entry_row(
+                palette,
+                entry,
+                library.selected.as_ref() == Some(&entry.path),
+            ))
+        });
+
+    let content = column![
+        toolbar,
+        folders,
+        container(header)
+            .padding([4, 8])
+            .width(Length::Fill)
+            .style(menu_header_style(palette)),
+        scrollable(rows).height(Length::Fill),
+        text(status).size(11).color(palette.accent_text),
+    ]
+    .spacing(8)
+    .padding(12);
+
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(background_style(palette))
+        .into()
+}
+
+fn sort_header<'a>(
+    label: &'a str,
+    sort: LibrarySort,
+    state: &'a ProteusApp,
+    width: Length,
+) -> Element<'a, Message> {
+    let palette = state.palette;
+    let indicator = match (state.library.sort == sort, state.library.sort_descending) {
+        (true, false) => " ▴",
+        (true, true) => " ▾",
+        (false, _) => "",
+    };
+
+    button(
+        text(format!("{label}{indicator}"))
+            .size(11)
+            .color(palette.accent_text),
+    )
+    .style(button::text)
+    .padding(0)
+    .width(width)
+    .on_press(Message::LibrarySortPressed(sort))
+    .into()
+}
+
+fn entry_row(palette: Palette, entry: &LibraryEntry, selected: bool) -> Element<'_, Message> {
+    let parts = entry
+        .part_count
+        .map(|count| count.to_string())
+        .unwrap_or_else(|| "–".to_owned());
+    let duration = entry
+        .duration
+        .map(format_time)
+        .unwrap_or_else(|| "–".to_owned());
+    let modified = entry
+        .modified
+        .map(format_modified)
+        .unwrap_or_else(|| "–".to_owned());
+
+    let content = row![
+        column![
+            text(&entry.title).size(13),
+            text(
+                entry
+                    .path
+                    .parent()
+                    .map(|parent| parent.display().to_string())
+                    .unwrap_or_default()
+            )
+            .size(10)
+            .color(palette.accent_text),
+        ]
+        .width(Length::Fill),
+        text(&entry.format)
+            .size(12)
+            .width(Length::Fixed(FORMAT_COLUMN_WIDTH)),
+        text(parts)
+            .size(12)
+            .width(Length::Fixed(PARTS_COLUMN_WIDTH)),
+        text(duration)
+            .size(12)
+            .width(Length::Fixed(DURATION_COLUMN_WIDTH)),
+        text(modified).size(12),
+    ]
+    .spacing(4)
+    .align_y(Alignment::Center);
+
+    mouse_area(
+        container(content)
+            .padding([4, 8])
+            .width(Length::Fill)
+            .style(library_row_style(palette, selected)),
+    )
+    .on_press(Message::LibraryEntrySelected(entry.path.clone()))
+    .on_double_click(Message::LibraryEntryActivated(entry.path.clone()))
+    .into()
+}
+
+/// Formats seconds since the Unix epoch as a UTC `YYYY-MM-DD` date.
+fn format_modified(seconds: u64) -> String {
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    format!("{year:04}-{month:02}-{day:02}")
+}
@@ -0,0 +1,524 @@
+//! A local control link for authoring tools such as Proteus Author.
+//!
+//! The player listens on a loopback TCP port and writes the port, with a
+//! token, to `link.json` in the app data directory. A client connects and
+//! sends `hello` with the token; after that both sides exchange one JSON
+//! object per line:
+//!
+//! ```text
+//! → {"id": 1, "command": "hello", "token": "…", "client": "Proteus Author"}
+//! ← {"event": "reply", "id": 1, "ok": true}
+//! → {"id": 2, "command": "open", "path": "/songs/take.prot", "position": 30.0, "play": true}
+//! ← {"event": "reply", "id": 2, "ok": true}
+//! ← {"event": "loaded", "path": "/songs/take.prot", "duration": 184.2}
+//! ← {"event": "position", "path": "/songs/take.prot", "time": 30.25, …}
+//! ```
+//!
+//! Commands are `open`, `seek`, `play`, `pause` and `status`. They all act
+//! on one link window, the one the last `open` went to. While a client is
+//! connected its position is reported a few times a second.
+//!
+//! Version 1 cannot choose takes: proteus-lib picks them itself and has no
+//! call to select a combination. Position reports name the takes playing,
+//! so a client can still see which ones were picked. A `select-takes`
+//! command is blocked on that call and is listed under the README's known
+//! gaps.
+
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, Shutdown, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::storage;
+
+pub(crate) const PROTOCOL_VERSION: u32 = 1;
+
+const LINK_FILE_NAME: &str = "link.json";
+/// Longest a client that is not reading may hold up the UI thread while a
+/// line is written to it.
+const WRITE_TIMEOUT: Duration = Duration::from_millis(50);
+/// How long a new connection has to say hello before it is closed.
+const HELLO_TIMEOUT: Duration = Duration::from_secs(5);
+/// Longest line read before hello. Anything longer closes the connection.
+const MAX_HELLO_LINE_LEN: u64 = 1024;
+/// Longest request line read from a client that said hello.
+const MAX_LINE_LEN: u64 = 64 * 1024;
+/// Connections that have yet to say hello. Further ones are closed straight
+/// away until some of these finish.
+const MAX_PENDING_CLIENTS: usize = 8;
+
+pub(crate) type ClientId = u64;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct Request {
+    /// Echoed in the reply, so a client can match them up.
+    #[serde(default)]
+    pub(crate) id: Option<u64>,
+    #[serde(flatten)]
+    pub(crate) command: Command,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub(crate) enum Command {
+    Hello {
+        token: String,
+        #[serde(default)]
+        client: Option<String>,
+    },
+    /// Opens a file in the link window, at `position` seconds if given.
+    Open {
+        path: PathBuf,
+        #[serde(default)]
+        position: Option<f64>,
+        #[serde(default)]
+        play: bool,
+    },
+    Seek {
+        position: f64,
+    },
+    Play,
+    Pause,
+    /// Asks for a position report straight away.
+    Status,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub(crate) enum Event {
+    Reply {
+        id: Option<u64>,
+        ok: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    Position {
+        path: Option<PathBuf>,
+        time: f64,
+        duration: Option<f64>,
+        playing: bool,
+        /// Track ids of the takes playing.
+        combination: Vec<String>,
+    },
+    Loaded {
+        path: PathBuf,
+        duration: Option<f64>,
+    },
+    LoadFailed {
+        path: PathBuf,
+        error: String,
+    },
+    /// The link window was closed; the next `open` makes a new one.
+    Closed,
+}
+
+impl Event {
+    pub(crate) fn reply(id: Option<u64>, result: Result<(), String>) -> Self {
+        match result {
+            Ok(()) => Self::Reply {
+                id,
+                ok: true,
+                error: None,
+            },
+            Err(error) => Self::Reply {
+                id,
+                ok: false,
+                error: Some(error),
+            },
+        }
+    }
+}
+
+/// What `link.json` tells a client about the running player.
+#[derive(Debug, Serialize)]
+struct LinkFile<'a> {
+    version: u32,
+    port: u16,
+    pid: u32,
+    token: &'a str,
+}
+
+/// Accepts link clients in the background and hands their requests to the
+/// UI thread, which polls for them every tick. Dropping it disconnects the
+/// clients, stops listening and removes `link.json`.
+pub(crate) struct LinkServer {
+    port: u16,
+    requests: Receiver<(ClientId, Request)>,
+    /// Write halves of the clients that said hello.
+    clients: Arc<Mutex<HashMap<ClientId, TcpStream>>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl LinkServer {
+    pub(crate) fn start() -> Result<Self, String> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .map_err(|error| format!("could not listen for link clients: {error}"))?;
+        let port = listener
+            .local_addr()
+            .map_err(|error| format!("could not read the link port: {error}"))?
+            .port();
+        let token = new_token()?;
+        write_link_file(&LinkFile {
+            version: PROTOCOL_VERSION,
+            port,
+            pid: std::process::id(),
+            token: &token,
+        })?;
+
+        let (sender, requests) = mpsc::channel();
+        let clients = Arc::new(Mutex::new(HashMap::new()));
+        let stopped = Arc::new(AtomicBool::new(false));
+        let accepted_clients = Arc::clone(&clients);
+        let listener_stopped = Arc::clone(&stopped);
+        thread::Builder::new()
+            .name("link-listener".to_owned())
+            .spawn(move || {
+                accept_clients(listener, token, sender, accepted_clients, &listener_stopped)
+            })
+            .map_err(|error| format!("could not start the link listener: {error}"))?;
+
+        log::info!("link listening port={port}");
+        Ok(Self {
+            port,
+            requests,
+            clients,
+            stopped,
+        })
+    }
+
+    pub(crate) fn poll_request(&self) -> Option<(ClientId, Request)> {
+        self.requests.try_recv().ok()
+    }
+
+    pub(crate) fn has_clients(&self) -> bool {
+        !self.lock_clients().is_empty()
+    }
+
+    pub(crate) fn send(&self, client: ClientId, event: &Event) {
+        let mut clients = self.lock_clients();
+        if let Some(stream) = clients.get_mut(&client)
+            && write_event(stream, event).is_err()
+        {
+            log::info!("link client dropped client={client}");
+            clients.remove(&client);
+        }
+    }
+
+    pub(crate) fn broadcast(&self, event: &Event) {
+        self.lock_clients().retain(|client, stream| {
+            let sent = write_event(stream, event).is_ok();
+            if !sent {
+                log::info!("link client dropped client={client}");
+            }
+            sent
+        });
+    }
+
+    fn lock_clients(&self) -> std::sync::MutexGuard<'_, HashMap<ClientId, TcpStream>> {
+        self.clients.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for LinkServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Release);
+        // Wake the listener, which is blocked waiting for a connection.
+        let _ = TcpStream::connect((Ipv4Addr::LOCALHOST, self.port));
+        for (_, stream) in self.lock_clients().drain() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        remove_link_file(self.port);
+        log::info!("link stopped port={}", self.port);
+    }
+}
+
+fn accept_clients(
+    listener: TcpListener,
+    token: String,
+    requests: Sender<(ClientId, Request)>,
+    clients: Arc<Mutex<HashMap<ClientId, TcpStream>>>,
+    stopped: &AtomicBool,
+) {
+    static NEXT_CLIENT: AtomicU64 = AtomicU64::new(1);
+    let pending = Arc::new(AtomicUsize::new(0));
+
+    for stream in listener.incoming() {
+        if stopped.load(Ordering::Acquire) {
+            break;
+        }
+        let Ok(stream) = stream else {
+            continue;
+        };
+        if pending.load(Ordering::Acquire) >= MAX_PENDING_CLIENTS {
+            log::debug!("link connection refused, too many waiting for hello");
+            continue;
+        }
+        let waiting = PendingClient::new(&pending);
+        let client = NEXT_CLIENT.fetch_add(1, Ordering::Relaxed);
+        let token = token.clone();
+        let requests = requests.clone();
+        let clients = Arc::clone(&clients);
+        let _ = thread::Builder::new()
+            .name(format!("link-client-{client}"))
+            .spawn(move || serve_client(client, stream, waiting, &token, &requests, &clients));
+    }
+}
+
+/// Counts a connection as waiting for hello for as long as it is held.
+struct PendingClient(Arc<AtomicUsize>);
+
+impl PendingClient {
+    fn new(pending: &Arc<AtomicUsize>) -> Self {
+        pending.fetch_add(1, Ordering::AcqRel);
+        Self(Arc::clone(pending))
+    }
+}
+
+impl Drop for PendingClient {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Reads one client's requests until it disconnects. Nothing but `hello`
+/// with the right token is accepted until the client has said it, and it
+/// has to say it promptly and briefly.
+fn serve_client(
+    client: ClientId,
+    stream: TcpStream,
+    waiting: PendingClient,
+    token: &str,
+    requests: &Sender<(ClientId, Request)>,
+    clients: &Mutex<HashMap<ClientId, TcpStream>>,
+) {
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    let _ = writer.set_write_timeout(Some(WRITE_TIMEOUT));
+    let _ = stream.set_read_timeout(Some(HELLO_TIMEOUT));
+    let mut reader = BufReader::new(stream);
+    let mut waiting = Some(waiting);
+    let mut line = Vec::new();
+
+    loop {
+        let limit = if waiting.is_some() {
+            MAX_HELLO_LINE_LEN
+        } else {
+            MAX_LINE_LEN
+        };
+        line.clear();
+        match (&mut reader).take(limit).read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        if line.last() != Some(&b'\n') {
+            let reply = Event::reply(None, Err(format!("requests are limited to {limit} bytes")));
+            let _ = write_event(&mut writer, &reply);
+            break;
+        }
+        if line.trim_ascii().is_empty() {
+            continue;
+        }
+
+        let request: Request = match serde_json::from_slice(&line) {
+            Ok(request) => request,
+            Err(error) => {
+                let reply = Event::reply(None, Err(format!("invalid request: {error}")));
+                if write_event(&mut writer, &reply).is_err() || waiting.is_some() {
+                    break;
+                }
+                continue;
+            }
+        };
+
+        match (&request.command, waiting.is_some()) {
+            (Command::Hello { token: given, .. }, true) if tokens_match(given, token) => {
+                let Ok(registered) = writer.try_clone() else {
+                    break;
+                };
+                if write_event(&mut writer, &Event::reply(request.id, Ok(()))).is_err() {
+                    break;
+                }
+                log::info!("link client connected client={client} request={request:?}");
+                let _ = reader.get_ref().set_read_timeout(None);
+                clients
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .insert(client, registered);
+                waiting = None;
+            }
+            (_, true) => {
+                let reply = Event::reply(request.id, Err("say hello with the token first".into()));
+                let _ = write_event(&mut writer, &reply);
+                break;
+            }
+            (_, false) => {
+                if requests.send((client, request)).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    if waiting.is_none() {
+        log::info!("link client disconnected client={client}");
+    }
+    clients
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&client);
+}
+
+fn write_event(stream: &mut TcpStream, event: &Event) -> std::io::Result<()> {
+    let mut line = serde_json::to_vec(event).map_err(std::io::Error::other)?;
+    line.push(b'\n');
+    stream.write_all(&line)
+}
+
+/// A random token from the operating system's generator, so only processes
+/// that can read the link file (that is, the same user's) can control the
+/// player.
+fn new_token() -> Result<String, String> {
+    let mut bytes = [0; 16];
+    getrandom::fill(&mut bytes)
+        .map_err(|error| format!("could not generate a link token: {error}"))?;
+    Ok(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+/// Checks a position a client asked for, in seconds, against the file's
+/// length when that is known.
+pub(crate) fn check_position(position: f64, duration: Option<f64>) -> Result<(), String> {
+    if !position.is_finite() || position < 0.0 {
+        return Err(format!(
+            "position must be a non-negative number of seconds, not {position}"
+        ));
+    }
+    match duration {
+        Some(duration) if position > duration => Err(format!(
+            "position {position} is past the end of the file at {duration}"
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Compares every byte whatever the first difference, so the time taken
+/// does not tell a client how much of its guess was right.
+fn tokens_match(given: &str, token: &str) -> bool {
+    let difference = given
+        .bytes()
+        .zip(token.bytes())
+        .fold(0, |difference, (given, expected)| {
+            difference | (given ^ expected)
+        });
+    std::hint::black_box(difference) == 0 && given.len() == token.len()
+}
+
+fn write_link_file(link: &LinkFile<'_>) -> Result<(), String> {
+    let path = storage::data_file_path(LINK_FILE_NAME)?;
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)
+            .map_err(|error| format!("could not create {}: {error}", directory.display()))?;
+    }
+    let contents = serde_json::to_vec(link)
+        .map_err(|error| format!("could not serialize {}: {error}", path.display()))?;
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(&path)
+        .and_then(|mut file| {
+            // The mode above only applies to a new file, so one left by an
+            // earlier run is narrowed before the token goes into it.
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                file.set_permissions(fs::Permissions::from_mode(0o600))?;
+            }
+            file.write_all(&contents)
+        })
+        .map_err(|error| format!("could not write {}: {error}", path.display()))
+}
+
+/// Removes `link.json` if it still describes this process's listener, and
+/// not one another instance has written since.
+fn remove_link_file(port: u16) {
+    let Ok(path) = storage::data_file_path(LINK_FILE_NAME) else {
+        return;
+    };
+    let Ok(link) = storage::read_json::<serde_json::Value>(&path) else {
+        return;
+    };
+    if link["port"] == port && link["pid"] == std::process::id() {
+        let _ = fs::remove_file(&path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_parse_and_events_serialize_as_json_lines() {
+        let request: Request = serde_json::from_str(
+            r#"{"id": 2, "command": "open", "path": "/songs/a.prot", "position": 30.0, "play": true}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            request,
+            Request {
+                id: Some(2),
+                command: Command::Open {
+                    path: PathBuf::from("/songs/a.prot"),
+                    position: Some(30.0),
+                    play: true,
+                },
+            }
+        );
+
+        assert!(serde_json::from_str::<Request>(r#"{"command": "rewind"}"#).is_err());
+        assert!(
+            serde_json::from_str::<Request>(r#"{"command": "select-takes", "takes": ["1"]}"#)
+                .is_err()
+        );
+
+        assert!(check_position(30.0, Some(184.2)).is_ok());
+        assert!(check_position(30.0, None).is_ok());
+        assert!(check_position(200.0, Some(184.2)).is_err());
+        assert!(check_position(-1.0, None).is_err());
+
+        assert_eq!(
+            serde_json::to_string(&Event::reply(Some(2), Ok(()))).unwrap(),
+            r#"{"event":"reply","id":2,"ok":true}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&Event::reply(None, Err("nothing is loaded".to_owned())))
+                .unwrap(),
+            r#"{"event":"reply","id":null,"ok":false,"error":"nothing is loaded"}"#
+        );
+    }
+
+    #[test]
+    fn tokens_must_match_in_full() {
+        let token = new_token().unwrap();
+        assert_eq!(token.len(), 32);
+        assert_ne!(token, new_token().unwrap());
+
+        assert!(tokens_match(&token, &token));
+        assert!(!tokens_match(&token[..31], &token));
+        assert!(!tokens_match(&format!("{token}0"), &token));
+        assert!(!tokens_match("", &token));
+    }
+}
@@ -0,0 +1,182 @@
+//! Leveled logging to a rotating file in the app data directory.
+//!
+//! Lines read `<timestamp> <LEVEL> <target>: <message>`, with messages
+//! written as `event key=value …` so they can be searched and parsed.
+//! Other crates (iced, wgpu, the audio backend) only get their warnings and
+//! errors through, whatever the level.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::app::helpers::civil_from_days;
+use crate::app::storage;
+
+pub(crate) const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+const LOG_FILE_NAME: &str = "proteus-player.log";
+/// Size at which the log is rotated.
+const MAX_LOG_BYTES: u64 = 2 * 1024 * 1024;
+/// Rotated logs kept alongside the current one, as `.1` (newest) to `.3`.
+const KEPT_LOGS: usize = 3;
+
+/// Where the current log is written.
+pub(crate) fn log_file_path() -> Result<PathBuf, String> {
+    storage::data_file_path("logs").map(|directory| directory.join(LOG_FILE_NAME))
+}
+
+/// Routes the `log` macros to the log file and records panics there before
+/// the default hook runs. Failing to open the file leaves logging off rather
+/// than stopping the app.
+pub(crate) fn init(level: LevelFilter) {
+    let logger = match log_file_path().and_then(|path| {
+        RotatingFile::open(path.clone())
+            .map_err(|error| format!("could not open {}: {error}", path.display()))
+    }) {
+        Ok(file) => FileLogger {
+            level,
+            file: Mutex::new(file),
+        },
+        Err(error) => {
+            eprintln!("Logging is disabled: {error}");
+            return;
+        }
+    };
+
+    if log::set_boxed_logger(Box::new(logger)).is_err() {
+        return;
+    }
+    log::set_max_level(level);
+
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        log::error!("panic {info}");
+        log::logger().flush();
+        default_hook(info);
+    }));
+}
+
+struct FileLogger {
+    level: LevelFilter,
+    file: Mutex<RotatingFile>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let level = if metadata.target().starts_with(env!("CARGO_CRATE_NAME")) {
+            self.level
+        } else {
+            self.level.min(LevelFilter::Warn)
+        };
+        metadata.level() <= level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!(
+            "{} {:<5} {}: {}\n",
+            utc_timestamp(SystemTime::now()),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        if let Ok(mut file) = self.file.lock() {
+            // There is nowhere left to report a failed log write.
+            let _ = file.write_line(line.as_bytes());
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+/// The log file, moved aside to `.1` once it reaches [`MAX_LOG_BYTES`].
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    written: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf) -> io::Result<Self> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            written,
+        })
+    }
+
+    fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        if self.written > 0 && self.written + line.len() as u64 > MAX_LOG_BYTES {
+            self.rotate()?;
+        }
+        self.file.write_all(line)?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        for index in (1..KEPT_LOGS).rev() {
+            let from = rotated_path(&self.path, index);
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.path, index + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        *self = Self::open(self.path.clone())?;
+        Ok(())
+    }
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_owned();
+    name.push(format!(".{index}"));
+    path.with_file_name(name)
+}
+
+/// `time` as an RFC 3339 UTC timestamp with milliseconds.
+fn utc_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    let seconds_of_day = seconds % 86_400;
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        seconds_of_day / 3600,
+        seconds_of_day / 60 % 60,
+        seconds_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn timestamps_are_utc_with_milliseconds() {
+        assert_eq!(utc_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+
+        // 2024-02-29 23:59:58.5, a leap day.
+        let time = UNIX_EPOCH + Duration::from_millis(1_709_251_198_500);
+        assert_eq!(utc_timestamp(time), "2024-02-29T23:59:58.500Z");
+    }
+}
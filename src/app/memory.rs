@@ -1,131 +1,375 @@
-#[cfg(feature = "debug")]
-mod enabled {
-    use std::process::Command;
-    use std::time::{Duration, Instant};
-
-    use sysinfo::{Pid, ProcessesToUpdate, System};
-
-    pub(crate) struct MemorySampler {
-        system: System,
-        pid: Pid,
-        next_periodic_log: Instant,
-        peak_rss: u64,
-        peak_virtual: u64,
-    }
-
-    impl MemorySampler {
-        pub(crate) fn from_feat() -> Option<Self> {
-            Some(Self {
-                system: System::new(),
-                pid: Pid::from_u32(std::process::id()),
-                next_periodic_log: Instant::now(),
-                peak_rss: 0,
-                peak_virtual: 0,
-            })
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use sysinfo::{Pid, ProcessesToUpdate, System};
+
+use crate::playback::PlaybackController;
+
+/// Time between periodic samples.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+/// Samples kept for the diagnostics panel and export, about an hour's worth.
+const HISTORY_LEN: usize = 3600;
+
+/// What the app has open when a sample is taken.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct PlayerCounts {
+    pub(crate) windows: usize,
+    pub(crate) loaded_players: usize,
+    /// Output chunks queued across every player.
+    pub(crate) queued_chunks: usize,
+    /// Buffer underruns across every player since each was built.
+    pub(crate) underruns: u64,
+}
+
+impl PlayerCounts {
+    /// Counts the players given, one per window.
+    pub(crate) fn from_players<'a>(
+        players: impl ExactSizeIterator<Item = &'a PlaybackController>,
+    ) -> Self {
+        let windows = players.len();
+        players.fold(
+            Self {
+                windows,
+                ..Self::default()
+            },
+            |mut counts, player| {
+                if let Some(stats) = player.buffer_stats() {
+                    counts.loaded_players += 1;
+                    counts.queued_chunks += stats.queued_chunks;
+                    counts.underruns += stats.underruns;
+                }
+                counts
+            },
+        )
+    }
+}
+
+/// File formats the diagnostics history can be exported in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
         }
+    }
+}
+
+/// One row of the diagnostics history.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Sample {
+    /// Seconds since the sampler started.
+    pub(crate) elapsed_secs: f64,
+    /// `tick` for periodic samples, otherwise the event that caused it.
+    pub(crate) reason: &'static str,
+    pub(crate) rss_bytes: u64,
+    pub(crate) virtual_bytes: u64,
+    /// Counted only while thread counting is on; see
+    /// [`MemorySampler::set_count_threads`].
+    pub(crate) threads: Option<usize>,
+    pub(crate) windows: usize,
+    pub(crate) loaded_players: usize,
+    pub(crate) queued_chunks: usize,
+    pub(crate) underruns: u64,
+    /// Average and longest time between ticks since the previous sample.
+    pub(crate) tick_interval_ms: Option<f64>,
+    pub(crate) tick_interval_max_ms: Option<f64>,
+    /// Average time spent handling a tick since the previous sample.
+    pub(crate) tick_work_ms: Option<f64>,
+    /// Average and longest time between frames since the previous sample,
+    /// measured only while the diagnostics panel is open.
+    pub(crate) frame_interval_ms: Option<f64>,
+    pub(crate) frame_interval_max_ms: Option<f64>,
+}
 
-        pub(crate) fn maybe_log_periodic(&mut self, windows: usize, loaded_players: usize) {
-            let now = Instant::now();
-            if now < self.next_periodic_log {
-                return;
-            }
-            self.next_periodic_log = now + Duration::from_secs(1);
-            self.log("tick", windows, loaded_players);
+/// Samples process memory, thread count and app timing once a second,
+/// plus whenever a window opens or closes, keeping the history for the
+/// diagnostics panel.
+pub(crate) struct MemorySampler {
+    system: System,
+    pid: Pid,
+    started_at: Instant,
+    next_sample_at: Instant,
+    samples: VecDeque<Sample>,
+    pub(crate) peak_rss: u64,
+    pub(crate) peak_virtual: u64,
+    last_tick_at: Option<Instant>,
+    tick_intervals: Timings,
+    tick_work: Timings,
+    last_frame_at: Option<Instant>,
+    frame_intervals: Timings,
+    count_threads: bool,
+}
+
+impl MemorySampler {
+    pub(crate) fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            system: System::new(),
+            pid: Pid::from_u32(std::process::id()),
+            started_at: now,
+            next_sample_at: now,
+            samples: VecDeque::with_capacity(HISTORY_LEN),
+            peak_rss: 0,
+            peak_virtual: 0,
+            last_tick_at: None,
+            tick_intervals: Timings::default(),
+            tick_work: Timings::default(),
+            last_frame_at: None,
+            frame_intervals: Timings::default(),
+            count_threads: false,
+        }
+    }
+
+    /// Turns thread counting on or off. On macOS each count runs `ps`, so it
+    /// is only done while someone is looking at the numbers.
+    pub(crate) fn set_count_threads(&mut self, count_threads: bool) {
+        self.count_threads = count_threads;
+    }
+
+    /// Records a tick that started at `started` and took `work` to handle.
+    pub(crate) fn record_tick(&mut self, started: Instant, work: Duration) {
+        if let Some(last) = self.last_tick_at.replace(started) {
+            self.tick_intervals.add(started.duration_since(last));
         }
+        self.tick_work.add(work);
+    }
 
-        pub(crate) fn log_event(&mut self, reason: &str, windows: usize, loaded_players: usize) {
-            self.log(reason, windows, loaded_players);
+    pub(crate) fn record_frame(&mut self, at: Instant) {
+        if let Some(last) = self.last_frame_at.replace(at) {
+            self.frame_intervals.add(at.saturating_duration_since(last));
         }
+    }
+
+    /// Forgets the last frame, so the gap while frames were not being
+    /// measured is not counted as one long frame.
+    pub(crate) fn reset_frames(&mut self) {
+        self.last_frame_at = None;
+    }
+
+    pub(crate) fn maybe_sample_periodic(&mut self, counts: PlayerCounts) {
+        let now = Instant::now();
+        if now < self.next_sample_at {
+            return;
+        }
+        self.next_sample_at = now + SAMPLE_INTERVAL;
+        self.sample("tick", counts);
+    }
+
+    pub(crate) fn sample_event(&mut self, reason: &'static str, counts: PlayerCounts) {
+        self.sample(reason, counts);
+    }
+
+    pub(crate) fn samples(&self) -> &VecDeque<Sample> {
+        &self.samples
+    }
+
+    fn sample(&mut self, reason: &'static str, counts: PlayerCounts) {
+        self.system
+            .refresh_processes(ProcessesToUpdate::Some(&[self.pid]), true);
 
-        fn log(&mut self, reason: &str, windows: usize, loaded_players: usize) {
-            self.system
-                .refresh_processes(ProcessesToUpdate::Some(&[self.pid]), true);
+        let Some(process) = self.system.process(self.pid) else {
+            return;
+        };
 
-            let Some(process) = self.system.process(self.pid) else {
-                return;
-            };
+        let rss = process.memory();
+        let virtual_memory = process.virtual_memory();
+        self.peak_rss = self.peak_rss.max(rss);
+        self.peak_virtual = self.peak_virtual.max(virtual_memory);
 
-            let rss = process.memory();
-            let virtual_memory = process.virtual_memory();
-            let thread_count = thread_count_for_pid(self.pid.as_u32());
-            self.peak_rss = self.peak_rss.max(rss);
-            self.peak_virtual = self.peak_virtual.max(virtual_memory);
+        let sample = Sample {
+            elapsed_secs: self.started_at.elapsed().as_secs_f64(),
+            reason,
+            rss_bytes: rss,
+            virtual_bytes: virtual_memory,
+            threads: self
+                .count_threads
+                .then(|| thread_count_for_pid(self.pid.as_u32()))
+                .flatten(),
+            windows: counts.windows,
+            loaded_players: counts.loaded_players,
+            queued_chunks: counts.queued_chunks,
+            underruns: counts.underruns,
+            tick_interval_ms: self.tick_intervals.average_ms(),
+            tick_interval_max_ms: self.tick_intervals.max_ms(),
+            tick_work_ms: self.tick_work.average_ms(),
+            frame_interval_ms: self.frame_intervals.average_ms(),
+            frame_interval_max_ms: self.frame_intervals.max_ms(),
+        };
+        self.tick_intervals = Timings::default();
+        self.tick_work = Timings::default();
+        self.frame_intervals = Timings::default();
 
-            eprintln!(
-                "[mem] reason={reason} rss_mb={:.1} virtual_mb={:.1} peak_rss_mb={:.1} peak_virtual_mb={:.1} threads={} windows={} loaded_players={}",
+        // Periodic samples would fill the log, so only `debug` builds write
+        // them there.
+        if reason != "tick" || cfg!(feature = "debug") {
+            log::debug!(
+                "memory reason={reason} rss_mb={:.1} virtual_mb={:.1} peak_rss_mb={:.1} peak_virtual_mb={:.1} threads={} windows={} loaded_players={}",
                 bytes_to_mb(rss),
                 bytes_to_mb(virtual_memory),
                 bytes_to_mb(self.peak_rss),
                 bytes_to_mb(self.peak_virtual),
-                thread_count
-                    .map(|value| value.to_string())
-                    .unwrap_or_else(|| "n/a".to_owned()),
-                windows,
-                loaded_players,
+                optional(sample.threads),
+                sample.windows,
+                sample.loaded_players,
             );
         }
+
+        if self.samples.len() == HISTORY_LEN {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
     }
 
-    fn bytes_to_mb(bytes: u64) -> f64 {
-        bytes as f64 / (1024.0 * 1024.0)
+    /// The history as CSV, one sample per row.
+    pub(crate) fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "elapsed_secs,reason,rss_bytes,virtual_bytes,threads,windows,loaded_players,queued_chunks,underruns,tick_interval_ms,tick_interval_max_ms,tick_work_ms,frame_interval_ms,frame_interval_max_ms\n",
+        );
+        for sample in &self.samples {
+            let _ = writeln!(
+                csv,
+                "{:.3},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                sample.elapsed_secs,
+                sample.reason,
+                sample.rss_bytes,
+                sample.virtual_bytes,
+                optional(sample.threads),
+                sample.windows,
+                sample.loaded_players,
+                sample.queued_chunks,
+                sample.underruns,
+                optional_ms(sample.tick_interval_ms),
+                optional_ms(sample.tick_interval_max_ms),
+                optional_ms(sample.tick_work_ms),
+                optional_ms(sample.frame_interval_ms),
+                optional_ms(sample.frame_interval_max_ms),
+            );
+        }
+        csv
     }
 
-    #[cfg(target_os = "macos")]
-    fn thread_count_for_pid(pid: u32) -> Option<usize> {
-        let output = Command::new("ps")
-            .args(["-M", "-p", &pid.to_string()])
-            .output()
-            .ok()?;
+    /// The history as a JSON array of samples.
+    pub(crate) fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(&self.samples)
+            .map_err(|error| format!("could not serialize diagnostics: {error}"))
+    }
 
-        if !output.status.success() {
-            return None;
+    pub(crate) fn export(&self, format: ExportFormat) -> Result<String, String> {
+        match format {
+            ExportFormat::Csv => Ok(self.to_csv()),
+            ExportFormat::Json => self.to_json(),
         }
-
-        let stdout = String::from_utf8(output.stdout).ok()?;
-        let line_count = stdout.lines().count();
-        line_count.checked_sub(1)
     }
+}
 
-    #[cfg(target_os = "linux")]
-    fn thread_count_for_pid(pid: u32) -> Option<usize> {
-        let output = Command::new("ps")
-            .args(["-L", "-p", &pid.to_string()])
-            .output()
-            .ok()?;
+/// Durations collected between two samples.
+#[derive(Debug, Clone, Copy, Default)]
+struct Timings {
+    count: u32,
+    total: Duration,
+    max: Duration,
+}
 
-        if !output.status.success() {
-            return None;
-        }
+impl Timings {
+    fn add(&mut self, duration: Duration) {
+        self.count += 1;
+        self.total += duration;
+        self.max = self.max.max(duration);
+    }
 
-        let stdout = String::from_utf8(output.stdout).ok()?;
-        let line_count = stdout.lines().count();
-        line_count.checked_sub(1)
+    fn average_ms(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.total.as_secs_f64() * 1000.0 / f64::from(self.count))
     }
 
-    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
-    fn thread_count_for_pid(_pid: u32) -> Option<usize> {
-        None
+    fn max_ms(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.max.as_secs_f64() * 1000.0)
     }
 }
 
-#[cfg(not(feature = "debug"))]
-mod disabled {
-    pub(crate) struct MemorySampler;
+pub(crate) fn bytes_to_mb(bytes: u64) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
 
-    impl MemorySampler {
-        pub(crate) fn from_feat() -> Option<Self> {
-            None
-        }
+fn optional(value: Option<usize>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
 
-        pub(crate) fn maybe_log_periodic(&mut self, _windows: usize, _loaded_players: usize) {}
+fn optional_ms(value: Option<f64>) -> String {
+    value.map(|value| format!("{value:.2}")).unwrap_or_default()
+}
 
-        pub(crate) fn log_event(&mut self, _reason: &str, _windows: usize, _loaded_players: usize) {
-        }
+#[cfg(target_os = "macos")]
+fn thread_count_for_pid(pid: u32) -> Option<usize> {
+    let output = std::process::Command::new("ps")
+        .args(["-M", "-p", &pid.to_string()])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
     }
+
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    let line_count = stdout.lines().count();
+    line_count.checked_sub(1)
 }
 
-#[cfg(not(feature = "debug"))]
-pub(crate) use disabled::MemorySampler;
+/// Reads the process's task directory, which is far cheaper than running
+/// `ps` every second.
+#[cfg(target_os = "linux")]
+fn thread_count_for_pid(pid: u32) -> Option<usize> {
+    std::fs::read_dir(format!("/proc/{pid}/task"))
+        .ok()
+        .map(Iterator::count)
+}
 
-#[cfg(feature = "debug")]
-pub(crate) use enabled::MemorySampler;
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn thread_count_for_pid(_pid: u32) -> Option<usize> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_export_as_csv_rows_with_blank_missing_timings() {
+        let mut sampler = MemorySampler::new();
+        let counts = PlayerCounts {
+            windows: 2,
+            loaded_players: 1,
+            queued_chunks: 4,
+            underruns: 0,
+        };
+        let started = Instant::now();
+        sampler.record_tick(started, Duration::from_millis(2));
+        sampler.record_tick(
+            started + Duration::from_millis(16),
+            Duration::from_millis(4),
+        );
+        sampler.sample_event("window_opened", counts);
+        sampler.sample_event("window_closed", counts);
+
+        let csv = sampler.to_csv();
+        let rows: Vec<Vec<&str>> = csv.lines().map(|line| line.split(',').collect()).collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].len(), rows[1].len());
+
+        let opened = &rows[1];
+        assert_eq!(opened[1], "window_opened");
+        assert_eq!(&opened[5..9], ["2", "1", "4", "0"]);
+        assert_eq!(&opened[9..12], ["16.00", "16.00", "3.00"]);
+        assert_eq!(&opened[12..], ["", ""]);
+
+        // Timings restart with each sample.
+        assert_eq!(&rows[2][9..], ["", "", "", "", ""]);
+    }
+}
@@ -0,0 +1,182 @@
+use iced::widget::{Space, button, column, container, mouse_area, row, rule, scrollable, text};
+use iced::{Alignment, Element, Length, Padding, window};
+
+use crate::app::messages::Message;
+use crate::app::state::{PlayerWindowState, ProteusApp};
+use crate::app::styles::{MENU_BAR_HEIGHT, menu_header_style, menu_surface_style};
+use crate::native_menu::{MENUS, MenuAction, MenuEntry};
+
+const MENU_TITLE_WIDTH: f32 = 64.0;
+const DROPDOWN_WIDTH: f32 = 230.0;
+
+/// The in-window counterpart of `NativeMenu` for platforms without a global
+/// menu bar, built from the same `MENUS` table. The macOS-only app menu
+/// items (Services, Hide, …) and the Edit menu, which text inputs already
+/// handle, are left out.
+pub(crate) fn menu_bar<'a>(
+    state: &'a ProteusApp,
+    window: &'a PlayerWindowState,
+    window_id: window::Id,
+) -> Element<'a, Message> {
+    let titles = MENUS
+        .iter()
+        .enumerate()
+        .fold(row![], |titles, (index, (title, _))| {
+            let style = if window.open_menu == Some(index) {
+                button::secondary
+            } else {
+                button::text
+            };
+
+            titles.push(
+                button(text(*title).size(12))
+                    .style(style)
+                    .padding([2, 8])
+                    .width(Length::Fixed(MENU_TITLE_WIDTH))
+                    .on_press(Message::MenuBarPressed {
+                        window_id,
+                        menu: index,
+                    }),
+            )
+        });
+
+    container(titles.align_y(Alignment::Center))
+        .width(Length::Fill)
+        .height(Length::Fixed(MENU_BAR_HEIGHT))
+        .style(menu_header_style(state.palette))
+        .into()
+}
+
+/// The open dropdown, positioned under its title, over a layer that closes
+/// the menu when clicked.
+pub(crate) fn menu_dropdown<'a>(
+    state: &'a ProteusApp,
+    window: &'a PlayerWindowState,
+    window_id: window::Id,
+) -> Option<Element<'a, Message>> {
+    let index = window.open_menu?;
+    let (_, entries) = MENUS.get(index)?;
+    let palette = state.palette;
+    let playback = window.playback_menu_state();
+
+    let items = entries
+        .iter()
+        .fold(column![].spacing(1), |items, entry| match entry {
+            MenuEntry::Item(label, action) => {
+                let label = match action {
+                    MenuAction::PlayPause => playback.play_pause_label(),
+                    _ => label,
+                };
+                let label = if playback.is_checked(action) {
+                    format!("✓ {label}")
+                } else {
+                    label.to_string()
+                };
+                items.push(menu_item(
+                    state,
+                    label,
+                    window_id,
+                    action.clone(),
+                    playback.is_enabled(action),
+                ))
+            }
+            MenuEntry::Separator => items.push(rule::horizontal(1)),
+            MenuEntry::OpenWindows => {
+                state
+                    .window_menu_entries()
+                    .into_iter()
+                    .fold(items, |items, entry| {
+                        items.push(menu_item(
+                            state,
+                            entry.label(),
+                            window_id,
+                            MenuAction::FocusWindow(entry.id),
+                            true,
+                        ))
+                    })
+            }
+            MenuEntry::OpenRecent => {
+                let recent_files = state.recent_files();
+                let marker = if window.recent_submenu_open {
+                    "▾"
+                } else {
+                    "▸"
+                };
+                let header = button(
+                    row![
+                        text("Open Recent").size(12).width(Length::Fill),
+                        text(marker).size(11).color(palette.accent_text)
+                    ]
+                    .align_y(Alignment::Center),
+                )
+                .style(button::text)
+                .padding([2, 6])
+                .width(Length::Fill)
+                .on_press_maybe(
+                    (!recent_files.is_empty()).then_some(Message::RecentSubmenuPressed(window_id)),
+                );
+
+                let items = items.push(header);
+                if !window.recent_submenu_open {
+                    return items;
+                }
+
+                recent_files.iter().fold(items, |items, path| {
+                    let name = path
+                        .file_name()
+                        .unwrap_or(path.as_os_str())
+                        .to_string_lossy();
+                    items.push(
+                        container(menu_item(
+                            state,
+                            name.into_owned(),
+                            window_id,
+                            MenuAction::OpenRecent(path.clone()),
+                            true,
+                        ))
+                        .padding(Padding::ZERO.left(12)),
+                    )
+                })
+            }
+        });
+
+    let dropdown = container(scrollable(items))
+        .padding(4)
+        .width(Length::Fixed(DROPDOWN_WIDTH))
+        .style(menu_surface_style(palette));
+
+    let dismiss = mouse_area(Space::new().width(Length::Fill).height(Length::Fill))
+        .on_press(Message::MenuBarDismissed(window_id));
+
+    let positioned = container(dropdown).padding(Padding {
+        top: MENU_BAR_HEIGHT,
+        left: MENU_TITLE_WIDTH * index as f32,
+        ..Padding::ZERO
+    });
+
+    Some(iced::widget::stack![dismiss, positioned].into())
+}
+
+fn menu_item<'a>(
+    state: &'a ProteusApp,
+    label: String,
+    window_id: window::Id,
+    action: MenuAction,
+    enabled: bool,
+) -> Element<'a, Message> {
+    let shortcut = state.keybindings.menu_shortcut(&action).unwrap_or_default();
+
+    button(
+        row![
+            text(label).size(12).width(Length::Fill),
+            text(shortcut).size(11).color(state.palette.accent_text)
+        ]
+        .align_y(Alignment::Center)
+        .width(Length::Fill),
+    )
+    .style(button::text)
+    .padding([2, 6])
+    .width(Length::Fill)
+    .on_press_maybe(enabled.then_some(Message::WindowMenuAction { window_id, action }))
+    .into()
+}
@@ -1,8 +1,18 @@
 use std::path::PathBuf;
+use std::time::Instant;
 
-use iced::window;
+use iced::keyboard::{Key, Modifiers};
+use iced::{Point, Size, theme, window};
 
+use crate::app::context_menu::{ContextAction, ContextMenuKind};
+use crate::app::keybindings::{Action, KeyChord};
+use crate::app::library::{FolderContents, LibraryEntry, LibraryIndex, LibrarySort};
+use crate::app::memory::ExportFormat;
+use crate::app::properties::FileProperties;
+use crate::app::settings::SettingChange;
+use crate::app::theme::UserTheme;
 use crate::native_menu::MenuAction;
+use crate::waveform::Waveform;
 
 #[derive(Debug, Clone)]
 pub(crate) enum Message {
@@ -11,6 +21,8 @@ pub(crate) enum Message {
     WindowFocused(window::Id),
     WindowCloseRequested(window::Id),
     WindowClosed(window::Id),
+    WindowResized(window::Id, Size),
+    WindowMoved(window::Id, Point),
     TimelineChanged {
         window_id: window::Id,
         percent: f64,
@@ -21,6 +33,8 @@ pub(crate) enum Message {
     },
     PlayPausePressed(window::Id),
     ResetPressed(window::Id),
+    StopShortcut(window::Id),
+    RestartShortcut(window::Id),
     ShufflePressed(window::Id),
     #[cfg(not(target_os = "macos"))]
     FilePicked {
@@ -41,18 +55,143 @@ pub(crate) enum Message {
         generation: u64,
         result: Result<(), String>,
     },
+    ShowLibrary,
+    LibraryLoaded(Result<LibraryIndex, String>),
+    LibraryPersisted {
+        generation: u64,
+        result: Result<(), String>,
+    },
+    LibraryScanned {
+        generation: u64,
+        entries: Vec<LibraryEntry>,
+    },
+    LibrarySearchChanged(String),
+    LibrarySortPressed(LibrarySort),
+    LibraryAddFolderPressed,
+    LibraryFolderPicked(Option<PathBuf>),
+    LibraryRemoveFolderPressed(PathBuf),
+    LibraryRescanPressed,
+    LibraryEntrySelected(PathBuf),
+    LibraryEntryActivated(PathBuf),
+    ShowPreferences,
+    SettingChanged(SettingChange),
+    ResetSettingsPressed,
+    SettingsPersisted {
+        generation: u64,
+        result: Result<(), String>,
+    },
+    SessionPersisted(Result<(), String>),
+    UserThemesLoaded(Result<Vec<UserTheme>, String>),
+    SystemThemeChanged(theme::Mode),
+    BookmarksPersisted {
+        generation: u64,
+        result: Result<(), String>,
+    },
+    CursorMoved {
+        window_id: window::Id,
+        position: Point,
+    },
+    ContextMenuOpened {
+        window_id: window::Id,
+        kind: ContextMenuKind,
+    },
+    ContextMenuDismissed(window::Id),
+    ContextMenuAction {
+        window_id: window::Id,
+        action: ContextAction,
+    },
+    PropertiesLoaded {
+        window_id: window::Id,
+        result: Result<FileProperties, String>,
+    },
+    PropertiesClosed(window::Id),
+    FolderScanned {
+        window_id: window::Id,
+        path: PathBuf,
+        contents: FolderContents,
+    },
+    WaveformRead {
+        window_id: window::Id,
+        path: PathBuf,
+        result: Result<Waveform, String>,
+    },
+    QueueEntryPressed {
+        window_id: window::Id,
+        path: PathBuf,
+    },
+    PartLevelChanged {
+        window_id: window::Id,
+        slot: usize,
+        level: f32,
+    },
+    ResumePositionsPersisted {
+        generation: u64,
+        result: Result<(), String>,
+    },
+    ResumePressed(window::Id),
+    StartFromBeginningPressed(window::Id),
+    KeyPressed {
+        window_id: window::Id,
+        key: Key,
+        modifiers: Modifiers,
+    },
+    ShortcutRecordPressed(Action),
+    ShortcutRemovePressed(Action, KeyChord),
+    ShortcutResetPressed(Action),
+    KeybindingsPersisted {
+        generation: u64,
+        result: Result<(), String>,
+    },
     PlayPauseShortcut(window::Id),
-    SeekByShortcut {
+    SeekStepShortcut {
+        window_id: window::Id,
+        forward: bool,
+    },
+    VolumeStepShortcut {
+        window_id: window::Id,
+        up: bool,
+    },
+    MuteShortcut(window::Id),
+    LoopShortcut(window::Id),
+    SiblingFileShortcut {
         window_id: window::Id,
-        offset: f64,
+        forward: bool,
     },
+    SiblingFileFound {
+        window_id: window::Id,
+        path: Option<PathBuf>,
+    },
+    CancelLoadPressed(window::Id),
+    CopyErrorDetailsPressed(window::Id),
+    ErrorDismissed(window::Id),
     NewWindowShortcut(window::Id),
     OpenShortcut(window::Id),
     CloseWindowShortcut(window::Id),
     ZoomInShortcut(window::Id),
     ZoomOutShortcut(window::Id),
-    _ToggleWindowMenu(window::Id),
-    _WindowMenuAction {
+    ToggleShortcuts(window::Id),
+    ToggleNotifications(window::Id),
+    NotificationDismissed(u64),
+    NotificationsCleared,
+    ToggleDiagnostics(window::Id),
+    FrameRendered(Instant),
+    ExportDiagnosticsPressed(ExportFormat),
+    DiagnosticsExported(Result<Option<PathBuf>, String>),
+    ToggleCommandPalette(window::Id),
+    ToggleMiniPlayer(window::Id),
+    ToggleVisualizer(window::Id),
+    MiniPlayerDragged(window::Id),
+    CommandPaletteChanged(String),
+    CommandPaletteKey(window::Id, Key),
+    CommandPaletteSubmitted,
+    CommandPaletteItemPressed(usize),
+    MenuBarPressed {
+        window_id: window::Id,
+        menu: usize,
+    },
+    MenuBarDismissed(window::Id),
+    RecentSubmenuPressed(window::Id),
+    WindowMenuAction {
         window_id: window::Id,
         action: MenuAction,
     },
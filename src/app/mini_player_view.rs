@@ -0,0 +1,58 @@
+use iced::widget::text::Wrapping;
+use iced::widget::{button, column, container, mouse_area, progress_bar, row, svg, text};
+use iced::{Alignment, Element, Length, window};
+
+use crate::app::messages::Message;
+use crate::app::state::{PlayerWindowState, ProteusApp};
+use crate::app::styles::{background_style, icon_style, progress_style};
+
+/// The borderless mini player. Dragging anywhere moves the window and a
+/// double-click returns to the full player.
+pub(crate) fn mini_player_view<'a>(
+    state: &'a ProteusApp,
+    window: &'a PlayerWindowState,
+    window_id: window::Id,
+) -> Element<'a, Message> {
+    let palette = state.palette;
+    let play_icon = if window.playing {
+        state.icons.pause.clone()
+    } else {
+        state.icons.play.clone()
+    };
+
+    let content = row![
+        button(
+            svg(play_icon)
+                .width(20)
+                .height(20)
+                .style(icon_style(palette))
+        )
+        .style(button::text)
+        .padding(0)
+        .on_press(Message::PlayPausePressed(window_id)),
+        column![
+            text(window.window_title.clone())
+                .size(12)
+                .wrapping(Wrapping::None),
+            progress_bar(0.0..=100.0, window.current_time_percent as f32)
+                .girth(3)
+                .style(progress_style(palette)),
+        ]
+        .spacing(5)
+        .width(Length::Fill),
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center);
+
+    mouse_area(
+        container(content)
+            .padding([6, 10])
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center_y(Length::Fill)
+            .style(background_style(palette)),
+    )
+    .on_press(Message::MiniPlayerDragged(window_id))
+    .on_double_click(Message::ToggleMiniPlayer(window_id))
+    .into()
+}
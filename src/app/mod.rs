@@ -1,35 +1,99 @@
+mod bookmarks;
+mod bookmarks_store;
+mod command_palette;
+mod command_palette_view;
+mod context_menu;
+mod context_menu_view;
+mod diagnostics_view;
 mod effects;
+mod error_report;
+mod error_view;
+mod expanded_view;
+mod file_watch;
 mod helpers;
 mod icons;
+mod keybindings;
+mod keybindings_store;
+mod layout;
+mod library;
+mod library_store;
+mod library_view;
+mod link;
+mod logging;
 mod memory;
+mod menu_bar_view;
 mod messages;
+mod mini_player_view;
+mod notifications;
+mod notifications_view;
+mod perf_script;
+mod preferences_view;
+mod properties;
+mod properties_view;
 mod recent_files_store;
+mod resume;
+mod resume_store;
+mod session;
+mod session_store;
+mod settings;
+mod settings_store;
+mod shortcuts_view;
 mod state;
+pub(crate) mod storage;
+mod stored_path;
 mod styles;
+mod theme;
 mod view;
+mod visualizer_view;
 mod widgets;
 
-use std::path::PathBuf;
-use std::time::Duration;
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use iced::event;
 use iced::keyboard;
 use iced::task::Task;
-use iced::{Subscription, Theme, daemon, time, window};
+use iced::{Subscription, Theme, daemon, system, time, window};
+use log::LevelFilter;
 
-use crate::app::helpers::handle_key_press;
+use crate::app::command_palette::PaletteCommand;
+use crate::app::helpers::{action_message, handle_key_press};
 use crate::app::messages::Message;
+use crate::app::notifications::Severity;
 use crate::app::state::ProteusApp;
+use crate::native_menu;
+use crate::playback;
 
 pub fn install_startup_integrations() {
     let _ = effects::ensure_macos_open_file_handler();
 }
 
-pub fn run(initial_path: Option<PathBuf>) -> iced::Result {
+/// Options parsed from the command line.
+#[derive(Debug, Clone, Default)]
+pub struct LaunchOptions {
+    pub initial_path: Option<PathBuf>,
+    pub restore_session: bool,
+    /// Most detailed level written to the log file, `info` when unset.
+    pub log_level: Option<LevelFilter>,
+    /// Run this performance script headlessly instead of opening windows.
+    pub perf_script: Option<PathBuf>,
+}
+
+pub fn run(options: LaunchOptions) -> iced::Result {
+    logging::init(options.log_level.unwrap_or(logging::DEFAULT_LEVEL));
+    log::info!(
+        "startup version={} os={} arch={} options={options:?}",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    playback::remove_stale_path_aliases();
+
     daemon(
         move || {
-            let mut app = ProteusApp::new();
-            let task = initial_boot_task(&mut app, initial_path.clone());
+            let mut app = load_app();
+            let task = initial_boot_task(&mut app, options.clone());
             (app, task)
         },
         update,
@@ -42,12 +106,27 @@ pub fn run(initial_path: Option<PathBuf>) -> iced::Result {
     .run()
 }
 
+/// Runs a `--perf-script` without starting the UI, returning the process
+/// exit code.
+pub fn run_perf_script(script: &Path, log_level: Option<LevelFilter>) -> i32 {
+    logging::init(log_level.unwrap_or(logging::DEFAULT_LEVEL));
+    perf_script::run(script)
+}
+
 fn update(state: &mut ProteusApp, message: Message) -> Task<Message> {
+    if !matches!(message, Message::Tick | Message::CursorMoved { .. }) {
+        log::trace!("update message={message:?}");
+    }
+
     match message {
         Message::Tick => {
+            let tick_started = Instant::now();
+            state.notifications.expire(tick_started);
             state.ensure_app_icon();
             state.ensure_native_menu();
+            state.sync_link();
             state.refresh_windows();
+            state.poll_loads();
 
             let mut tasks = Vec::new();
 
@@ -66,8 +145,55 @@ fn update(state: &mut ProteusApp, message: Message) -> Task<Message> {
                 tasks.push(effects::persist_recent_files(generation, files));
             }
 
+            if let Some((generation, settings)) = state.take_settings_to_persist() {
+                tasks.push(effects::persist_settings(generation, settings));
+            }
+
+            if let Some(session) = state.take_session_to_persist() {
+                tasks.push(effects::persist_session(session));
+            }
+
+            if let Some((generation, file)) = state.take_keybindings_to_persist() {
+                tasks.push(effects::persist_keybindings(generation, file));
+            }
+
+            for (window_id, path) in state.take_folder_scans() {
+                tasks.push(effects::scan_folder(path.clone()).map(move |contents| {
+                    Message::FolderScanned {
+                        window_id,
+                        path: path.clone(),
+                        contents,
+                    }
+                }));
+            }
+
+            for (window_id, path) in state.take_waveform_reads() {
+                tasks.push(effects::read_waveform(path.clone()).map(move |result| {
+                    Message::WaveformRead {
+                        window_id,
+                        path: path.clone(),
+                        result,
+                    }
+                }));
+            }
+
+            if let Some((generation, bookmarks)) = state.take_bookmarks_to_persist() {
+                tasks.push(effects::persist_bookmarks(generation, bookmarks));
+            }
+
+            if let Some((generation, positions)) = state.take_resume_positions_to_persist() {
+                tasks.push(effects::persist_resume_positions(generation, positions));
+            }
+
+            if let Some((generation, index)) = state.take_library_to_persist() {
+                tasks.push(effects::persist_library(generation, index));
+            }
+
             if let Err(err) = effects::ensure_macos_open_file_handler() {
-                state.global_error = Some(format!("Failed to install file-open handler: {err}"));
+                state.notify(
+                    Severity::Error,
+                    format!("Failed to install file-open handler: {err}"),
+                );
             }
 
             let opened_paths = effects::take_macos_opened_files();
@@ -76,34 +202,77 @@ fn update(state: &mut ProteusApp, message: Message) -> Task<Message> {
             }
 
             let mut actions = Vec::new();
-            if let Some(menu) = &state.native_menu {
-                while let Some(action) = menu.poll_action() {
+            let mut tray_actions = Vec::new();
+            while let Some(id) = native_menu::next_menu_event() {
+                if let Some(action) = state
+                    .native_menu
+                    .as_ref()
+                    .and_then(|menu| menu.action_for(&id))
+                {
                     actions.push(action);
+                } else if let Some(action) =
+                    state.tray.as_ref().and_then(|tray| tray.action_for(&id))
+                {
+                    tray_actions.push(action);
                 }
             }
+            if let Some(tray) = &state.tray {
+                tray_actions.extend(iter::from_fn(|| tray.poll_action()));
+            }
 
             for action in actions {
                 tasks.push(state.handle_menu_action(action));
             }
+            for action in tray_actions {
+                tasks.push(state.handle_tray_action(action));
+            }
+            state.sync_playback_menu();
+            state.sync_window_menu();
+            state.sync_tray();
+
+            if let Some(task) = state.poll_themes_file() {
+                tasks.push(task);
+            }
+
+            state.poll_loaded_files();
+
+            if let Some(task) = state.poll_link() {
+                tasks.push(task);
+            }
 
             if let Some(task) = state.maybe_startup_open_dialog_task() {
                 tasks.push(task);
             }
 
+            state
+                .memory_sampler
+                .record_tick(tick_started, tick_started.elapsed());
             Task::batch(tasks)
         }
         Message::WindowOpened(window_id) | Message::WindowFocused(window_id) => {
             state.set_focused_window(window_id);
             Task::none()
         }
+        Message::WindowResized(window_id, size) => {
+            state.window_resized(window_id, size);
+            Task::none()
+        }
+        Message::WindowMoved(window_id, position) => {
+            state.window_moved(window_id, position);
+            Task::none()
+        }
+        Message::ToggleMiniPlayer(window_id) => state.toggle_mini_player(window_id),
+        Message::ToggleVisualizer(window_id) => state.toggle_visualizer(window_id),
+        Message::MiniPlayerDragged(window_id) => window::drag(window_id),
         Message::WindowCloseRequested(window_id) => {
             state.close_window_state(window_id);
             window::close(window_id)
         }
         Message::WindowClosed(window_id) => {
             state.close_window_state(window_id);
-            if state.windows.is_empty() {
-                if should_exit_on_last_window_close() {
+            if !state.has_open_windows() {
+                if should_exit_on_last_window_close() && !state.keeps_running_in_tray() {
+                    state.save_before_exit();
                     iced::exit()
                 } else {
                     Task::none()
@@ -126,6 +295,7 @@ fn update(state: &mut ProteusApp, message: Message) -> Task<Message> {
         }
         Message::PlayPausePressed(window_id) | Message::PlayPauseShortcut(window_id) => {
             if let Some(window) = state.window_mut(window_id) {
+                window.resume_offer = None;
                 window.playback.play_pause();
             }
             Task::none()
@@ -136,6 +306,20 @@ fn update(state: &mut ProteusApp, message: Message) -> Task<Message> {
             }
             Task::none()
         }
+        Message::StopShortcut(window_id) => {
+            if let Some(window) = state.window_mut(window_id) {
+                window.resume_offer = None;
+                window.playback.rewind();
+            }
+            Task::none()
+        }
+        Message::RestartShortcut(window_id) => {
+            if let Some(window) = state.window_mut(window_id) {
+                window.resume_offer = None;
+                window.playback.start_at(0.0, true);
+            }
+            Task::none()
+        }
         Message::ShufflePressed(window_id) => {
             if let Some(window) = state.window_mut(window_id) {
                 window.playback.shuffle();
@@ -169,32 +353,367 @@ fn update(state: &mut ProteusApp, message: Message) -> Task<Message> {
             state.recent_files_persisted(generation, result);
             Task::none()
         }
-        Message::SeekByShortcut { window_id, offset } => {
+        Message::ShowLibrary => state.show_library(),
+        Message::LibraryLoaded(result) => state.library_loaded(result),
+        Message::LibraryPersisted { generation, result } => {
+            state.library_persisted(generation, result);
+            Task::none()
+        }
+        Message::LibraryScanned {
+            generation,
+            entries,
+        } => {
+            state.library_scanned(generation, entries);
+            Task::none()
+        }
+        Message::LibrarySearchChanged(search) => {
+            state.library.search = search;
+            Task::none()
+        }
+        Message::LibrarySortPressed(sort) => {
+            state.library.set_sort(sort);
+            Task::none()
+        }
+        Message::LibraryAddFolderPressed => state.start_library_folder_dialog(),
+        Message::LibraryFolderPicked(folder) => state.add_library_folder(folder),
+        Message::LibraryRemoveFolderPressed(folder) => state.remove_library_folder(folder),
+        Message::LibraryRescanPressed => state.rescan_library(),
+        Message::LibraryEntrySelected(path) => {
+            state.library.selected = Some(path);
+            Task::none()
+        }
+        Message::LibraryEntryActivated(path) => {
+            state.library.selected = Some(path.clone());
+            state.handle_external_open_path(path)
+        }
+        Message::ShowPreferences => state.show_preferences(),
+        Message::SettingChanged(change) => {
+            state.change_setting(change);
+            Task::none()
+        }
+        Message::ResetSettingsPressed => {
+            state.reset_settings();
+            Task::none()
+        }
+        Message::SettingsPersisted { generation, result } => {
+            state.settings_persisted(generation, result);
+            Task::none()
+        }
+        Message::SessionPersisted(result) => {
+            state.session_persisted(result);
+            Task::none()
+        }
+        Message::UserThemesLoaded(result) => {
+            state.user_themes_loaded(result);
+            Task::none()
+        }
+        Message::SystemThemeChanged(mode) => {
+            state.set_system_theme(mode);
+            Task::none()
+        }
+        Message::ResumePositionsPersisted { generation, result } => {
+            state.resume_positions_persisted(generation, result);
+            Task::none()
+        }
+        Message::BookmarksPersisted { generation, result } => {
+            state.bookmarks_persisted(generation, result);
+            Task::none()
+        }
+        Message::CursorMoved {
+            window_id,
+            position,
+        } => {
             if let Some(window) = state.window_mut(window_id) {
-                window.playback.seek_by(offset);
+                window.set_cursor_position(position);
+            }
+            Task::none()
+        }
+        Message::ContextMenuOpened { window_id, kind } => {
+            state.set_focused_window(window_id);
+            if let Some(window) = state.window_mut(window_id) {
+                window.open_context_menu(kind);
+            }
+            Task::none()
+        }
+        Message::ContextMenuDismissed(window_id) => {
+            if let Some(window) = state.window_mut(window_id) {
+                window.context_menu = None;
+            }
+            Task::none()
+        }
+        Message::ContextMenuAction { window_id, action } => {
+            state.handle_context_action(window_id, action)
+        }
+        Message::PropertiesLoaded { window_id, result } => {
+            state.properties_loaded(window_id, result);
+            Task::none()
+        }
+        Message::FolderScanned {
+            window_id,
+            path,
+            contents,
+        } => {
+            state.folder_scanned(window_id, path, contents);
+            Task::none()
+        }
+        Message::WaveformRead {
+            window_id,
+            path,
+            result,
+        } => {
+            state.waveform_read(window_id, path, result);
+            Task::none()
+        }
+        Message::QueueEntryPressed { window_id, path } => {
+            state.sibling_file_found(window_id, Some(path))
+        }
+        Message::PartLevelChanged {
+            window_id,
+            slot,
+            level,
+        } => {
+            if let Some(window) = state.window_mut(window_id) {
+                window.set_part_level(slot, level);
+            }
+            Task::none()
+        }
+        Message::PropertiesClosed(window_id) => {
+            if let Some(window) = state.window_mut(window_id) {
+                window.properties_open = false;
+            }
+            Task::none()
+        }
+        Message::ResumePressed(window_id) => {
+            state.accept_resume_offer(window_id);
+            Task::none()
+        }
+        Message::StartFromBeginningPressed(window_id) => {
+            state.decline_resume_offer(window_id);
+            Task::none()
+        }
+        Message::KeyPressed {
+            window_id,
+            key,
+            modifiers,
+        } => {
+            // Keys typed into an open palette belong to it, apart from the
+            // chord that toggles it closed again.
+            if state
+                .command_palette
+                .as_ref()
+                .is_some_and(|palette| palette.window_id == window_id)
+            {
+                let toggles_palette = keybindings::KeyChord::from_key_press(&key, modifiers)
+                    .and_then(|chord| state.keybindings.action_for(&chord))
+                    == Some(keybindings::Action::CommandPalette);
+                return if toggles_palette {
+                    state.toggle_command_palette(window_id)
+                } else {
+                    Task::none()
+                };
+            }
+
+            // Shortcuts are recorded from the preferences window only; keys
+            // pressed in player windows keep working meanwhile.
+            if state.recording_shortcut.is_some() && state.is_preferences_window(window_id) {
+                if let Some(chord) = keybindings::KeyChord::from_key_press(&key, modifiers) {
+                    state.record_shortcut(chord);
+                }
+                return Task::none();
+            }
+
+            // Escape dismisses the cheat sheet, notification history and
+            // diagnostics whatever it is bound to.
+            if let Some(window) = state.window_mut(window_id)
+                && (window.shortcuts_open || window.notifications_open || window.diagnostics_open)
+                && matches!(key, keyboard::Key::Named(keyboard::key::Named::Escape))
+            {
+                window.shortcuts_open = false;
+                window.notifications_open = false;
+                window.diagnostics_open = false;
+                return Task::none();
+            }
+
+            if matches!(key, keyboard::Key::Named(keyboard::key::Named::Escape))
+                && (state.close_menu_bar(window_id) || state.close_window_popup(window_id))
+            {
+                return Task::none();
+            }
+
+            // Escape also leaves the visualizer, as it does other fullscreen views.
+            if matches!(key, keyboard::Key::Named(keyboard::key::Named::Escape))
+                && state
+                    .windows
+                    .get(&window_id)
+                    .is_some_and(|window| window.visualizer)
+            {
+                return state.toggle_visualizer(window_id);
+            }
+
+            match handle_key_press(&state.keybindings, window_id, &key, modifiers) {
+                Some(message) => update(state, message),
+                None => Task::none(),
+            }
+        }
+        Message::ShortcutRecordPressed(action) => {
+            state.start_recording_shortcut(action);
+            Task::none()
+        }
+        Message::ShortcutRemovePressed(action, chord) => {
+            state.remove_shortcut(action, chord);
+            Task::none()
+        }
+        Message::ShortcutResetPressed(action) => {
+            state.reset_shortcut(action);
+            Task::none()
+        }
+        Message::KeybindingsPersisted { generation, result } => {
+            state.keybindings_persisted(generation, result);
+            Task::none()
+        }
+        Message::VolumeStepShortcut { window_id, up } => {
+            if let Some(window) = state.window_mut(window_id) {
+                window.step_volume(up);
+            }
+            Task::none()
+        }
+        Message::MuteShortcut(window_id) => {
+            if let Some(window) = state.window_mut(window_id) {
+                window.toggle_mute();
+            }
+            Task::none()
+        }
+        Message::LoopShortcut(window_id) => {
+            if let Some(window) = state.window_mut(window_id) {
+                window.toggle_loop();
+            }
+            Task::none()
+        }
+        Message::SiblingFileShortcut { window_id, forward } => {
+            state.open_sibling_file(window_id, forward)
+        }
+        Message::SiblingFileFound { window_id, path } => state.sibling_file_found(window_id, path),
+        Message::CancelLoadPressed(window_id) => {
+            if let Some(window) = state.window_mut(window_id) {
+                window.cancel_load();
+            }
+            Task::none()
+        }
+        Message::CopyErrorDetailsPressed(window_id) => state
+            .windows
+            .get(&window_id)
+            .and_then(|window| window.error_report.as_ref())
+            .map_or_else(Task::none, |report| {
+                iced::clipboard::write(report.details_text())
+            }),
+        Message::ErrorDismissed(window_id) => {
+            if let Some(window) = state.window_mut(window_id) {
+                window.error_report = None;
+            }
+            Task::none()
+        }
+        Message::SeekStepShortcut { window_id, forward } => {
+            let step = state.settings.seek_step_seconds;
+            if let Some(window) = state.window_mut(window_id) {
+                window.playback.seek_by(if forward { step } else { -step });
             }
             Task::none()
         }
         Message::ZoomInShortcut(window_id) => {
             if let Some(window) = state.window_mut(window_id) {
-                window.zoom_factor = (window.zoom_factor + 0.1).min(2.0);
+                window.zoom_factor = (window.zoom_factor + 0.1).min(settings::MAX_ZOOM);
             }
             Task::none()
         }
         Message::ZoomOutShortcut(window_id) => {
             if let Some(window) = state.window_mut(window_id) {
-                window.zoom_factor = (window.zoom_factor - 0.1).max(0.5);
+                window.zoom_factor = (window.zoom_factor - 0.1).max(settings::MIN_ZOOM);
             }
             Task::none()
         }
-        Message::_ToggleWindowMenu(window_id) => {
+        Message::ToggleCommandPalette(window_id) => state.toggle_command_palette(window_id),
+        Message::CommandPaletteChanged(query) => {
+            state.search_command_palette(query);
+            Task::none()
+        }
+        Message::CommandPaletteKey(window_id, key) => {
+            state.command_palette_key(window_id, key);
+            Task::none()
+        }
+        Message::CommandPaletteSubmitted => match state.take_command_palette_choice(None) {
+            Some((window_id, command)) => run_palette_command(state, window_id, command),
+            None => Task::none(),
+        },
+        Message::CommandPaletteItemPressed(index) => {
+            match state.take_command_palette_choice(Some(index)) {
+                Some((window_id, command)) => run_palette_command(state, window_id, command),
+                None => Task::none(),
+            }
+        }
+        Message::ToggleShortcuts(window_id) => {
+            state.toggle_shortcuts(window_id);
+            Task::none()
+        }
+        Message::ToggleNotifications(window_id) => {
+            state.toggle_notifications(window_id);
+            Task::none()
+        }
+        Message::NotificationDismissed(id) => {
+            state.notifications.dismiss(id);
+            Task::none()
+        }
+        Message::NotificationsCleared => {
+            state.notifications.clear();
+            Task::none()
+        }
+        Message::ToggleDiagnostics(window_id) => {
+            state.toggle_diagnostics(window_id);
+            Task::none()
+        }
+        Message::FrameRendered(at) => {
+            state.memory_sampler.record_frame(at);
+            Task::none()
+        }
+        Message::ExportDiagnosticsPressed(format) => match state.memory_sampler.export(format) {
+            Ok(contents) => effects::export_diagnostics(format, contents),
+            Err(error) => {
+                state.notify(
+                    Severity::Warning,
+                    format!("Failed to export diagnostics: {error}"),
+                );
+                Task::none()
+            }
+        },
+        Message::DiagnosticsExported(result) => {
+            match result {
+                Ok(Some(path)) => state.notify(
+                    Severity::Info,
+                    format!("Diagnostics exported to {}", path.display()),
+                ),
+                Ok(None) => {}
+                Err(error) => state.notify(
+                    Severity::Warning,
+                    format!("Failed to export diagnostics: {error}"),
+                ),
+            }
+            Task::none()
+        }
+        Message::MenuBarPressed { window_id, menu } => {
             state.set_focused_window(window_id);
-            state.toggle_window_menu(window_id);
+            state.toggle_menu_bar(window_id, menu);
             Task::none()
         }
-        Message::_WindowMenuAction { window_id, action } => {
+        Message::MenuBarDismissed(window_id) => {
+            state.close_menu_bar(window_id);
+            Task::none()
+        }
+        Message::RecentSubmenuPressed(window_id) => {
+            state.toggle_recent_submenu(window_id);
+            Task::none()
+        }
+        Message::WindowMenuAction { window_id, action } => {
             state.set_focused_window(window_id);
-            state.close_window_menu(window_id);
+            state.close_menu_bar(window_id);
             state.handle_menu_action(action)
         }
         Message::CloseWindowShortcut(window_id) => {
@@ -205,35 +724,128 @@ fn update(state: &mut ProteusApp, message: Message) -> Task<Message> {
     }
 }
 
-fn subscription(_state: &ProteusApp) -> Subscription<Message> {
+fn run_palette_command(
+    state: &mut ProteusApp,
+    window_id: window::Id,
+    command: PaletteCommand,
+) -> Task<Message> {
+    match command {
+        PaletteCommand::Action(action) => update(state, action_message(action, window_id)),
+        PaletteCommand::Menu(action) => {
+            state.set_focused_window(window_id);
+            state.handle_menu_action(action)
+        }
+        PaletteCommand::Bookmark { path, position } => {
+            state.go_to_bookmark(window_id, path, position)
+        }
+    }
+}
+
+fn subscription(state: &ProteusApp) -> Subscription<Message> {
+    // The palette's text input captures Escape and arrow keys, so they are
+    // read regardless of capture status while it is open. Chords it captured
+    // are passed on too, so the palette shortcut can close it again.
+    let palette_keys = if state.command_palette.is_some() {
+        event::listen_with(|event, status, window_id| match event {
+            iced::Event::Keyboard(keyboard::Event::KeyPressed { key, .. })
+                if matches!(
+                    key,
+                    keyboard::Key::Named(
+                        keyboard::key::Named::Escape
+                            | keyboard::key::Named::ArrowUp
+                            | keyboard::key::Named::ArrowDown
+                    )
+                ) =>
+            {
+                Some(Message::CommandPaletteKey(window_id, key))
+            }
+            iced::Event::Keyboard(keyboard::Event::KeyPressed {
+                key,
+                modifiers,
+                repeat: false,
+                ..
+            }) if status == event::Status::Captured
+                && (modifiers.command() || modifiers.control()) =>
+            {
+                Some(Message::KeyPressed {
+                    window_id,
+                    key,
+                    modifiers,
+                })
+            }
+            _ => None,
+        })
+    } else {
+        Subscription::none()
+    };
+
+    // Frames are only timed while someone is looking at the numbers.
+    let frames = if state.diagnostics_visible() {
+        window::frames().map(Message::FrameRendered)
+    } else {
+        Subscription::none()
+    };
+
     Subscription::batch([
+        palette_keys,
+        frames,
         time::every(Duration::from_millis(16)).map(|_| Message::Tick),
         window::close_requests().map(Message::WindowCloseRequested),
         window::close_events().map(Message::WindowClosed),
-        event::listen_with(|event, _status, window_id| match event {
+        window::resize_events().map(|(window_id, size)| Message::WindowResized(window_id, size)),
+        window::events().filter_map(|(window_id, event)| match event {
+            window::Event::Moved(position) => Some(Message::WindowMoved(window_id, position)),
+            _ => None,
+        }),
+        system::theme_changes().map(Message::SystemThemeChanged),
+        event::listen_with(|event, status, window_id| match event {
+            // Keys typed into a focused text input, such as the library search
+            // field, must not double as playback shortcuts.
             iced::Event::Keyboard(keyboard::Event::KeyPressed {
                 key,
                 modifiers,
                 repeat,
                 ..
-            }) if !repeat => handle_key_press(window_id, key, modifiers),
+            }) if !repeat && status == event::Status::Ignored => Some(Message::KeyPressed {
+                window_id,
+                key,
+                modifiers,
+            }),
             iced::Event::Window(window::Event::Focused) => Some(Message::WindowFocused(window_id)),
             _ => None,
         }),
     ])
 }
 
-fn app_theme(_state: &ProteusApp, _window_id: window::Id) -> Theme {
-    Theme::Dark
+fn app_theme(state: &ProteusApp, _window_id: window::Id) -> Theme {
+    state.theme.clone()
 }
 
-fn initial_boot_task(state: &mut ProteusApp, initial_path: Option<PathBuf>) -> Task<Message> {
-    let startup_task = match initial_path {
+/// Builds the app state with settings, resume positions, bookmarks and
+/// keybindings read synchronously, since they decide how the first window
+/// opens, looks, where it starts and which keys it answers to.
+fn load_app() -> ProteusApp {
+    let mut app = match settings_store::load() {
+        Ok(settings) => ProteusApp::new(settings),
+        Err(error) => {
+            let mut app = ProteusApp::new(settings::Settings::default());
+            app.notify(Severity::Error, format!("Failed to load settings: {error}"));
+            app
+        }
+    };
+    app.set_resume_positions(resume_store::load());
+    app.set_bookmarks(bookmarks_store::load());
+    app.set_keybindings(keybindings_store::load());
+    app
+}
+
+fn initial_boot_task(state: &mut ProteusApp, options: LaunchOptions) -> Task<Message> {
+    let startup_task = match options.initial_path {
         Some(path) => state.open_window(Some(path)),
         None if cfg!(target_os = "macos") => {
             let opened_paths = effects::take_macos_opened_files();
             if opened_paths.is_empty() {
-                state.schedule_startup_open_dialog(Duration::from_millis(350))
+                state.start_without_file(Duration::from_millis(350), options.restore_session)
             } else {
                 let mut tasks = Vec::with_capacity(opened_paths.len());
                 for path in opened_paths {
@@ -242,10 +854,15 @@ fn initial_boot_task(state: &mut ProteusApp, initial_path: Option<PathBuf>) -> T
                 Task::batch(tasks)
             }
         }
-        None => state.open_window(None),
+        None => state.start_without_file(Duration::from_millis(350), options.restore_session),
     };
 
-    Task::batch([effects::load_recent_files(), startup_task])
+    Task::batch([
+        system::theme().map(Message::SystemThemeChanged),
+        effects::load_recent_files(),
+        effects::load_library(),
+        startup_task,
+    ])
 }
 
 fn should_exit_on_last_window_close() -> bool {
@@ -253,6 +870,14 @@ fn should_exit_on_last_window_close() -> bool {
 }
 
 fn app_title(state: &ProteusApp, window_id: window::Id) -> String {
+    if state.is_library_window(window_id) {
+        return "Library".to_owned();
+    }
+
+    if state.is_preferences_window(window_id) {
+        return "Settings".to_owned();
+    }
+
     state
         .windows
         .get(&window_id)
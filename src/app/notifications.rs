@@ -0,0 +1,179 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How long an info or warning toast stays up before dismissing itself.
+const TOAST_DURATION: Duration = Duration::from_secs(6);
+/// Entries kept for the history panel, oldest dropped first.
+const HISTORY_LEN: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Severity {
+    Info,
+    /// Something failed but will be retried or is harmless, such as a save
+    /// that the next change writes again.
+    Warning,
+    /// Something failed that the user should know about, such as a file that
+    /// could not be read. Its toast stays up until dismissed.
+    Error,
+}
+
+impl Severity {
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Info => "Info",
+            Self::Warning => "Warning",
+            Self::Error => "Error",
+        }
+    }
+
+    fn is_transient(self) -> bool {
+        self != Self::Error
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Notification {
+    pub(crate) id: u64,
+    pub(crate) severity: Severity,
+    pub(crate) message: String,
+    /// When it last happened.
+    pub(crate) at: Instant,
+    /// How many times in a row it happened.
+    pub(crate) count: usize,
+    /// When its toast went up, while it is showing.
+    toast_since: Option<Instant>,
+}
+
+impl Notification {
+    pub(crate) fn is_toast(&self) -> bool {
+        self.toast_since.is_some()
+    }
+}
+
+/// Everything that went wrong (or is worth mentioning) this run, newest
+/// last, with the ones still showing as toasts.
+#[derive(Debug, Default)]
+pub(crate) struct Notifications {
+    entries: VecDeque<Notification>,
+    next_id: u64,
+}
+
+impl Notifications {
+    /// Records a notification and shows it as a toast. The same message
+    /// repeated is counted on the previous entry instead, and only raises
+    /// its toast again once it has been quiet for a while, so a failure
+    /// retried every tick cannot keep a dismissed toast on screen.
+    pub(crate) fn push(&mut self, severity: Severity, message: String, now: Instant) {
+        if let Some(last) = self.entries.back_mut()
+            && last.severity == severity
+            && last.message == message
+        {
+            if now.duration_since(last.at) >= TOAST_DURATION {
+                last.toast_since = Some(now);
+            }
+            last.count += 1;
+            last.at = now;
+            return;
+        }
+
+        if self.entries.len() == HISTORY_LEN {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(Notification {
+            id: self.next_id,
+            severity,
+            message,
+            at: now,
+            count: 1,
+            toast_since: Some(now),
+        });
+        self.next_id += 1;
+    }
+
+    /// Takes down toasts for transient notifications that have been up long
+    /// enough. Called every tick.
+    pub(crate) fn expire(&mut self, now: Instant) {
+        for entry in &mut self.entries {
+            if entry.severity.is_transient()
+                && entry
+                    .toast_since
+                    .is_some_and(|since| now.duration_since(since) >= TOAST_DURATION)
+            {
+                entry.toast_since = None;
+            }
+        }
+    }
+
+    /// Takes down a toast, keeping the entry in the history.
+    pub(crate) fn dismiss(&mut self, id: u64) {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.id == id) {
+            entry.toast_since = None;
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Notifications showing as toasts, oldest first.
+    pub(crate) fn toasts(&self) -> impl Iterator<Item = &Notification> {
+        self.entries.iter().filter(|entry| entry.is_toast())
+    }
+
+    /// Every notification kept, newest first.
+    pub(crate) fn history(&self) -> impl Iterator<Item = &Notification> {
+        self.entries.iter().rev()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// How long ago something happened, as the history panel shows it.
+pub(crate) fn format_age(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    match seconds {
+        0..60 => "just now".to_owned(),
+        60..3600 => format!("{} min ago", seconds / 60),
+        _ => format!("{} h ago", seconds / 3600),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warnings_expire_and_repeats_do_not_raise_a_dismissed_toast() {
+        let start = Instant::now();
+        let mut notifications = Notifications::default();
+        notifications.push(Severity::Error, "Failed to load library".to_owned(), start);
+        notifications.push(Severity::Warning, "Failed to save".to_owned(), start);
+
+        notifications.expire(start + TOAST_DURATION);
+        let toasts: Vec<&str> = notifications
+            .toasts()
+            .map(|entry| entry.message.as_str())
+            .collect();
+        assert_eq!(toasts, ["Failed to load library"]);
+
+        let error_id = notifications.history().last().map(|entry| entry.id);
+        notifications.dismiss(error_id.unwrap());
+        notifications.push(
+            Severity::Warning,
+            "Failed to save".to_owned(),
+            start + TOAST_DURATION,
+        );
+        notifications.push(
+            Severity::Warning,
+            "Failed to save".to_owned(),
+            start + TOAST_DURATION + Duration::from_secs(1),
+        );
+
+        let latest = notifications.history().next().unwrap();
+        assert_eq!(latest.count, 3);
+        assert!(latest.is_toast());
+        assert_eq!(notifications.toasts().count(), 1);
+    }
+}
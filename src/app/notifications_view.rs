@@ -0,0 +1,121 @@
+use std::time::Instant;
+
+use iced::widget::{Column, button, column, container, row, scrollable, text};
+use iced::{Alignment, Color, Element, Length, window};
+
+use crate::app::messages::Message;
+use crate::app::notifications::{Notification, Severity, format_age};
+use crate::app::state::ProteusApp;
+use crate::app::styles::{background_style, menu_surface_style};
+use crate::app::theme::Palette;
+
+const SEVERITY_WIDTH: f32 = 60.0;
+const AGE_WIDTH: f32 = 70.0;
+
+/// Toasts stacked above the bottom of every player window.
+pub(crate) fn toasts(state: &ProteusApp) -> Option<Element<'_, Message>> {
+    let palette = state.palette;
+    let toasts =
+        state
+            .notifications
+            .toasts()
+            .fold(Column::new().spacing(4), |toasts, notification| {
+                toasts.push(
+                    container(
+                        row![
+                            text(message(notification))
+                                .size(11)
+                                .color(severity_color(palette, notification.severity))
+                                .width(Length::Fill),
+                            button(text("×").size(11))
+                                .style(button::text)
+                                .padding([0, 4])
+                                .on_press(Message::NotificationDismissed(notification.id)),
+                        ]
+                        .spacing(8)
+                        .align_y(Alignment::Center),
+                    )
+                    .padding([4, 8])
+                    .width(Length::Fill)
+                    .style(menu_surface_style(palette)),
+                )
+            });
+
+    (state.notifications.toasts().next().is_some())
+        .then(|| container(toasts).padding([4, 12]).into())
+}
+
+/// Everything reported this run, newest first.
+pub(crate) fn notifications_overlay(
+    state: &ProteusApp,
+    window_id: window::Id,
+) -> Element<'_, Message> {
+    let palette = state.palette;
+    let now = Instant::now();
+
+    let header = row![
+        text("Notifications").size(13).width(Length::Fill),
+        button(text("Clear").size(11))
+            .style(button::text)
+            .padding([0, 4])
+            .on_press_maybe(
+                (!state.notifications.is_empty()).then_some(Message::NotificationsCleared)
+            ),
+        button(text("Close").size(11))
+            .style(button::text)
+            .padding([0, 4])
+            .on_press(Message::ToggleNotifications(window_id)),
+    ]
+    .spacing(4)
+    .align_y(Alignment::Center);
+
+    let rows = if state.notifications.is_empty() {
+        column![
+            text("Nothing to report.")
+                .size(11)
+                .color(palette.accent_text)
+        ]
+    } else {
+        state
+            .notifications
+            .history()
+            .fold(column![].spacing(4), |rows, notification| {
+                rows.push(
+                    row![
+                        text(notification.severity.label())
+                            .size(11)
+                            .color(severity_color(palette, notification.severity))
+                            .width(Length::Fixed(SEVERITY_WIDTH)),
+                        text(message(notification)).size(11).width(Length::Fill),
+                        text(format_age(now.duration_since(notification.at)))
+                            .size(11)
+                            .color(palette.accent_text)
+                            .width(Length::Fixed(AGE_WIDTH)),
+                    ]
+                    .spacing(8),
+                )
+            })
+    };
+
+    container(column![header, scrollable(rows).height(Length::Fill)].spacing(6))
+        .padding([8, 12])
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(background_style(palette))
+        .into()
+}
+
+fn message(notification: &Notification) -> String {
+    if notification.count > 1 {
+        format!("{} (×{})", notification.message, notification.count)
+    } else {
+        notification.message.clone()
+    }
+}
+
+fn severity_color(palette: Palette, severity: Severity) -> Color {
+    match severity {
+        Severity::Error => palette.error_text,
+        Severity::Warning | Severity::Info => palette.accent_text,
+    }
+}
@@ -0,0 +1,514 @@
+//! Headless memory and performance runs for `--perf-script <file>`.
+//!
+//! A script is a TOML file of steps run in order `cycles` times against
+//! plain players, one per simulated window, with no GPU or event loop:
+//!
+//! ```toml
+//! cycles = 10
+//!
+//! [budgets]
+//! peak_rss_mb = 400.0
+//! leak_per_cycle_mb = 1.0
+//! latency_ms = { load = 500.0, seek = 50.0 }
+//!
+//! [[steps]]
+//! action = "open-windows"
+//! count = 4
+//!
+//! [[steps]]
+//! action = "load"
+//! path = "fixtures/song.prot"
+//!
+//! [[steps]]
+//! action = "play"
+//!
+//! [[steps]]
+//! action = "wait"
+//! seconds = 2.0
+//!
+//! [[steps]]
+//! action = "close-windows"
+//! ```
+//!
+//! The JSON report goes to stdout. A build with the `no-player` feature runs
+//! the same script against the null backend, for machines without audio.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::memory::{MemorySampler, PlayerCounts, Sample, bytes_to_mb};
+use crate::playback::PlaybackController;
+
+/// Exit code when every budget was met.
+pub(crate) const EXIT_PASSED: i32 = 0;
+/// Exit code when the run finished but a budget was exceeded.
+pub(crate) const EXIT_OVER_BUDGET: i32 = 1;
+/// Exit code when the script could not be read or a step failed.
+pub(crate) const EXIT_FAILED: i32 = 2;
+
+/// How often RSS is sampled while a `wait` step lets playback run.
+const WAIT_SLICE: Duration = Duration::from_millis(100);
+/// How often a `load` step checks whether the player thread has finished.
+const LOAD_POLL: Duration = Duration::from_millis(1);
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Script {
+    #[serde(default = "default_cycles")]
+    cycles: usize,
+    #[serde(default)]
+    budgets: Budgets,
+    steps: Vec<Step>,
+}
+
+fn default_cycles() -> usize {
+    1
+}
+
+/// Limits that fail the run when exceeded. Unset limits are not checked.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+struct Budgets {
+    peak_rss_mb: Option<f64>,
+    leak_per_cycle_mb: Option<f64>,
+    /// 95th percentile latency allowed per action, keyed by action name.
+    latency_ms: BTreeMap<String, f64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "action", rename_all = "kebab-case", deny_unknown_fields)]
+enum Step {
+    /// Opens more windows, each with its own player.
+    OpenWindows {
+        count: usize,
+    },
+    /// Loads a file into every open window. Relative paths are resolved
+    /// against the script's directory.
+    Load {
+        path: PathBuf,
+    },
+    Play,
+    Pause,
+    Seek {
+        seconds: f64,
+    },
+    Shuffle,
+    /// Lets playback run.
+    Wait {
+        seconds: f64,
+    },
+    CloseWindows,
+}
+
+impl Step {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::OpenWindows { .. } => "open-windows",
+            Self::Load { .. } => "load",
+            Self::Play => "play",
+            Self::Pause => "pause",
+            Self::Seek { .. } => "seek",
+            Self::Shuffle => "shuffle",
+            Self::Wait { .. } => "wait",
+            Self::CloseWindows => "close-windows",
+        }
+    }
+}
+
+impl Script {
+    /// Rejects values the runner cannot act on, such as a `wait` of `inf`
+    /// seconds, before anything runs.
+    fn validate(&self) -> Result<(), String> {
+        if self.cycles == 0 {
+            return Err("cycles must be at least 1".to_owned());
+        }
+        if self.steps.is_empty() {
+            return Err("the script has no steps".to_owned());
+        }
+        for (index, step) in self.steps.iter().enumerate() {
+            if let Step::Seek { seconds } | Step::Wait { seconds } = step
+                && !(seconds.is_finite() && Duration::try_from_secs_f64(*seconds).is_ok())
+            {
+                return Err(format!(
+                    "step {} ({}) needs a finite, non-negative number of seconds, not {seconds}",
+                    index + 1,
+                    step.name()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Report<'a> {
+    script: &'a Path,
+    backend: &'static str,
+    version: &'static str,
+    cycles: usize,
+    passed: bool,
+    failures: Vec<String>,
+    peak_rss_mb: f64,
+    peak_virtual_mb: f64,
+    /// Resident memory once each cycle had finished.
+    rss_after_cycle_mb: Vec<f64>,
+    leak_per_cycle_mb: Option<f64>,
+    latencies: BTreeMap<&'static str, Latency>,
+    budgets: &'a Budgets,
+    samples: Vec<&'a Sample>,
+}
+
+/// How long one kind of step took across the run.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+struct Latency {
+    count: usize,
+    mean_ms: f64,
+    p95_ms: f64,
+    max_ms: f64,
+}
+
+/// Runs the script at `path`, prints the report and returns the exit code.
+pub(crate) fn run(path: &Path) -> i32 {
+    let script = match read_script(path) {
+        Ok(script) => script,
+        Err(error) => {
+            eprintln!("{error}");
+            return EXIT_FAILED;
+        }
+    };
+    let base = path.parent().unwrap_or(Path::new(""));
+    log::info!(
+        "perf script start path={path:?} cycles={} steps={}",
+        script.cycles,
+        script.steps.len()
+    );
+
+    let mut runner = Runner {
+        sampler: MemorySampler::new(),
+        players: Vec::new(),
+        durations: BTreeMap::new(),
+    };
+    runner.sampler.set_count_threads(true);
+    runner.sample("start");
+
+    let mut rss_after_cycle = Vec::with_capacity(script.cycles);
+    for cycle in 0..script.cycles {
+        for step in &script.steps {
+            if let Err(error) = runner.run_step(step, base) {
+                eprintln!("Cycle {}, step {}: {error}", cycle + 1, step.name());
+                return EXIT_FAILED;
+            }
+        }
+        runner.sample("cycle-end");
+        let rss = runner
+            .sampler
+            .samples()
+            .back()
+            .map_or(0, |sample| sample.rss_bytes);
+        rss_after_cycle.push(bytes_to_mb(rss));
+    }
+    runner.close_windows();
+    runner.sample("end");
+
+    let latencies: BTreeMap<&'static str, Latency> = runner
+        .durations
+        .iter()
+        .filter_map(|(name, durations)| Some((*name, Latency::of(durations)?)))
+        .collect();
+    let peak_rss_mb = bytes_to_mb(runner.sampler.peak_rss);
+    let leak_per_cycle_mb = leak_per_cycle(&rss_after_cycle);
+    let failures = budget_failures(&script.budgets, peak_rss_mb, leak_per_cycle_mb, &latencies);
+
+    let report = Report {
+        script: path,
+        backend: if cfg!(feature = "with-player") {
+            "proteus"
+        } else {
+            "null"
+        },
+        version: env!("CARGO_PKG_VERSION"),
+        cycles: script.cycles,
+        passed: failures.is_empty(),
+        failures,
+        peak_rss_mb,
+        peak_virtual_mb: bytes_to_mb(runner.sampler.peak_virtual),
+        rss_after_cycle_mb: rss_after_cycle,
+        leak_per_cycle_mb,
+        latencies,
+        budgets: &script.budgets,
+        samples: runner.sampler.samples().iter().collect(),
+    };
+
+    match serde_json::to_string_pretty(&report) {
+        Ok(json) => println!("{json}"),
+        Err(error) => {
+            eprintln!("Could not serialize the report: {error}");
+            return EXIT_FAILED;
+        }
+    }
+    for failure in &report.failures {
+        eprintln!("Over budget: {failure}");
+    }
+    log::info!(
+        "perf script finished passed={} peak_rss_mb={peak_rss_mb:.1}",
+        report.passed
+    );
+
+    if report.passed {
+        EXIT_PASSED
+    } else {
+        EXIT_OVER_BUDGET
+    }
+}
+
+fn read_script(path: &Path) -> Result<Script, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|error| format!("Could not read {}: {error}", path.display()))?;
+    let script: Script = toml::from_str(&contents)
+        .map_err(|error| format!("Could not parse {}: {error}", path.display()))?;
+    script
+        .validate()
+        .map_err(|error| format!("{}: {error}", path.display()))?;
+    Ok(script)
+}
+
+struct Runner {
+    sampler: MemorySampler,
+    /// One player per simulated window.
+    players: Vec<PlaybackController>,
+    durations: BTreeMap<&'static str, Vec<Duration>>,
+}
+
+impl Runner {
+    fn run_step(&mut self, step: &Step, base: &Path) -> Result<(), String> {
+        let started = Instant::now();
+        match step {
+            Step::OpenWindows { count } => {
+                self.players
+                    .extend((0..*count).map(|_| PlaybackController::new()));
+            }
+            Step::Load { path } => {
+                let path = base.join(path);
+                for player in &mut self.players {
+                    load_and_wait(player, &path)?;
+                }
+            }
+            Step::Play => {
+                for player in &mut self.players {
+                    let position = player.status().time;
+                    player.start_at(position, true);
+                }
+            }
+            Step::Pause => self.players.iter_mut().for_each(PlaybackController::pause),
+            Step::Seek { seconds } => {
+                for player in &mut self.players {
+                    player.seek(*seconds);
+                }
+            }
+            Step::Shuffle => self
+                .players
+                .iter_mut()
+                .for_each(PlaybackController::shuffle),
+            Step::Wait { seconds } => {
+                let until = started + Duration::from_secs_f64(*seconds);
+                while let Some(remaining) = until.checked_duration_since(Instant::now()) {
+                    thread::sleep(remaining.min(WAIT_SLICE));
+                    self.players
+                        .iter_mut()
+                        .for_each(PlaybackController::refresh);
+                    let counts = self.counts();
+                    self.sampler.maybe_sample_periodic(counts);
+                }
+                // Waiting is not an operation worth timing.
+                return Ok(());
+            }
+            Step::CloseWindows => self.close_windows(),
+        }
+
+        self.durations
+            .entry(step.name())
+            .or_default()
+            .push(started.elapsed());
+        self.sample(step.name());
+        Ok(())
+    }
+
+    fn close_windows(&mut self) {
+        for mut player in self.players.drain(..) {
+            player.shutdown();
+        }
+    }
+
+    fn counts(&self) -> PlayerCounts {
+        PlayerCounts::from_players(self.players.iter())
+    }
+
+    fn sample(&mut self, reason: &'static str) {
+        let counts = self.counts();
+        self.sampler.sample_event(reason, counts);
+    }
+}
+
+/// Loads `path` the way a window does, waiting for the player thread to
+/// build it, so the `load` latency covers the whole open.
+fn load_and_wait(player: &mut PlaybackController, path: &Path) -> Result<(), String> {
+    player.load(0, path.to_path_buf());
+    loop {
+        if let Some((generation, result)) = player.poll_load() {
+            result.map_err(|error| error.to_string())?;
+            player.commit_load(generation);
+            return Ok(());
+        }
+        thread::sleep(LOAD_POLL);
+    }
+}
+
+impl Latency {
+    fn of(durations: &[Duration]) -> Option<Self> {
+        if durations.is_empty() {
+            return None;
+        }
+        let mut millis: Vec<f64> = durations
+            .iter()
+            .map(|duration| duration.as_secs_f64() * 1000.0)
+            .collect();
+        millis.sort_by(f64::total_cmp);
+        let p95_index = (millis.len() * 95).div_ceil(100).saturating_sub(1);
+        Some(Self {
+            count: millis.len(),
+            mean_ms: millis.iter().sum::<f64>() / millis.len() as f64,
+            p95_ms: millis[p95_index],
+            max_ms: millis[millis.len() - 1],
+        })
+    }
+}
+
+/// Memory gained per cycle, as the slope of a least-squares line through
+/// the RSS after each cycle. The first cycle warms caches and allocators
+/// up, so it is left out once there are enough cycles to spare it.
+fn leak_per_cycle(rss_after_cycle: &[f64]) -> Option<f64> {
+    let points = if rss_after_cycle.len() >= 3 {
+        &rss_after_cycle[1..]
+    } else {
+        rss_after_cycle
+    };
+    if points.len() < 2 {
+        return None;
+    }
+
+    let count = points.len() as f64;
+    let mean_x = (count - 1.0) / 2.0;
+    let mean_y = points.iter().sum::<f64>() / count;
+    let (covariance, variance) =
+        points
+            .iter()
+            .enumerate()
+            .fold((0.0, 0.0), |(covariance, variance), (index, rss)| {
+                let dx = index as f64 - mean_x;
+                (covariance + dx * (rss - mean_y), variance + dx * dx)
+            });
+    Some(covariance / variance)
+}
+
+fn budget_failures(
+    budgets: &Budgets,
+    peak_rss_mb: f64,
+    leak_per_cycle_mb: Option<f64>,
+    latencies: &BTreeMap<&'static str, Latency>,
+) -> Vec<String> {
+    let mut failures = Vec::new();
+    if let Some(budget) = budgets.peak_rss_mb
+        && peak_rss_mb > budget
+    {
+        failures.push(format!(
+            "peak RSS {peak_rss_mb:.1} MB is over {budget:.1} MB"
+        ));
+    }
+    if let (Some(budget), Some(leak)) = (budgets.leak_per_cycle_mb, leak_per_cycle_mb)
+        && leak > budget
+    {
+        failures.push(format!(
+            "RSS grew {leak:.2} MB per cycle, over {budget:.2} MB"
+        ));
+    }
+    for (action, budget) in &budgets.latency_ms {
+        match latencies.get(action.as_str()) {
+            Some(latency) if latency.p95_ms > *budget => failures.push(format!(
+                "{action} took {:.1} ms at the 95th percentile, over {budget:.1} ms",
+                latency.p95_ms
+            )),
+            Some(_) => {}
+            None => failures.push(format!("{action} has a budget but never ran")),
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steady_growth_after_warm_up_is_reported_against_the_budgets() {
+        // The first cycle's jump is warm-up; after it RSS grows 2 MB a cycle.
+        let leak = leak_per_cycle(&[100.0, 140.0, 142.0, 144.0, 146.0]);
+        assert_eq!(leak, Some(2.0));
+        assert_eq!(leak_per_cycle(&[100.0]), None);
+
+        let script: Script = toml::from_str(
+            r#"
+            cycles = 5
+
+            [budgets]
+            peak_rss_mb = 200.0
+            leak_per_cycle_mb = 1.0
+            latency_ms = { seek = 50.0, load = 500.0 }
+
+            [[steps]]
+            action = "open-windows"
+            count = 2
+
+            [[steps]]
+            action = "seek"
+            seconds = 30.0
+            "#,
+        )
+        .unwrap();
+        assert_eq!(script.steps.len(), 2);
+        assert_eq!(script.validate(), Ok(()));
+
+        let endless: Script = toml::from_str(
+            r#"
+            [[steps]]
+            action = "wait"
+            seconds = inf
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            endless.validate(),
+            Err("step 1 (wait) needs a finite, non-negative number of seconds, not inf".to_owned())
+        );
+
+        let latencies = BTreeMap::from([(
+            "seek",
+            Latency::of(&[Duration::from_millis(10), Duration::from_millis(80)]).unwrap(),
+        )]);
+        assert_eq!(latencies["seek"].p95_ms, 80.0);
+
+        let failures = budget_failures(&script.budgets, 150.0, leak, &latencies);
+        assert_eq!(
+            failures,
+            [
+                "RSS grew 2.00 MB per cycle, over 1.00 MB",
+                "load has a budget but never ran",
+                "seek took 80.0 ms at the 95th percentile, over 50.0 ms",
+            ]
+        );
+    }
+}
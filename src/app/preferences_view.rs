@@ -0,0 +1,234 @@
+use iced::widget::{button, checkbox, column, container, pick_list, row, scrollable, slider, text};
+use iced::{Alignment, Element, Length};
+
+use crate::app::keybindings::Action;
+use crate::app::messages::Message;
+use crate::app::settings::{
+    MAX_BUFFER_CHUNKS, MAX_RECENT_FILES_LIMIT, MAX_SEEK_STEP, MAX_ZOOM, MIN_BUFFER_CHUNKS,
+    MIN_SEEK_STEP, MIN_ZOOM, SettingChange, StartupBehavior,
+};
+use crate::app::state::ProteusApp;
+use crate::app::styles::{background_style, volume_slider_style};
+use crate::app::theme::Palette;
+
+const LABEL_WIDTH: f32 = 130.0;
+const VALUE_WIDTH: f32 = 60.0;
+
+pub(crate) fn preferences_view(state: &ProteusApp) -> Element<'_, Message> {
+    let settings = &state.settings;
+    let palette = state.palette;
+
+    let content = column![
+        setting_row(
+            palette,
+            "Default volume",
+            slider(0.0..=100.0, settings.default_volume_percent, |percent| {
+                Message::SettingChanged(SettingChange::DefaultVolume(percent))
+            })
+            .step(1.0)
+            .style(volume_slider_style(palette))
+            .into(),
+            format!("{:.0}%", settings.default_volume_percent),
+        ),
+        setting_row(
+            palette,
+            "Zoom",
+            slider(MIN_ZOOM..=MAX_ZOOM, settings.default_zoom, |zoom| {
+                Message::SettingChanged(SettingChange::DefaultZoom(zoom))
+            })
+            .step(0.1)
+            .style(volume_slider_style(palette))
+            .into(),
+            format!("{:.0}%", settings.default_zoom * 100.0),
+        ),
+        setting_row(
+            palette,
+            "Theme",
+            pick_list(
+                state.themes.choices(),
+                Some(settings.theme.clone()),
+                |theme| { Message::SettingChanged(SettingChange::Theme(theme)) }
+            )
+            .text_size(12)
+            .width(Length::Fill)
+            .into(),
+            String::new(),
+        ),
+        setting_row(
+            palette,
+            "Buffer size",
+            slider(
+                MIN_BUFFER_CHUNKS..=MAX_BUFFER_CHUNKS,
+                settings.buffer_chunks,
+                |chunks| Message::SettingChanged(SettingChange::BufferChunks(chunks)),
+            )
+            .style(volume_slider_style(palette))
+            .into(),
+            format!("{} chunks", settings.buffer_chunks),
+        ),
+        setting_row(
+            palette,
+            "Seek step",
+            slider(
+                MIN_SEEK_STEP..=MAX_SEEK_STEP,
+                settings.seek_step_seconds,
+                |seconds| Message::SettingChanged(SettingChange::SeekStep(seconds)),
+            )
+            .step(1.0)
+            .style(volume_slider_style(palette))
+            .into(),
+            format!("{:.0} s", settings.seek_step_seconds),
+        ),
+        setting_row(
+            palette,
+            "Recent files",
+            slider(
+                0..=MAX_RECENT_FILES_LIMIT,
+                settings.recent_files_limit,
+                |limit| Message::SettingChanged(SettingChange::RecentFilesLimit(limit)),
+            )
+            .style(volume_slider_style(palette))
+            .into(),
+            settings.recent_files_limit.to_string(),
+        ),
+        setting_row(
+            palette,
+            "On startup",
+            pick_list(StartupBehavior::ALL, Some(settings.startup), |startup| {
+                Message::SettingChanged(SettingChange::Startup(startup))
+            })
+            .text_size(12)
+            .width(Length::Fill)
+            .into(),
+            String::new(),
+        ),
+        setting_row(
+            palette,
+            "Tray icon",
+            checkbox(settings.show_tray_icon)
+                .label("Show in the system tray")
+                .text_size(12)
+                .on_toggle(|show| Message::SettingChanged(SettingChange::ShowTrayIcon(show)))
+                .into(),
+            String::new(),
+        ),
+        setting_row(
+            palette,
+            "Closing windows",
+            checkbox(settings.keep_running_in_tray)
+                .label("Keep running in the tray")
+                .text_size(12)
+                .on_toggle_maybe(settings.show_tray_icon.then_some(|keep| {
+                    Message::SettingChanged(SettingChange::KeepRunningInTray(keep))
+                }))
+                .into(),
+            String::new(),
+        ),
+        setting_row(
+            palette,
+            "Proteus Author",
+            checkbox(settings.link_enabled)
+                .label("Allow control over a local connection")
+                .text_size(12)
+                .on_toggle(|enabled| Message::SettingChanged(SettingChange::LinkEnabled(enabled)))
+                .into(),
+            String::new(),
+        ),
+        container(
+            button(text("Restore Defaults").size(12))
+                .padding([4, 8])
+                .on_press(Message::ResetSettingsPressed),
+        )
+        .width(Length::Fill)
+        .align_x(Alignment::End),
+    ]
+    .spacing(12);
+
+    let content = column![content, shortcuts_section(state)]
+        .spacing(20)
+        .padding(16);
+
+    container(scrollable(content))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(background_style(palette))
+        .into()
+}
+
+fn shortcuts_section(state: &ProteusApp) -> Element<'_, Message> {
+    let palette = state.palette;
+    let header = match &state.shortcut_notice {
+        Some(notice) => text(notice).size(11).color(palette.error_text),
+        None => text("Shortcuts — click a shortcut to remove it. Escape cancels recording.")
+            .size(11)
+            .color(palette.accent_text),
+    };
+
+    Action::ALL
+        .into_iter()
+        .fold(column![header].spacing(6), |section, action| {
+            section.push(shortcut_row(state, action))
+        })
+        .into()
+}
+
+fn shortcut_row(state: &ProteusApp, action: Action) -> Element<'_, Message> {
+    let palette = state.palette;
+    let chords = state
+        .keybindings
+        .chords(action)
+        .iter()
+        .fold(row![].spacing(4), |chords, chord| {
+            chords.push(
+                button(text(format!("{chord} ✕")).size(11))
+                    .padding([1, 6])
+                    .style(button::secondary)
+                    .on_press(Message::ShortcutRemovePressed(action, chord.clone())),
+            )
+        })
+        .wrap();
+
+    let recording = state.recording_shortcut == Some(action);
+    let add_label = if recording { "Press keys…" } else { "Add" };
+
+    row![
+        text(action.label())
+            .size(12)
+            .color(palette.accent_text)
+            .width(Length::Fixed(LABEL_WIDTH)),
+        container(chords).width(Length::Fill),
+        button(text(add_label).size(11))
+            .padding([1, 6])
+            .on_press_maybe((!recording).then_some(Message::ShortcutRecordPressed(action))),
+        button(text("Reset").size(11))
+            .style(button::text)
+            .padding([1, 4])
+            .on_press(Message::ShortcutResetPressed(action)),
+    ]
+    .spacing(6)
+    .align_y(Alignment::Center)
+    .into()
+}
+
+fn setting_row<'a>(
+    palette: Palette,
+    label: &'a str,
+    control: Element<'a, Message>,
+    value: String,
+) -> Element<'a, Message> {
+    row![
+        text(label)
+            .size(12)
+            .color(palette.accent_text)
+            .width(Length::Fixed(LABEL_WIDTH)),
+        container(control).width(Length::Fill),
+        text(value)
+            .size(12)
+            .color(palette.accent_text)
+            .width(Length::Fixed(VALUE_WIDTH))
+            .align_x(Alignment::End),
+    ]
+    .spacing(8)
+    .align_y(Alignment::Center)
+    .into()
+}
@@ -0,0 +1,65 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::playback::probe_media;
+
+/// File details shown in a player window's properties panel.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FileProperties {
+    pub(crate) path: PathBuf,
+    pub(crate) size_bytes: u64,
+    pub(crate) duration: Option<f64>,
+    pub(crate) part_count: Option<usize>,
+}
+
+impl FileProperties {
+    /// Reads the file's size and media layout. Blocking; run off the UI thread.
+    pub(crate) fn read(path: &Path) -> Result<Self, String> {
+        let metadata = fs::metadata(path)
+            .map_err(|error| format!("could not read {}: {error}", path.display()))?;
+        let media = probe_media(path);
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            size_bytes: metadata.len(),
+            duration: media.duration,
+            part_count: media.part_count,
+        })
+    }
+
+    pub(crate) fn format(&self) -> String {
+        self.path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_uppercase())
+            .unwrap_or_else(|| "Unknown".to_owned())
+    }
+}
+
+/// Formats a byte count with a binary unit, e.g. `12.4 MB`.
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} bytes");
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_use_the_largest_whole_unit() {
+        assert_eq!(format_size(512), "512 bytes");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MB");
+    }
+}
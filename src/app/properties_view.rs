@@ -0,0 +1,94 @@
+use iced::widget::{Column, button, column, container, row, scrollable, text};
+use iced::{Alignment, Element, Length, window};
+
+use crate::app::helpers::format_time;
+use crate::app::messages::Message;
+use crate::app::properties::format_size;
+use crate::app::state::{PlayerWindowState, ProteusApp};
+use crate::app::styles::background_style;
+
+const LABEL_WIDTH: f32 = 90.0;
+
+/// Details of the window's file, plus what is set on it in this window.
+pub(crate) fn properties_overlay<'a>(
+    state: &'a ProteusApp,
+    window: &'a PlayerWindowState,
+    window_id: window::Id,
+) -> Element<'a, Message> {
+    let palette = state.palette;
+
+    let header = row![
+        text("Properties").size(13).width(Length::Fill),
+        button(text("Close").size(11))
+            .style(button::text)
+            .padding([0, 4])
+            .on_press(Message::PropertiesClosed(window_id)),
+    ]
+    .align_y(Alignment::Center);
+
+    let mut fields: Vec<(&str, String)> = Vec::new();
+    match &window.properties {
+        None => fields.push(("", "Reading file…".to_owned())),
+        Some(Err(error)) => fields.push(("", error.clone())),
+        Some(Ok(properties)) => {
+            fields.push((
+                "Name",
+                properties
+                    .path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+            ));
+            fields.push((
+                "Folder",
+                properties
+                    .path
+                    .parent()
+                    .map(|parent| parent.display().to_string())
+                    .unwrap_or_default(),
+            ));
+            fields.push(("Format", properties.format()));
+            fields.push(("Size", format_size(properties.size_bytes)));
+            if let Some(duration) = properties.duration {
+                fields.push(("Duration", format_time(duration)));
+            }
+            if let Some(part_count) = properties.part_count {
+                fields.push(("Parts", part_count.to_string()));
+            }
+        }
+    }
+
+    if let Some(code) = window.playback.combination_code() {
+        fields.push(("Combination", code));
+    }
+    if let (Some(start), Some(end)) = (window.loop_in, window.loop_out) {
+        fields.push((
+            "Loop",
+            format!("{} – {}", format_time(start), format_time(end)),
+        ));
+    }
+    let bookmark_count = state.window_bookmarks(window).len();
+    if bookmark_count > 0 {
+        fields.push(("Bookmarks", bookmark_count.to_string()));
+    }
+
+    let rows = fields.into_iter().fold(
+        Column::new().spacing(2),
+        |rows: Column<'a, Message>, (label, value)| {
+            rows.push(
+                row![
+                    text(label).size(11).width(Length::Fixed(LABEL_WIDTH)),
+                    text(value).size(11).color(palette.accent_text),
+                ]
+                .spacing(8),
+            )
+        },
+    );
+
+    container(column![header, scrollable(rows).height(Length::Fill)].spacing(6))
+        .padding([8, 12])
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(background_style(palette))
+        .into()
+}
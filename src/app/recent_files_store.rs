@@ -1,45 +1,32 @@
-use std::fs;
-use std::io::ErrorKind;
 use std::path::PathBuf;
 
-const APP_DIRECTORY: &str = "proteus-player";
+use serde::{Deserialize, Serialize};
+
+use crate::app::{storage, stored_path};
+
 const RECENT_FILES_NAME: &str = "recent-files.json";
 
-pub(crate) fn load() -> Result<Vec<PathBuf>, String> {
-    let path = storage_path()?;
+/// The file is a bare JSON list of paths.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(transparent)]
+struct RecentFiles(#[serde(with = "stored_path::list")] Vec<PathBuf>);
 
-    match fs::read(&path) {
-        Ok(contents) => serde_json::from_slice(&contents)
-            .map_err(|error| format!("could not parse {}: {error}", path.display())),
-        Err(error) if error.kind() == ErrorKind::NotFound => Ok(Vec::new()),
-        Err(error) => Err(format!("could not read {}: {error}", path.display())),
-    }
+pub(crate) fn load() -> Result<Vec<PathBuf>, String> {
+    storage::read_json(&storage_path()?).map(|RecentFiles(files)| files)
 }
 
 pub(crate) fn save(files: &[PathBuf]) -> Result<(), String> {
-    let path = storage_path()?;
-    let directory = path
-        .parent()
-        .expect("the recent-files storage path always has a parent directory");
-
-    fs::create_dir_all(directory)
-        .map_err(|error| format!("could not create {}: {error}", directory.display()))?;
-
-    let contents = serde_json::to_vec(files)
-        .map_err(|error| format!("could not serialize recent files: {error}"))?;
-    fs::write(&path, contents)
-        .map_err(|error| format!("could not write {}: {error}", path.display()))
+    storage::write_json(&storage_path()?, &RecentFiles(files.to_vec()))
 }
 
 fn storage_path() -> Result<PathBuf, String> {
-    dirs::data_local_dir()
-        .map(|directory| directory.join(APP_DIRECTORY).join(RECENT_FILES_NAME))
-        .ok_or_else(|| "could not determine the app data directory".to_owned())
+    storage::data_file_path(RECENT_FILES_NAME)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::app::storage::APP_DIRECTORY;
     use std::path::Path;
 
     #[test]
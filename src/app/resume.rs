@@ -0,0 +1,115 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::stored_path;
+
+pub(crate) const RESUME_POSITIONS_VERSION: u32 = 1;
+
+/// How many files keep a resume point before the oldest are forgotten.
+const MAX_RESUME_POINTS: usize = 200;
+/// Positions this close to either end of a file are not worth resuming.
+const RESUME_MARGIN_SECONDS: f64 = 5.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ResumePoint {
+    #[serde(with = "stored_path")]
+    pub(crate) path: PathBuf,
+    pub(crate) position: f64,
+    /// Track ids of the combination that was playing, compared with the one
+    /// playing now so the resume prompt can say when the takes changed.
+    #[serde(default)]
+    pub(crate) combination: Vec<String>,
+}
+
+/// Last known playback position per file, most recently played first.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct ResumePositions {
+    pub(crate) version: u32,
+    pub(crate) points: Vec<ResumePoint>,
+}
+
+impl Default for ResumePositions {
+    fn default() -> Self {
+        Self {
+            version: RESUME_POSITIONS_VERSION,
+            points: Vec::new(),
+        }
+    }
+}
+
+impl ResumePositions {
+    pub(crate) fn get(&self, path: &Path) -> Option<&ResumePoint> {
+        self.points.iter().find(|point| point.path == path)
+    }
+
+    /// Updates the resume point for `path`, returning whether anything changed.
+    ///
+    /// Positions near the start or end of the file clear the point instead, so
+    /// finished or barely-started listens are not offered for resuming.
+    pub(crate) fn record(
+        &mut self,
+        path: &Path,
+        position: f64,
+        duration: Option<f64>,
+        combination: Vec<String>,
+    ) -> bool {
+        let near_end =
+            duration.is_some_and(|duration| position >= duration - RESUME_MARGIN_SECONDS);
+        if position < RESUME_MARGIN_SECONDS || near_end {
+            return self.remove(path);
+        }
+
+        let index = self.points.iter().position(|point| point.path == path);
+        if let Some(index) = index {
+            let point = &self.points[index];
+            if index == 0
+                && (point.position - position).abs() < 1.0
+                && point.combination == combination
+            {
+                return false;
+            }
+            self.points.remove(index);
+        }
+
+        self.points.insert(
+            0,
+            ResumePoint {
+                path: path.to_path_buf(),
+                position,
+                combination,
+            },
+        );
+        self.points.truncate(MAX_RESUME_POINTS);
+        true
+    }
+
+    pub(crate) fn remove(&mut self, path: &Path) -> bool {
+        let count = self.points.len();
+        self.points.retain(|point| point.path != path);
+        self.points.len() != count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_near_either_end_clear_the_resume_point() {
+        let path = Path::new("/music/long-form.prot");
+        let mut positions = ResumePositions::default();
+
+        assert!(positions.record(path, 600.0, Some(1500.0), Vec::new()));
+        assert_eq!(positions.get(path).map(|point| point.position), Some(600.0));
+        assert!(!positions.record(path, 600.4, Some(1500.0), Vec::new()));
+
+        assert!(positions.record(path, 1498.0, Some(1500.0), Vec::new()));
+        assert!(positions.get(path).is_none());
+
+        positions.record(path, 600.0, Some(1500.0), Vec::new());
+        assert!(positions.record(path, 1.0, Some(1500.0), Vec::new()));
+        assert!(positions.get(path).is_none());
+    }
+}
@@ -0,0 +1,18 @@
+use std::path::PathBuf;
+
+use crate::app::resume::ResumePositions;
+use crate::app::storage;
+
+const RESUME_POSITIONS_NAME: &str = "resume-positions.json";
+
+pub(crate) fn load() -> Result<ResumePositions, String> {
+    storage::read_json(&storage_path()?)
+}
+
+pub(crate) fn save(positions: &ResumePositions) -> Result<(), String> {
+    storage::write_json(&storage_path()?, positions)
+}
+
+fn storage_path() -> Result<PathBuf, String> {
+    storage::data_file_path(RESUME_POSITIONS_NAME)
+}
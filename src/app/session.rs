@@ -0,0 +1,48 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::stored_path;
+
+pub(crate) const SESSION_VERSION: u32 = 1;
+
+/// One player window as it was when the session was last saved.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct SessionWindow {
+    #[serde(with = "stored_path")]
+    pub(crate) path: PathBuf,
+    pub(crate) position: f64,
+    pub(crate) volume_percent: f32,
+    pub(crate) zoom_factor: f64,
+    /// Track ids of the combination that was playing. Takes cannot be chosen
+    /// when a file loads, so on restore this only tells the user that a
+    /// different combination is playing.
+    #[serde(default)]
+    pub(crate) combination: Vec<String>,
+    pub(crate) playing: bool,
+    /// Inner size the window was resized to, if it was.
+    #[serde(default)]
+    pub(crate) size: Option<WindowSize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub(crate) struct WindowSize {
+    pub(crate) width: f32,
+    pub(crate) height: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Session {
+    pub(crate) version: u32,
+    pub(crate) windows: Vec<SessionWindow>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            version: SESSION_VERSION,
+            windows: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+
+use crate::app::session::Session;
+use crate::app::storage;
+
+const SESSION_NAME: &str = "session.json";
+
+pub(crate) fn load() -> Result<Session, String> {
+    storage::read_json(&storage_path()?)
+}
+
+pub(crate) fn save(session: &Session) -> Result<(), String> {
+    storage::write_json(&storage_path()?, session)
+}
+
+fn storage_path() -> Result<PathBuf, String> {
+    storage::data_file_path(SESSION_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::session::{SESSION_VERSION, SessionWindow, WindowSize};
+
+    #[test]
+    fn session_round_trips_through_json() {
+        let session = Session {
+            version: SESSION_VERSION,
+            windows: vec![SessionWindow {
+                path: PathBuf::from("/music/review/take-3.prot"),
+                position: 42.5,
+                volume_percent: 80.0,
+                zoom_factor: 1.2,
+                combination: vec!["12".to_owned(), "7".to_owned()],
+                playing: true,
+                size: Some(WindowSize {
+                    width: 640.0,
+                    height: 420.0,
+                }),
+            }],
+        };
+        let serialized = serde_json::to_vec(&session).expect("the session should serialize");
+
+        let restored: Session =
+            serde_json::from_slice(&serialized).expect("the session should deserialize");
+
+        assert_eq!(restored, session);
+    }
+}
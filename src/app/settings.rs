@@ -0,0 +1,188 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::theme::ThemeChoice;
+
+pub(crate) const SETTINGS_VERSION: u32 = 1;
+
+pub(crate) const MIN_ZOOM: f64 = 0.5;
+pub(crate) const MAX_ZOOM: f64 = 2.0;
+pub(crate) const MIN_BUFFER_CHUNKS: u32 = 5;
+pub(crate) const MAX_BUFFER_CHUNKS: u32 = 120;
+pub(crate) const MIN_SEEK_STEP: f64 = 1.0;
+pub(crate) const MAX_SEEK_STEP: f64 = 60.0;
+pub(crate) const MAX_RECENT_FILES_LIMIT: u32 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum StartupBehavior {
+    OpenDialog,
+    EmptyWindow,
+    RestoreSession,
+}
+
+impl StartupBehavior {
+    pub(crate) const ALL: [Self; 3] = [Self::OpenDialog, Self::EmptyWindow, Self::RestoreSession];
+}
+
+impl Default for StartupBehavior {
+    fn default() -> Self {
+        // macOS apps conventionally start without a window, so prompt for a file
+        // there; elsewhere an empty player window is the familiar starting point.
+        if cfg!(target_os = "macos") {
+            Self::OpenDialog
+        } else {
+            Self::EmptyWindow
+        }
+    }
+}
+
+impl fmt::Display for StartupBehavior {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::OpenDialog => "Show the open dialog",
+            Self::EmptyWindow => "Open an empty window",
+            Self::RestoreSession => "Restore the last session",
+        })
+    }
+}
+
+/// User preferences persisted to `settings.json`.
+///
+/// Missing fields fall back to their defaults, so files written by older
+/// versions keep loading as new settings are added.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Settings {
+    pub(crate) version: u32,
+    pub(crate) default_volume_percent: f32,
+    pub(crate) default_zoom: f64,
+    pub(crate) theme: ThemeChoice,
+    pub(crate) buffer_chunks: u32,
+    pub(crate) seek_step_seconds: f64,
+    pub(crate) recent_files_limit: u32,
+    pub(crate) startup: StartupBehavior,
+    /// Where the mini player was last moved to on screen.
+    pub(crate) mini_player_position: Option<ScreenPosition>,
+    pub(crate) show_tray_icon: bool,
+    /// Keep running with only the tray icon once the last window closes.
+    pub(crate) keep_running_in_tray: bool,
+    /// Let local authoring tools control the player through `link.json`.
+    pub(crate) link_enabled: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ScreenPosition {
+    pub(crate) x: f32,
+    pub(crate) y: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            default_volume_percent: 100.0,
+            default_zoom: 1.0,
+            theme: ThemeChoice::default(),
+            buffer_chunks: 30,
+            seek_step_seconds: 5.0,
+            recent_files_limit: 10,
+            startup: StartupBehavior::default(),
+            mini_player_position: None,
+            show_tray_icon: false,
+            keep_running_in_tray: false,
+            link_enabled: false,
+        }
+    }
+}
+
+/// A single edit made in the preferences window.
+#[derive(Debug, Clone)]
+pub(crate) enum SettingChange {
+    DefaultVolume(f32),
+    DefaultZoom(f64),
+    Theme(ThemeChoice),
+    BufferChunks(u32),
+    SeekStep(f64),
+    RecentFilesLimit(u32),
+    Startup(StartupBehavior),
+    ShowTrayIcon(bool),
+    KeepRunningInTray(bool),
+    LinkEnabled(bool),
+}
+
+impl Settings {
+    /// Brings settings read from disk up to the current version and clamps
+    /// hand-edited values back into their supported ranges.
+    pub(crate) fn migrated(mut self) -> Self {
+        self.version = SETTINGS_VERSION;
+        self.default_volume_percent =
+            clamp_finite(f64::from(self.default_volume_percent), 0.0, 100.0, 100.0) as f32;
+        self.default_zoom = clamp_finite(self.default_zoom, MIN_ZOOM, MAX_ZOOM, 1.0);
+        self.seek_step_seconds =
+            clamp_finite(self.seek_step_seconds, MIN_SEEK_STEP, MAX_SEEK_STEP, 5.0);
+        self.buffer_chunks = self
+            .buffer_chunks
+            .clamp(MIN_BUFFER_CHUNKS, MAX_BUFFER_CHUNKS);
+        self.recent_files_limit = self.recent_files_limit.min(MAX_RECENT_FILES_LIMIT);
+        self
+    }
+
+    pub(crate) fn apply(&mut self, change: SettingChange) {
+        match change {
+            SettingChange::DefaultVolume(percent) => self.default_volume_percent = percent,
+            SettingChange::DefaultZoom(zoom) => self.default_zoom = zoom,
+            SettingChange::Theme(theme) => self.theme = theme,
+            SettingChange::BufferChunks(chunks) => self.buffer_chunks = chunks,
+            SettingChange::SeekStep(seconds) => self.seek_step_seconds = seconds,
+            SettingChange::RecentFilesLimit(limit) => self.recent_files_limit = limit,
+            SettingChange::Startup(startup) => self.startup = startup,
+            SettingChange::ShowTrayIcon(show) => self.show_tray_icon = show,
+            SettingChange::KeepRunningInTray(keep) => self.keep_running_in_tray = keep,
+            SettingChange::LinkEnabled(enabled) => self.link_enabled = enabled,
+        }
+        *self = self.clone().migrated();
+    }
+}
+
+fn clamp_finite(value: f64, min: f64, max: f64, fallback: f64) -> f64 {
+    if value.is_finite() {
+        value.clamp(min, max)
+    } else {
+        fallback
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_fall_back_to_defaults() {
+        let settings: Settings =
+            serde_json::from_str(r#"{ "version": 1, "seek_step_seconds": 10.0 }"#)
+                .expect("partial settings should deserialize");
+
+        assert_eq!(settings.seek_step_seconds, 10.0);
+        assert_eq!(settings.recent_files_limit, 10);
+        assert_eq!(settings.theme, ThemeChoice::Dark);
+    }
+
+    #[test]
+    fn migration_clamps_out_of_range_values() {
+        let settings = Settings {
+            version: 0,
+            default_volume_percent: 250.0,
+            default_zoom: f64::NAN,
+            buffer_chunks: 0,
+            ..Settings::default()
+        }
+        .migrated();
+
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert_eq!(settings.default_volume_percent, 100.0);
+        assert_eq!(settings.default_zoom, 1.0);
+        assert_eq!(settings.buffer_chunks, MIN_BUFFER_CHUNKS);
+    }
+}
@@ -0,0 +1,18 @@
+use std::path::PathBuf;
+
+use crate::app::settings::Settings;
+use crate::app::storage;
+
+const SETTINGS_NAME: &str = "settings.json";
+
+pub(crate) fn load() -> Result<Settings, String> {
+    storage::read_json(&storage_path()?).map(Settings::migrated)
+}
+
+pub(crate) fn save(settings: &Settings) -> Result<(), String> {
+    storage::write_json(&storage_path()?, settings)
+}
+
+fn storage_path() -> Result<PathBuf, String> {
+    storage::data_file_path(SETTINGS_NAME)
+}
@@ -0,0 +1,57 @@
+use iced::widget::{button, column, container, row, scrollable, text};
+use iced::{Alignment, Element, Length, window};
+
+use crate::app::keybindings::Action;
+use crate::app::messages::Message;
+use crate::app::state::ProteusApp;
+use crate::app::styles::background_style;
+
+const LABEL_WIDTH: f32 = 130.0;
+
+/// Lists every action with its current bindings, straight from the
+/// keybinding table that the key handler and menus use.
+pub(crate) fn shortcuts_overlay(state: &ProteusApp, window_id: window::Id) -> Element<'_, Message> {
+    let palette = state.palette;
+
+    let header = row![
+        text("Keyboard Shortcuts").size(13).width(Length::Fill),
+        button(text("Close").size(11))
+            .style(button::text)
+            .padding([0, 4])
+            .on_press(Message::ToggleShortcuts(window_id)),
+    ]
+    .align_y(Alignment::Center);
+
+    let rows = Action::ALL
+        .into_iter()
+        .fold(column![].spacing(2), |rows, action| {
+            let chords = state
+                .keybindings
+                .chords(action)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            let chords = if chords.is_empty() {
+                "Not bound".to_owned()
+            } else {
+                chords.join("  ")
+            };
+
+            rows.push(
+                row![
+                    text(action.label())
+                        .size(11)
+                        .width(Length::Fixed(LABEL_WIDTH)),
+                    text(chords).size(11).color(palette.accent_text),
+                ]
+                .spacing(8),
+            )
+        });
+
+    container(column![header, scrollable(rows).height(Length::Fill)].spacing(6))
+        .padding([8, 12])
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(background_style(palette))
+        .into()
+}
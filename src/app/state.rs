@@ -1,18 +1,121 @@
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
 use iced::task::Task;
-use iced::window;
+use iced::{Point, Size, Theme, window};
 
+use crate::app::bookmarks::Bookmarks;
+use crate::app::bookmarks_store;
+use crate::app::command_palette::{CommandPalette, PaletteCommand, PaletteItem};
+use crate::app::command_palette_view::COMMAND_PALETTE_INPUT;
+use crate::app::context_menu::{ContextAction, ContextMenu, ContextMenuKind};
 #[cfg(not(target_os = "macos"))]
 use crate::app::effects::request_open_dialog;
-use crate::app::effects::{open_player_window, set_macos_app_icon_from_bytes, show_about_dialog};
+use crate::app::effects::{
+    enter_mini_player, find_sibling_file, leave_mini_player, load_user_themes, open_library_window,
+    open_player_window, open_preferences_window, raise_window, read_file_properties,
+    request_library_folder_dialog, reveal_in_file_manager, scan_library,
+    set_macos_app_icon_from_bytes, show_about_dialog,
+};
+use crate::app::error_report::ErrorReport;
+use crate::app::file_watch::{FileStamp, FileWatch, FolderWatcher};
+use crate::app::helpers::{action_message, format_time};
 use crate::app::icons::IconSet;
-use crate::app::memory::MemorySampler;
+use crate::app::keybindings::{Action, KeyChord, Keybindings, KeybindingsFile};
+use crate::app::library::{FolderContents, LibraryEntry, LibraryIndex, LibraryState};
+use crate::app::link::{self, ClientId, Command, LinkServer};
+use crate::app::logging;
+use crate::app::memory::{MemorySampler, PlayerCounts};
 use crate::app::messages::Message;
-use crate::native_menu::{MenuAction, NativeMenu};
-use crate::playback::{PlaybackController, PlaybackLoadError};
+use crate::app::notifications::{Notifications, Severity};
+use crate::app::properties::FileProperties;
+use crate::app::resume::{ResumePoint, ResumePositions};
+use crate::app::resume_store;
+use crate::app::session::{Session, SessionWindow, WindowSize};
+use crate::app::session_store;
+use crate::app::settings::{
+    MAX_ZOOM, MIN_ZOOM, ScreenPosition, SettingChange, Settings, StartupBehavior,
+};
+use crate::app::theme::{Palette, Themes, UserTheme, themes_file_path};
+use crate::native_menu::{MenuAction, NativeMenu, PlaybackMenuState, WindowMenuEntry};
+use crate::playback::{LoadStage, PlaybackController, PlaybackLoadError};
+use crate::tray::{SystemTray, TrayState};
+use crate::waveform::{Level, Waveform};
+
+/// How far one volume shortcut press moves the volume, in percent.
+const VOLUME_STEP_PERCENT: f32 = 5.0;
+
+/// How many levels, one per tick while playing, each of the visualizer's part
+/// lanes keeps.
+pub(crate) const LEVEL_HISTORY_LEN: usize = 120;
+
+/// How often the user themes file is checked for changes.
+const THEMES_FILE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// How often loaded files with a change event are checked. A change is
+/// reloaded once two checks in a row agree, so the file must also hold still
+/// this long.
+const LOADED_FILES_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Time between position reports to link clients.
+const LINK_REPORT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How often open windows are snapshotted for session restore.
+const SESSION_CAPTURE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// What a window does once its file has loaded.
+#[derive(Debug, Clone)]
+enum LoadPurpose {
+    /// Opened by the user: offer to resume and add it to recent files.
+    Open,
+    /// Stepped to from the previous file, playing on if that was playing.
+    Step { was_playing: bool },
+    /// Reopened from the last session at its saved place.
+    Restore(SessionWindow),
+    /// Reloaded in place after it changed on disk.
+    Reload(KeptPlayback),
+    /// Opened by a link client, starting where it asked.
+    Link { position: Option<f64>, play: bool },
+    /// Opened from a bookmark in the command palette, paused at it.
+    Bookmark { position: f64 },
+}
+
+impl LoadPurpose {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Step { .. } => "step",
+            Self::Restore(_) => "restore",
+            Self::Reload(_) => "reload",
+            Self::Link { .. } => "link",
+            Self::Bookmark { .. } => "bookmark",
+        }
+    }
+}
+
+/// Where a window was in a file, carried over when the file is reloaded.
+/// The takes are not: proteus-lib picks them when the player is built and
+/// has no way to select them.
+#[derive(Debug, Clone)]
+struct KeptPlayback {
+    position: f64,
+    playing: bool,
+    part_levels: Vec<f32>,
+    loop_in: Option<f64>,
+    loop_out: Option<f64>,
+}
+
+/// A file being opened in a window, its player built on the window's player
+/// thread. The window keeps whatever it had loaded until the new player is
+/// ready.
+pub(crate) struct PendingLoad {
+    /// Tells the build this load is waiting for from ones started before it,
+    /// whose loads were cancelled or replaced.
+    generation: u64,
+    pub(crate) path: PathBuf,
+    pub(crate) started_at: Instant,
+    purpose: LoadPurpose,
+}
 
 #[derive(Debug, Clone, Copy)]
 enum FilePickTarget {
@@ -27,62 +130,207 @@ pub(crate) struct PlayerWindowState {
     pub(crate) current_time: f64,
     pub(crate) volume_percent: f32,
     pub(crate) playing: bool,
-    pub(crate) last_error: Option<String>,
+    /// A file that failed to open or play, shown over the window until dismissed.
+    pub(crate) error_report: Option<ErrorReport>,
     pub(crate) zoom_factor: f64,
     pub(crate) window_title: String,
     pending_title_tooltip: Option<String>,
-    pub(crate) menu_open: bool,
+    /// Index of the open in-window menu bar dropdown.
+    pub(crate) open_menu: Option<usize>,
+    pub(crate) recent_submenu_open: bool,
+    /// Whether the keyboard shortcut cheat sheet covers the window.
+    pub(crate) shortcuts_open: bool,
+    /// Whether the notification history covers the window.
+    pub(crate) notifications_open: bool,
+    /// Whether the diagnostics panel covers the window.
+    pub(crate) diagnostics_open: bool,
+    /// Saved position offered when the file was opened, until accepted or declined.
+    pub(crate) resume_offer: Option<ResumePoint>,
+    /// Volume to restore when unmuting.
+    muted_volume_percent: Option<f32>,
+    /// Last cursor position over the window, where a context menu opens.
+    cursor_position: Point,
+    pub(crate) context_menu: Option<ContextMenu>,
+    /// A-B loop markers set from the timeline's context menu.
+    pub(crate) loop_in: Option<f64>,
+    pub(crate) loop_out: Option<f64>,
+    pub(crate) properties_open: bool,
+    /// The loaded file's details, `None` while they are being read.
+    pub(crate) properties: Option<Result<FileProperties, String>>,
+    /// Inner size the window was last resized to, not counting the mini player
+    /// or fullscreen visualizer.
+    pub(crate) size: Option<Size>,
+    /// Last reported screen position.
+    position: Option<Point>,
+    /// Whether the window is shrunk to the borderless, always-on-top mini player.
+    pub(crate) mini: bool,
+    /// Where the full window was before it became the mini player.
+    full_position: Option<Point>,
+    /// Whether the window is fullscreen, showing the visualizer.
+    pub(crate) visualizer: bool,
+    /// Slots of the combination that have played to their end, as of the last tick.
+    pub(crate) finished_parts: Vec<usize>,
+    /// Each part's recent share of the output while the visualizer plays,
+    /// oldest first, by slot.
+    pub(crate) part_history: Vec<VecDeque<Level>>,
+    /// Levels set in the part mixer, by slot. Parts past the end play at full level.
+    pub(crate) part_levels: Vec<f32>,
+    /// The loaded file's folder listing, for the queue and artwork.
+    pub(crate) folder: Option<FolderContents>,
+    /// File whose folder was last scanned, so each load scans once.
+    folder_scanned_for: Option<PathBuf>,
+    /// Levels across the loaded file, `None` while they are being measured.
+    pub(crate) waveform: Option<Result<Waveform, String>>,
+    /// Whether the loaded file's waveform has been asked for since it loaded.
+    waveform_requested: bool,
+    /// The file being opened, if any.
+    pub(crate) loading: Option<PendingLoad>,
+    /// The loaded file as it was on disk, to reload it when it changes.
+    file_watch: FileWatch,
     timeline_override_until: Option<Instant>,
     volume_override_until: Option<Instant>,
 }
 
 impl PlayerWindowState {
-    fn new(path: Option<PathBuf>) -> Self {
-        let mut window = Self {
-            playback: PlaybackController::new(),
+    fn new(settings: &Settings) -> Self {
+        let mut playback = PlaybackController::new();
+        playback.set_volume(settings.default_volume_percent / 100.0);
+        playback.set_max_sink_chunks(settings.buffer_chunks as usize);
+
+        Self {
+            playback,
             current_time_percent: 0.0,
             duration: None,
             current_time: 0.0,
-            volume_percent: 100.0,
+            volume_percent: settings.default_volume_percent,
             playing: false,
-            last_error: None,
-            zoom_factor: 1.0,
+            error_report: None,
+            zoom_factor: settings.default_zoom,
             window_title: "Proteus Player".to_owned(),
             pending_title_tooltip: None,
-            menu_open: false,
+            open_menu: None,
+            recent_submenu_open: false,
+            shortcuts_open: false,
+            notifications_open: false,
+            diagnostics_open: false,
+            resume_offer: None,
+            muted_volume_percent: None,
+            cursor_position: Point::ORIGIN,
+            context_menu: None,
+            loop_in: None,
+            loop_out: None,
+            properties_open: false,
+            properties: None,
+            size: None,
+            position: None,
+            mini: false,
+            full_position: None,
+            visualizer: false,
+            finished_parts: Vec::new(),
+            part_history: Vec::new(),
+            part_levels: Vec::new(),
+            folder: None,
+            folder_scanned_for: None,
+            waveform: None,
+            waveform_requested: false,
+            loading: None,
+            file_watch: FileWatch::default(),
             timeline_override_until: None,
             volume_override_until: None,
-        };
+        }
+    }
 
-        if let Some(path) = path {
-            let _ = window.load(path);
+    /// Starts opening `path`, replacing any load already under way.
+    fn begin_load(&mut self, generation: u64, path: PathBuf, purpose: LoadPurpose) {
+        self.cancel_load();
+        self.playback.load(generation, path.clone());
+        self.loading = Some(PendingLoad {
+            generation,
+            path,
+            started_at: Instant::now(),
+            purpose,
+        });
+    }
+
+    /// Takes the pending load a finished build belongs to, or `None` when
+    /// that load has been cancelled or replaced since.
+    fn take_pending_load(&mut self, generation: u64) -> Option<PendingLoad> {
+        self.loading
+            .take_if(|pending| pending.generation == generation)
+    }
+
+    /// What the file being opened is busy with.
+    pub(crate) fn load_stage(&self) -> Option<LoadStage> {
+        let pending = self.loading.as_ref()?;
+        self.playback.load_stage(pending.generation)
+    }
+
+    /// Swaps in the player built for `generation`.
+    fn finish_load(&mut self, generation: u64, path: &Path) {
+        self.playback.commit_load(generation);
+
+        self.file_watch = FileWatch::loaded(FileStamp::read(path));
+        self.error_report = None;
+        self.loop_in = None;
+        self.loop_out = None;
+        self.properties_open = false;
+        self.properties = None;
+        self.part_history.clear();
+        self.part_levels.clear();
+        self.waveform = None;
+        self.waveform_requested = false;
+        if let Some(name) = path.file_name() {
+            let name = name.to_string_lossy().into_owned();
+            self.window_title = name.clone();
+            self.pending_title_tooltip = Some(name);
         }
+    }
 
-        window
+    /// Stops waiting for the file being opened. The player thread may still
+    /// build it to the end, but then drops it.
+    pub(crate) fn cancel_load(&mut self) {
+        if let Some(pending) = self.loading.take() {
+            log::info!("load cancelled path={:?}", pending.path);
+            self.playback.discard_load();
+        }
     }
 
-    fn load(&mut self, path: PathBuf) -> bool {
-        match self.playback.load(&path) {
-            Ok(()) => {
-                self.last_error = None;
-                if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                    self.window_title = name.to_owned();
-                    self.pending_title_tooltip = Some(name.to_owned());
-                }
-                true
-            }
-            Err(err @ PlaybackLoadError::UnsupportedFormat { .. }) => {
-                self.last_error = Some(err.to_string());
-                false
-            }
-            Err(err) => {
-                self.last_error = Some(format!("Failed to load file: {err}"));
-                false
+    /// Adds the output's latest level to each part's lane, split by how much
+    /// of the output the part makes up at this point of the file.
+    fn record_part_history(&mut self) {
+        let ids = self.playback.combination();
+        let fraction = self
+            .duration
+            .filter(|duration| *duration > 0.0)
+            .map_or(0.0, |duration| self.current_time / duration);
+        let unmeasured = Waveform::default();
+        let waveform = match &self.waveform {
+            Some(Ok(waveform)) => waveform,
+            _ => &unmeasured,
+        };
+        let shares = waveform.part_shares(&ids, fraction, |slot| self.part_level(slot));
+        let output = self.playback.output_level();
+
+        self.part_history
+            .resize_with(ids.len(), || VecDeque::with_capacity(LEVEL_HISTORY_LEN));
+        for (history, share) in self.part_history.iter_mut().zip(shares) {
+            if history.len() == LEVEL_HISTORY_LEN {
+                history.pop_front();
             }
+            history.push_back(Level {
+                peak: share.peak * output,
+                rms: share.rms * output,
+            });
         }
     }
 
     fn refresh_status(&mut self) {
+        self.playback.refresh();
+        if let Some(error) = self.playback.take_output_error() {
+            log::warn!("playback failed error={error}");
+            let path = self.playback.current_path().map(PathBuf::from);
+            self.error_report = Some(ErrorReport::new(error, path));
+        }
         let status = self.playback.status();
         let now = Instant::now();
 
@@ -90,6 +338,14 @@ impl PlayerWindowState {
         self.current_time = status.time;
         self.playing = status.playing;
 
+        if self.visualizer {
+            self.finished_parts = self.playback.finished_parts();
+        }
+
+        if status.playing && self.visualizer {
+            self.record_part_history();
+        }
+
         if self
             .timeline_override_until
             .is_none_or(|deadline| now >= deadline)
@@ -112,6 +368,7 @@ impl PlayerWindowState {
     }
 
     pub(crate) fn set_timeline_percent(&mut self, percent: f64) {
+        self.resume_offer = None;
         self.current_time_percent = percent;
         self.timeline_override_until = Some(Instant::now() + Duration::from_millis(250));
 
@@ -121,17 +378,120 @@ impl PlayerWindowState {
     }
 
     pub(crate) fn set_volume_percent(&mut self, percent: f32) {
+        self.muted_volume_percent = None;
         self.volume_percent = percent;
         self.volume_override_until = Some(Instant::now() + Duration::from_millis(250));
         self.playback.set_volume(percent / 100.0);
     }
 
-    pub(crate) fn is_empty(&self) -> bool {
-        !self.playback.is_loaded()
+    pub(crate) fn step_volume(&mut self, up: bool) {
+        let step = if up {
+            VOLUME_STEP_PERCENT
+        } else {
+            -VOLUME_STEP_PERCENT
+        };
+        let percent = self.muted_volume_percent.unwrap_or(self.volume_percent);
+        self.set_volume_percent((percent + step).clamp(0.0, 100.0));
+    }
+
+    pub(crate) fn toggle_mute(&mut self) {
+        match self.muted_volume_percent {
+            Some(percent) => self.set_volume_percent(percent),
+            None if self.volume_percent > 0.0 => {
+                let percent = self.volume_percent;
+                self.set_volume_percent(0.0);
+                self.muted_volume_percent = Some(percent);
+            }
+            None => {}
+        }
+    }
+
+    /// Sets one part's level from the mixer, `0.0` to `1.0`.
+    pub(crate) fn set_part_level(&mut self, slot: usize, level: f32) {
+        if !self.playback.set_part_level(slot, level) {
+            return;
+        }
+        if self.part_levels.len() <= slot {
+            self.part_levels.resize(slot + 1, 1.0);
+        }
+        self.part_levels[slot] = level;
+    }
+
+    pub(crate) fn part_level(&self, slot: usize) -> f32 {
+        self.part_levels.get(slot).copied().unwrap_or(1.0)
+    }
+
+    pub(crate) fn is_muted(&self) -> bool {
+        self.muted_volume_percent.is_some()
+    }
+
+    pub(crate) fn set_cursor_position(&mut self, position: Point) {
+        self.cursor_position = position;
+    }
+
+    pub(crate) fn open_context_menu(&mut self, kind: ContextMenuKind) {
+        self.open_menu = None;
+        self.context_menu = Some(ContextMenu {
+            kind,
+            position: self.cursor_position,
+        });
+    }
+
+    /// Marks the current position as the loop start, dropping an end marker
+    /// that would now come before it.
+    pub(crate) fn set_loop_in(&mut self) {
+        let time = self.playback.status().time;
+        self.loop_in = Some(time);
+        if self.loop_out.is_some_and(|end| end <= time) {
+            self.loop_out = None;
+        }
+        self.apply_loop_region();
+    }
+
+    /// Marks the current position as the loop end, dropping a start marker
+    /// that would now come after it.
+    pub(crate) fn set_loop_out(&mut self) {
+        let time = self.playback.status().time;
+        self.loop_out = Some(time);
+        if self.loop_in.is_some_and(|start| start >= time) {
+            self.loop_in = None;
+        }
+        self.apply_loop_region();
+    }
+
+    pub(crate) fn clear_loop(&mut self) {
+        self.loop_in = None;
+        self.loop_out = None;
+        self.apply_loop_region();
     }
 
-    pub(crate) fn load_path(&mut self, path: PathBuf) -> bool {
-        self.load(path)
+    fn apply_loop_region(&mut self) {
+        // A region needs both markers; without an end the loop runs to the
+        // file's end, which whole-file looping already covers.
+        self.playback
+            .set_loop_region(self.loop_in.zip(self.loop_out));
+    }
+
+    pub(crate) fn playback_menu_state(&self) -> PlaybackMenuState {
+        let status = self.playback.status();
+        PlaybackMenuState {
+            focused: true,
+            loaded: !status.is_empty(),
+            playing: status.playing,
+            muted: self.is_muted(),
+            looping: self.playback.is_looping(),
+            mini: self.mini,
+            visualizer: self.visualizer,
+        }
+    }
+
+    pub(crate) fn toggle_loop(&mut self) {
+        let looping = !self.playback.is_looping();
+        self.playback.set_looping(looping);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        !self.playback.is_loaded()
     }
 }
 
@@ -140,13 +500,64 @@ pub(crate) struct ProteusApp {
     pub(crate) focused_window: Option<window::Id>,
     pub(crate) native_menu: Option<NativeMenu>,
     native_menu_init_attempted: bool,
+    /// Playback menu state last applied to the native menu.
+    native_playback_menu: Option<PlaybackMenuState>,
+    /// Window list last applied to the native Window menu.
+    native_window_menu: Vec<WindowMenuEntry>,
+    pub(crate) tray: Option<SystemTray>,
+    /// Set when the tray could not be shown, so it is not retried every tick
+    /// until the setting is turned off and on again.
+    tray_install_failed: bool,
     app_icon_init_attempted: bool,
+    link: Option<LinkServer>,
+    link_start_failed: bool,
+    /// The window link clients control, once one has opened a file.
+    link_window: Option<window::Id>,
+    link_reported_at: Option<Instant>,
     pub(crate) icons: IconSet,
-    pub(crate) global_error: Option<String>,
-    memory_sampler: Option<MemorySampler>,
+    pub(crate) notifications: Notifications,
+    pub(crate) library: LibraryState,
+    pub(crate) library_window: Option<window::Id>,
+    pub(crate) settings: Settings,
+    pub(crate) themes: Themes,
+    /// The resolved palette and Iced theme for `settings.theme`.
+    pub(crate) palette: Palette,
+    pub(crate) theme: Theme,
+    themes_checked_at: Option<Instant>,
+    loaded_files_checked_at: Option<Instant>,
+    loaded_files_watcher: Option<FolderWatcher>,
+    loaded_files_watcher_init_attempted: bool,
+    pub(crate) preferences_window: Option<window::Id>,
+    pub(crate) keybindings: Keybindings,
+    /// The action waiting for a key press in the preferences window.
+    pub(crate) recording_shortcut: Option<Action>,
+    pub(crate) shortcut_notice: Option<String>,
+    pub(crate) command_palette: Option<CommandPalette>,
+    keybindings_generation: u64,
+    keybindings_persist_requested: bool,
+    keybindings_persist_in_flight: bool,
+    settings_generation: u64,
+    settings_persist_requested: bool,
+    settings_persist_in_flight: bool,
+    session: Session,
+    session_captured_at: Option<Instant>,
+    session_persist_requested: bool,
+    session_persist_in_flight: bool,
+    pub(crate) memory_sampler: MemorySampler,
+    resume_positions: ResumePositions,
+    resume_positions_generation: u64,
+    resume_positions_persist_requested: bool,
+    resume_positions_persist_in_flight: bool,
+    bookmarks: Bookmarks,
+    bookmarks_generation: u64,
+    bookmarks_persist_requested: bool,
+    bookmarks_persist_in_flight: bool,
     pending_file_pick_target: FilePickTarget,
     file_dialog_generation: u64,
     active_file_dialog_generation: Option<u64>,
+    /// Counts file loads across all windows, so a stale result is never
+    /// mistaken for the pending one.
+    load_generation: u64,
     #[cfg(target_os = "macos")]
     macos_open_dialog: Option<crate::app::effects::MacOpenDialog>,
     startup_open_dialog_due_at: Option<Instant>,
@@ -158,19 +569,65 @@ pub(crate) struct ProteusApp {
 }
 
 impl ProteusApp {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(settings: Settings) -> Self {
+        let themes = Themes::new();
+        let palette = themes.palette(&settings.theme);
+        let theme = palette.iced_theme(settings.theme.to_string());
+
         Self {
             windows: HashMap::new(),
             focused_window: None,
             native_menu: None,
             native_menu_init_attempted: false,
+            native_playback_menu: None,
+            native_window_menu: Vec::new(),
+            tray: None,
+            tray_install_failed: false,
             app_icon_init_attempted: false,
+            link: None,
+            link_start_failed: false,
+            link_window: None,
+            link_reported_at: None,
             icons: IconSet::new(),
-            global_error: None,
-            memory_sampler: MemorySampler::from_feat(),
+            notifications: Notifications::default(),
+            library: LibraryState::new(),
+            library_window: None,
+            settings,
+            themes,
+            palette,
+            theme,
+            themes_checked_at: None,
+            loaded_files_checked_at: None,
+            loaded_files_watcher: None,
+            loaded_files_watcher_init_attempted: false,
+            preferences_window: None,
+            keybindings: Keybindings::default(),
+            recording_shortcut: None,
+            shortcut_notice: None,
+            command_palette: None,
+            keybindings_generation: 0,
+            keybindings_persist_requested: false,
+            keybindings_persist_in_flight: false,
+            settings_generation: 0,
+            settings_persist_requested: false,
+            settings_persist_in_flight: false,
+            session: Session::default(),
+            session_captured_at: None,
+            session_persist_requested: false,
+            session_persist_in_flight: false,
+            memory_sampler: MemorySampler::new(),
+            resume_positions: ResumePositions::default(),
+            resume_positions_generation: 0,
+            resume_positions_persist_requested: false,
+            resume_positions_persist_in_flight: false,
+            bookmarks: Bookmarks::default(),
+            bookmarks_generation: 0,
+            bookmarks_persist_requested: false,
+            bookmarks_persist_in_flight: false,
             pending_file_pick_target: FilePickTarget::NewWindow,
             file_dialog_generation: 0,
             active_file_dialog_generation: None,
+            load_generation: 0,
             #[cfg(target_os = "macos")]
             macos_open_dialog: None,
             startup_open_dialog_due_at: None,
@@ -183,32 +640,210 @@ impl ProteusApp {
     }
 
     pub(crate) fn open_window(&mut self, path: Option<PathBuf>) -> Task<Message> {
-        let (window_id, task) = open_player_window();
-        let recent_path = path.clone();
-        let window_state = PlayerWindowState::new(path);
-
-        if window_state.playback.is_loaded()
-            && let Some(path) = recent_path
-        {
-            self.record_recent_file(path);
-        }
+        self.insert_window(path, None, LoadPurpose::Open).1
+    }
 
+    fn insert_window(
+        &mut self,
+        path: Option<PathBuf>,
+        size: Option<Size>,
+        purpose: LoadPurpose,
+    ) -> (window::Id, Task<Message>) {
+        let (window_id, task) = open_player_window(size);
+        let mut window_state = PlayerWindowState::new(&self.settings);
+        window_state.size = size;
         self.windows.insert(window_id, window_state);
+
         self.focused_window = Some(window_id);
-        self.log_memory_event("window_opened");
+        self.sample_memory_event("window_opened");
 
-        task.map(Message::WindowOpened)
+        if let Some(path) = path {
+            self.start_load(window_id, path, purpose);
+        }
+        (window_id, task.map(Message::WindowOpened))
+    }
+
+    /// Starts opening a file in a window, superseding any file the window
+    /// was already opening.
+    fn start_load(&mut self, window_id: window::Id, path: PathBuf, purpose: LoadPurpose) {
+        let Some(window) = self.windows.get_mut(&window_id) else {
+            return;
+        };
+
+        self.load_generation = self.load_generation.wrapping_add(1);
+        log::info!("load started path={path:?} purpose={}", purpose.name());
+        window.begin_load(self.load_generation, path, purpose);
+    }
+
+    /// Finishes loads whose players have been built since the last tick.
+    pub(crate) fn poll_loads(&mut self) {
+        let finished: Vec<_> = self
+            .windows
+            .iter_mut()
+            .filter_map(|(window_id, window)| {
+                let (generation, result) = window.playback.poll_load()?;
+                Some((*window_id, generation, result))
+            })
+            .collect();
+
+        for (window_id, generation, result) in finished {
+            self.file_load_finished(window_id, generation, result);
+        }
+    }
+
+    /// Swaps in a player built on the window's player thread, unless its
+    /// load was cancelled or superseded in the meantime.
+    fn file_load_finished(
+        &mut self,
+        window_id: window::Id,
+        generation: u64,
+        result: Result<(), PlaybackLoadError>,
+    ) {
+        let Some(window) = self.windows.get_mut(&window_id) else {
+            return;
+        };
+        let Some(pending) = window.take_pending_load(generation) else {
+            log::debug!("load dropped generation={generation}");
+            return;
+        };
+
+        let elapsed_ms = pending.started_at.elapsed().as_millis();
+        if let Err(error) = result {
+            log::warn!(
+                "load failed path={:?} kind={} elapsed_ms={elapsed_ms} error={error}",
+                pending.path,
+                error.kind()
+            );
+            let failed = link::Event::LoadFailed {
+                path: pending.path.clone(),
+                error: error.to_string(),
+            };
+            window.error_report = Some(ErrorReport::new(error, Some(pending.path)));
+            if let (LoadPurpose::Link { .. }, Some(link)) = (pending.purpose, &self.link) {
+                link.broadcast(&failed);
+            }
+            return;
+        }
+        log::info!(
+            "load finished path={:?} elapsed_ms={elapsed_ms}",
+            pending.path
+        );
+        window.finish_load(generation, &pending.path);
+
+        match pending.purpose {
+            LoadPurpose::Open => self.file_opened(window_id, pending.path),
+            LoadPurpose::Step { was_playing } => {
+                if was_playing {
+                    window.playback.play_pause();
+                }
+                self.file_opened(window_id, pending.path);
+            }
+            LoadPurpose::Restore(saved) => {
+                window.zoom_factor = saved.zoom_factor;
+                window.set_volume_percent(saved.volume_percent);
+                window.playback.start_at(saved.position, saved.playing);
+
+                // proteus-lib picks the takes itself and cannot be told which
+                // ones to play, so a changed combination is only reported.
+                let combination = window.playback.combination();
+                if !saved.combination.is_empty() && combination != saved.combination {
+                    let message = format!(
+                        "{} is playing different takes than last session ({} instead of {})",
+                        window.window_title,
+                        combination.join("-"),
+                        saved.combination.join("-"),
+                    );
+                    self.notify(Severity::Info, message);
+                }
+            }
+            LoadPurpose::Reload(kept) => {
+                let duration = window.playback.status().duration.unwrap_or_default();
+                window
+                    .playback
+                    .start_at(kept.position.min(duration), kept.playing);
+                for (slot, level) in kept.part_levels.into_iter().enumerate() {
+                    window.set_part_level(slot, level);
+                }
+                window.loop_in = kept.loop_in.filter(|start| *start < duration);
+                window.loop_out = kept.loop_out.filter(|end| *end <= duration);
+                window.apply_loop_region();
+
+                let message = format!("File updated: {}", window.window_title);
+                self.notify(Severity::Info, message);
+            }
+            LoadPurpose::Bookmark { position } => {
+                let duration = window.playback.status().duration.unwrap_or_default();
+                window.playback.start_at(position.min(duration), false);
+                self.record_recent_file(pending.path);
+            }
+            LoadPurpose::Link { position, play } => {
+                // The position was checked before the file's length was known.
+                let duration = window.playback.status().duration.unwrap_or_default();
+                let position = position.unwrap_or_default().min(duration);
+                window.playback.start_at(position, play);
+                let loaded = link::Event::Loaded {
+                    path: pending.path,
+                    duration: window.playback.status().duration,
+                };
+                if let Some(link) = &self.link {
+                    link.broadcast(&loaded);
+                }
+            }
+        }
     }
 
     pub(crate) fn close_window_state(&mut self, window_id: window::Id) {
+        // Snapshot first so the closing window's final position is kept if it
+        // turns out to be the last one, then again to drop it from the session.
+        self.capture_session();
+        self.remove_window_state(window_id);
+        self.capture_session();
+    }
+
+    /// Closes several windows together. The session is captured once before
+    /// any of them go, so it keeps them all rather than only the last one
+    /// closed.
+    fn close_windows_state(&mut self, window_ids: &[window::Id]) {
+        self.capture_session();
+        for window_id in window_ids {
+            self.remove_window_state(*window_id);
+        }
+    }
+
+    fn remove_window_state(&mut self, window_id: window::Id) {
         if let Some(mut window) = self.windows.remove(&window_id) {
             window.playback.shutdown();
         }
 
+        if self.library_window == Some(window_id) {
+            self.library_window = None;
+        }
+
+        if self
+            .command_palette
+            .as_ref()
+            .is_some_and(|palette| palette.window_id == window_id)
+        {
+            self.command_palette = None;
+        }
+
+        if self.preferences_window == Some(window_id) {
+            self.preferences_window = None;
+            self.recording_shortcut = None;
+            self.shortcut_notice = None;
+        }
+
         if self.focused_window == Some(window_id) {
             self.focused_window = self.windows.keys().next().copied();
         }
-        self.log_memory_event("window_closed");
+
+        if self.link_window == Some(window_id) {
+            self.link_window = None;
+            if let Some(link) = &self.link {
+                link.broadcast(&link::Event::Closed);
+            }
+        }
+        self.sample_memory_event("window_closed");
     }
 
     pub(crate) fn ensure_native_menu(&mut self) {
@@ -217,14 +852,138 @@ impl ProteusApp {
         }
 
         self.native_menu_init_attempted = true;
-        match NativeMenu::install() {
+        let keybindings = &self.keybindings;
+        match NativeMenu::install(|action| keybindings.menu_accelerator(action)) {
             Ok(menu) => {
                 self.native_menu = Some(menu);
             }
             Err(err) => {
-                self.global_error = Some(format!("Failed to install native menu: {err}"));
+                self.notify(
+                    Severity::Error,
+                    format!("Failed to install native menu: {err}"),
+                );
+            }
+        }
+    }
+
+    /// Starts or stops the link to match the setting.
+    pub(crate) fn sync_link(&mut self) {
+        if !self.settings.link_enabled {
+            self.link = None;
+            self.link_window = None;
+            self.link_start_failed = false;
+            return;
+        }
+
+        if self.link.is_none() && !self.link_start_failed {
+            match LinkServer::start() {
+                Ok(link) => self.link = Some(link),
+                Err(err) => {
+                    self.link_start_failed = true;
+                    self.notify(
+                        Severity::Warning,
+                        format!("Proteus Author cannot connect: {err}"),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Handles requests from link clients and reports the link window's
+    /// position to them.
+    pub(crate) fn poll_link(&mut self) -> Option<Task<Message>> {
+        let mut tasks = Vec::new();
+        while let Some((client, request)) = self.link.as_ref().and_then(LinkServer::poll_request) {
+            log::debug!("link request client={client} request={request:?}");
+            let result = self.handle_link_command(request.command, client, &mut tasks);
+            if let Some(link) = &self.link {
+                link.send(client, &link::Event::reply(request.id, result));
+            }
+        }
+
+        if self
+            .link_reported_at
+            .is_none_or(|reported_at| reported_at.elapsed() >= LINK_REPORT_INTERVAL)
+            && let Some(link) = &self.link
+            && link.has_clients()
+            && let Some(position) = self.link_position()
+        {
+            self.link_reported_at = Some(Instant::now());
+            link.broadcast(&position);
+        }
+
+        (!tasks.is_empty()).then(|| Task::batch(tasks))
+    }
+
+    fn handle_link_command(
+        &mut self,
+        command: Command,
+        client: ClientId,
+        tasks: &mut Vec<Task<Message>>,
+    ) -> Result<(), String> {
+        if let Command::Open {
+            path,
+            position,
+            play,
+        } = command
+        {
+            if let Some(position) = position {
+                link::check_position(position, None)?;
+            }
+            let purpose = LoadPurpose::Link { position, play };
+            match self.link_window.filter(|id| self.windows.contains_key(id)) {
+                Some(window_id) => self.start_load(window_id, path, purpose),
+                None => {
+                    let (window_id, task) = self.open_external_path(path, purpose);
+                    self.link_window = Some(window_id);
+                    tasks.push(task);
+                }
+            }
+            return Ok(());
+        }
+
+        if let Command::Status = command {
+            let position = self
+                .link_position()
+                .ok_or_else(|| "nothing is open; send open first".to_owned())?;
+            if let Some(link) = &self.link {
+                link.send(client, &position);
             }
+            return Ok(());
         }
+
+        let window = self
+            .link_window
+            .and_then(|window_id| self.windows.get_mut(&window_id))
+            .filter(|window| !window.is_empty())
+            .ok_or_else(|| "nothing is loaded; send open first".to_owned())?;
+        match command {
+            Command::Hello { .. } | Command::Open { .. } | Command::Status => {}
+            Command::Seek { position } => {
+                link::check_position(position, window.playback.status().duration)?;
+                window.resume_offer = None;
+                window.playback.seek(position);
+            }
+            Command::Play => {
+                if !window.playback.status().playing {
+                    window.playback.play_pause();
+                }
+            }
+            Command::Pause => window.playback.pause(),
+        }
+        Ok(())
+    }
+
+    fn link_position(&self) -> Option<link::Event> {
+        let window = self.windows.get(&self.link_window?)?;
+        let status = window.playback.status();
+        Some(link::Event::Position {
+            path: window.playback.current_path().map(PathBuf::from),
+            time: status.time,
+            duration: status.duration,
+            playing: status.playing,
+            combination: window.playback.combination(),
+        })
     }
 
     pub(crate) fn ensure_app_icon(&mut self) {
@@ -234,7 +993,7 @@ impl ProteusApp {
 
         self.app_icon_init_attempted = true;
         if let Err(err) = set_macos_app_icon_from_bytes() {
-            self.global_error = Some(format!("Failed to set app icon: {err}"));
+            self.notify(Severity::Warning, format!("Failed to set app icon: {err}"));
         }
     }
 
@@ -242,7 +1001,153 @@ impl ProteusApp {
         for window in self.windows.values_mut() {
             window.refresh_status();
         }
-        self.log_memory_tick();
+        let counts = self.player_counts();
+        let count_threads = self.diagnostics_visible();
+        self.memory_sampler.set_count_threads(count_threads);
+        self.memory_sampler.maybe_sample_periodic(counts);
+    }
+
+    pub(crate) fn window_resized(&mut self, window_id: window::Id, size: Size) {
+        if let Some(window) = self.windows.get_mut(&window_id)
+            && !window.mini
+            && !window.visualizer
+        {
+            window.size = Some(size);
+        }
+    }
+
+    /// Tracks where each window is, remembering the mini player's position
+    /// across launches.
+    pub(crate) fn window_moved(&mut self, window_id: window::Id, position: Point) {
+        let Some(window) = self.windows.get_mut(&window_id) else {
+            return;
+        };
+        window.position = Some(position);
+
+        let position = ScreenPosition {
+            x: position.x,
+            y: position.y,
+        };
+        if window.mini && self.settings.mini_player_position != Some(position) {
+            self.settings.mini_player_position = Some(position);
+            self.request_settings_persist();
+        }
+    }
+
+    /// Fills the screen with the window's visualizer, or returns it to a window.
+    pub(crate) fn toggle_visualizer(&mut self, window_id: window::Id) -> Task<Message> {
+        let Some(window) = self
+            .windows
+            .get_mut(&window_id)
+            .filter(|window| !window.mini)
+        else {
+            return Task::none();
+        };
+        window.visualizer = !window.visualizer;
+        window.open_menu = None;
+        window.context_menu = None;
+        window.finished_parts.clear();
+
+        let mode = if window.visualizer {
+            window::Mode::Fullscreen
+        } else {
+            window::Mode::Windowed
+        };
+        window::set_mode(window_id, mode)
+    }
+
+    /// Shrinks a player window to the mini player, or restores it to the size
+    /// and place it had before.
+    pub(crate) fn toggle_mini_player(&mut self, window_id: window::Id) -> Task<Message> {
+        let Some(window) = self
+            .windows
+            .get_mut(&window_id)
+            .filter(|window| !window.visualizer)
+        else {
+            return Task::none();
+        };
+        window.mini = !window.mini;
+        window.open_menu = None;
+        window.context_menu = None;
+
+        if window.mini {
+            window.full_position = window.position;
+            let position = self
+                .settings
+                .mini_player_position
+                .map(|position| Point::new(position.x, position.y));
+            enter_mini_player(window_id, position)
+        } else {
+            leave_mini_player(window_id, window.size, window.full_position.take())
+        }
+    }
+
+    /// Windows whose file's folder has not been listed since it was loaded.
+    pub(crate) fn take_folder_scans(&mut self) -> Vec<(window::Id, PathBuf)> {
+        let mut scans = Vec::new();
+        for (window_id, window) in &mut self.windows {
+            let Some(path) = window.playback.current_path() else {
+                continue;
+            };
+            if window.folder_scanned_for.as_deref() == Some(path) {
+                continue;
+            }
+
+            let path = path.to_path_buf();
+            window.folder_scanned_for = Some(path.clone());
+            scans.push((*window_id, path));
+        }
+        scans
+    }
+
+    pub(crate) fn folder_scanned(
+        &mut self,
+        window_id: window::Id,
+        path: PathBuf,
+        contents: FolderContents,
+    ) {
+        // A scan for a file the window has since moved on from is stale.
+        if let Some(window) = self.windows.get_mut(&window_id)
+            && window.playback.current_path() == Some(path.as_path())
+        {
+            window.folder = Some(contents);
+        }
+    }
+
+    /// Windows whose file has loaded without its waveform being measured.
+    /// Reloading a file measures it again, as its audio may have changed.
+    pub(crate) fn take_waveform_reads(&mut self) -> Vec<(window::Id, PathBuf)> {
+        let mut reads = Vec::new();
+        for (window_id, window) in &mut self.windows {
+            if window.waveform_requested {
+                continue;
+            }
+            let Some(path) = window.playback.current_path() else {
+                continue;
+            };
+
+            window.waveform_requested = true;
+            reads.push((*window_id, path.to_path_buf()));
+        }
+        reads
+    }
+
+    pub(crate) fn waveform_read(
+        &mut self,
+        window_id: window::Id,
+        path: PathBuf,
+        result: Result<Waveform, String>,
+    ) {
+        if let Err(error) = &result {
+            log::warn!("waveform unavailable path={path:?} error={error}");
+        }
+        // A waveform for a file the window has since moved on from is stale.
+        if let Some(window) = self.windows.get_mut(&window_id)
+            && window.waveform_requested
+            && window.playback.current_path() == Some(path.as_path())
+        {
+            window.waveform = Some(result);
+        }
     }
 
     pub(crate) fn take_pending_title_tooltips(&mut self) -> Vec<(window::Id, String)> {
@@ -276,7 +1181,10 @@ impl ProteusApp {
         if let Some(menu) = &mut self.native_menu
             && let Err(err) = menu.set_recent_files(&self.recent_files)
         {
-            self.global_error = Some(format!("Failed to update recent-files menu: {err}"));
+            self.notify(
+                Severity::Warning,
+                format!("Failed to update recent-files menu: {err}"),
+            );
         }
     }
 
@@ -284,7 +1192,10 @@ impl ProteusApp {
         let files = match result {
             Ok(files) => files,
             Err(error) => {
-                self.global_error = Some(format!("Failed to load recent files: {error}"));
+                self.notify(
+                    Severity::Error,
+                    format!("Failed to load recent files: {error}"),
+                );
                 return;
             }
         };
@@ -314,7 +1225,10 @@ impl ProteusApp {
         self.recent_files_persist_in_flight = false;
 
         if let Err(error) = result {
-            self.global_error = Some(format!("Failed to save recent files: {error}"));
+            self.notify(
+                Severity::Warning,
+                format!("Failed to save recent files: {error}"),
+            );
         }
 
         if generation != self.recent_files_generation {
@@ -322,55 +1236,1133 @@ impl ProteusApp {
         }
     }
 
-    fn record_recent_file(&mut self, path: PathBuf) {
-        const MAX_RECENT_FILES: usize = 10;
-
-        self.recent_files.retain(|recent| recent != &path);
-        self.recent_files.insert(0, path);
-        self.recent_files.truncate(MAX_RECENT_FILES);
-        self.recent_files_generation = self.recent_files_generation.wrapping_add(1);
-        self.recent_files_validation_requested = true;
-    }
-
-    pub(crate) fn handle_menu_action(&mut self, action: MenuAction) -> Task<Message> {
-        match action {
-            MenuAction::About => show_about_dialog(),
-            MenuAction::NewWindow => self.start_new_window_open_dialog(),
-            MenuAction::Open => self.start_open_command_dialog(),
-            MenuAction::OpenRecent(path) => self.handle_external_open_path(path),
-            MenuAction::ZoomIn => {
-                if let Some(window_id) = self.focused_window
-                    && let Some(window) = self.windows.get_mut(&window_id)
-                {
-                    window.zoom_factor = (window.zoom_factor + 0.1).min(2.0);
-                }
-                Task::none()
-            }
-            MenuAction::ZoomOut => {
-                if let Some(window_id) = self.focused_window
-                    && let Some(window) = self.windows.get_mut(&window_id)
-                {
-                    window.zoom_factor = (window.zoom_factor - 0.1).max(0.5);
-                }
-                Task::none()
-            }
-        }
-    }
-
-    pub(crate) fn toggle_window_menu(&mut self, window_id: window::Id) {
+    /// Records a file just loaded into a window and offers to resume it.
+    fn file_opened(&mut self, window_id: window::Id, path: PathBuf) {
         if let Some(window) = self.windows.get_mut(&window_id) {
-            window.menu_open = !window.menu_open;
+            window.resume_offer = self.resume_positions.get(&path).cloned();
         }
+        self.record_recent_file(path);
     }
 
-    pub(crate) fn close_window_menu(&mut self, window_id: window::Id) {
-        if let Some(window) = self.windows.get_mut(&window_id) {
-            window.menu_open = false;
+    pub(crate) fn set_resume_positions(&mut self, result: Result<ResumePositions, String>) {
+        match result {
+            Ok(positions) => self.resume_positions = positions,
+            Err(error) => {
+                self.notify(
+                    Severity::Error,
+                    format!("Failed to load playback positions: {error}"),
+                );
+            }
         }
     }
 
+    pub(crate) fn accept_resume_offer(&mut self, window_id: window::Id) {
+        let Some(window) = self.windows.get_mut(&window_id) else {
+            return;
+        };
+        let Some(offer) = window.resume_offer.take() else {
+            return;
+        };
+
+        window.playback.start_at(offer.position, true);
+    }
+
+    pub(crate) fn decline_resume_offer(&mut self, window_id: window::Id) {
+        let Some(window) = self.windows.get_mut(&window_id) else {
+            return;
+        };
+        if window.resume_offer.take().is_none() {
+            return;
+        }
+
+        window.playback.start_at(0.0, true);
+        if let Some(path) = window.playback.current_path()
+            && self.resume_positions.remove(path)
+        {
+            self.request_resume_positions_persist();
+        }
+    }
+
+    fn record_resume_positions(&mut self) {
+        let mut changed = false;
+        for window in self.windows.values() {
+            // A pending offer means the saved position has not been used yet;
+            // recording the untouched start would discard it.
+            if window.resume_offer.is_some() {
+                continue;
+            }
+            let Some(path) = window.playback.current_path() else {
+                continue;
+            };
+            changed |= self.resume_positions.record(
+                path,
+                window.current_time,
+                window.duration,
+                window.playback.combination(),
+            );
+        }
+
+        if changed {
+            self.request_resume_positions_persist();
+        }
+    }
+
+    pub(crate) fn take_resume_positions_to_persist(&mut self) -> Option<(u64, ResumePositions)> {
+        if !self.resume_positions_persist_requested || self.resume_positions_persist_in_flight {
+            return None;
+        }
+
+        self.resume_positions_persist_requested = false;
+        self.resume_positions_persist_in_flight = true;
+        Some((
+            self.resume_positions_generation,
+            self.resume_positions.clone(),
+        ))
+    }
+
+    pub(crate) fn resume_positions_persisted(
+        &mut self,
+        generation: u64,
+        result: Result<(), String>,
+    ) {
+        self.resume_positions_persist_in_flight = false;
+
+        if let Err(error) = result {
+            self.notify(
+                Severity::Warning,
+                format!("Failed to save playback positions: {error}"),
+            );
+        }
+
+        if generation != self.resume_positions_generation {
+            self.resume_positions_persist_requested = true;
+        }
+    }
+
+    fn request_resume_positions_persist(&mut self) {
+        self.resume_positions_generation = self.resume_positions_generation.wrapping_add(1);
+        self.resume_positions_persist_requested = true;
+    }
+
+    pub(crate) fn set_bookmarks(&mut self, result: Result<Bookmarks, String>) {
+        match result {
+            Ok(bookmarks) => self.bookmarks = bookmarks,
+            Err(error) => {
+                self.notify(
+                    Severity::Error,
+                    format!("Failed to load bookmarks: {error}"),
+                );
+            }
+        }
+    }
+
+    /// Bookmarked positions in the file a window is playing.
+    pub(crate) fn window_bookmarks(&self, window: &PlayerWindowState) -> Vec<f64> {
+        window
+            .playback
+            .current_path()
+            .map(|path| self.bookmarks.for_path(path))
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn take_bookmarks_to_persist(&mut self) -> Option<(u64, Bookmarks)> {
+        if !self.bookmarks_persist_requested || self.bookmarks_persist_in_flight {
+            return None;
+        }
+
+        self.bookmarks_persist_requested = false;
+        self.bookmarks_persist_in_flight = true;
+        Some((self.bookmarks_generation, self.bookmarks.clone()))
+    }
+
+    pub(crate) fn bookmarks_persisted(&mut self, generation: u64, result: Result<(), String>) {
+        self.bookmarks_persist_in_flight = false;
+
+        if let Err(error) = result {
+            self.notify(
+                Severity::Warning,
+                format!("Failed to save bookmarks: {error}"),
+            );
+        }
+
+        if generation != self.bookmarks_generation {
+            self.bookmarks_persist_requested = true;
+        }
+    }
+
+    /// Runs a context menu command in the window it was opened in.
+    pub(crate) fn handle_context_action(
+        &mut self,
+        window_id: window::Id,
+        action: ContextAction,
+    ) -> Task<Message> {
+        let Some(window) = self.windows.get_mut(&window_id) else {
+            return Task::none();
+        };
+        window.context_menu = None;
+        let path = window.playback.current_path().map(PathBuf::from);
+
+        match action {
+            ContextAction::PlayPause => {
+                window.resume_offer = None;
+                window.playback.play_pause();
+                Task::none()
+            }
+            ContextAction::CopyTime => {
+                iced::clipboard::write(format_time(window.playback.status().time))
+            }
+            ContextAction::SetLoopIn => {
+                window.set_loop_in();
+                Task::none()
+            }
+            ContextAction::SetLoopOut => {
+                window.set_loop_out();
+                Task::none()
+            }
+            ContextAction::ClearLoop => {
+                window.clear_loop();
+                Task::none()
+            }
+            ContextAction::AddBookmark => {
+                let position = window.playback.status().time;
+                if let Some(path) = path
+                    && self.bookmarks.add(&path, position)
+                {
+                    self.bookmarks_generation = self.bookmarks_generation.wrapping_add(1);
+                    self.bookmarks_persist_requested = true;
+                }
+                Task::none()
+            }
+            ContextAction::GoToBookmark(position) => {
+                window.resume_offer = None;
+                window.playback.seek(position);
+                Task::none()
+            }
+            ContextAction::CopyCombination => match window.playback.combination_code() {
+                Some(code) => iced::clipboard::write(code),
+                None => Task::none(),
+            },
+            ContextAction::RevealInFileManager => {
+                if let Some(path) = path
+                    && let Err(error) = reveal_in_file_manager(&path)
+                {
+                    self.notify(Severity::Warning, format!("Failed to reveal file: {error}"));
+                }
+                Task::none()
+            }
+            ContextAction::ShowProperties => {
+                let Some(path) = path else {
+                    return Task::none();
+                };
+                window.properties_open = true;
+                window.properties = None;
+                read_file_properties(path)
+                    .map(move |result| Message::PropertiesLoaded { window_id, result })
+            }
+            ContextAction::ToggleMiniPlayer => self.toggle_mini_player(window_id),
+            ContextAction::OpenInNewWindow => match path {
+                Some(path) => self.open_window(Some(path)),
+                None => Task::none(),
+            },
+        }
+    }
+
+    pub(crate) fn properties_loaded(
+        &mut self,
+        window_id: window::Id,
+        result: Result<FileProperties, String>,
+    ) {
+        // Ignore results for a file that has since been replaced.
+        if let Some(window) = self.windows.get_mut(&window_id)
+            && window.properties_open
+            && result.as_ref().map_or(true, |properties| {
+                window.playback.current_path() == Some(properties.path.as_path())
+            })
+        {
+            window.properties = Some(result);
+        }
+    }
+
+    fn record_recent_file(&mut self, path: PathBuf) {
+        self.recent_files.retain(|recent| recent != &path);
+        self.recent_files.insert(0, path);
+        self.truncate_recent_files();
+    }
+
+    fn truncate_recent_files(&mut self) {
+        self.recent_files
+            .truncate(self.settings.recent_files_limit as usize);
+        self.recent_files_generation = self.recent_files_generation.wrapping_add(1);
+        self.recent_files_validation_requested = true;
+    }
+
+    /// What the Playback menus show for the focused window.
+    pub(crate) fn playback_menu_state(&self) -> PlaybackMenuState {
+        self.focused_window
+            .and_then(|window_id| self.windows.get(&window_id))
+            .map(PlayerWindowState::playback_menu_state)
+            .unwrap_or_default()
+    }
+
+    /// Brings the native Playback menu up to date, touching it only on change.
+    pub(crate) fn sync_playback_menu(&mut self) {
+        let Some(menu) = &self.native_menu else {
+            return;
+        };
+
+        let state = self.playback_menu_state();
+        if self.native_playback_menu != Some(state) {
+            menu.set_playback_state(state);
+            self.native_playback_menu = Some(state);
+        }
+    }
+
+    /// Open player windows in the order they were created.
+    pub(crate) fn window_menu_entries(&self) -> Vec<WindowMenuEntry> {
+        let mut entries: Vec<WindowMenuEntry> = self
+            .windows
+            .iter()
+            .map(|(window_id, window)| WindowMenuEntry {
+                id: *window_id,
+                title: window.window_title.clone(),
+                playing: window.playing,
+            })
+            .collect();
+        entries.sort_by_key(|entry| entry.id);
+        entries
+    }
+
+    /// Brings the native Window menu's list up to date, touching it only on change.
+    pub(crate) fn sync_window_menu(&mut self) {
+        let entries = self.window_menu_entries();
+        if entries == self.native_window_menu {
+            return;
+        }
+
+        if let Some(menu) = &mut self.native_menu
+            && let Err(err) = menu.set_windows(&entries)
+        {
+            self.notify(
+                Severity::Warning,
+                format!("Failed to update window menu: {err}"),
+            );
+        }
+        self.native_window_menu = entries;
+    }
+
+    /// Shows or removes the tray icon to match the setting and keeps its
+    /// menu in step with the open windows.
+    pub(crate) fn sync_tray(&mut self) {
+        if !self.settings.show_tray_icon {
+            self.tray = None;
+            self.tray_install_failed = false;
+            return;
+        }
+
+        if self.tray.is_none() && !self.tray_install_failed {
+            match SystemTray::install() {
+                Ok(tray) => self.tray = Some(tray),
+                Err(err) => {
+                    self.tray_install_failed = true;
+                    self.notify(
+                        Severity::Warning,
+                        format!("Failed to show tray icon: {err}"),
+                    );
+                }
+            }
+        }
+
+        let state = TrayState {
+            windows: self.window_menu_entries(),
+            playing: self
+                .tray_target()
+                .and_then(|window_id| self.windows.get(&window_id))
+                .is_some_and(|window| window.playing),
+        };
+        if let Some(tray) = &mut self.tray
+            && let Err(err) = tray.set_state(state)
+        {
+            self.tray = None;
+            self.tray_install_failed = true;
+            self.notify(
+                Severity::Warning,
+                format!("Failed to update tray icon: {err}"),
+            );
+        }
+    }
+
+    /// The player window the tray's transport items act on: the focused one,
+    /// else the first one playing, else the first one open.
+    fn tray_target(&self) -> Option<window::Id> {
+        self.focused_window
+            .filter(|window_id| self.windows.contains_key(window_id))
+            .or_else(|| {
+                let mut window_ids: Vec<window::Id> = self.windows.keys().copied().collect();
+                window_ids.sort();
+                window_ids
+                    .iter()
+                    .copied()
+                    .find(|window_id| self.windows[window_id].playing)
+                    .or_else(|| window_ids.first().copied())
+            })
+    }
+
+    pub(crate) fn handle_tray_action(&mut self, action: MenuAction) -> Task<Message> {
+        match action {
+            MenuAction::BringAllToFront if self.windows.is_empty() => self.open_window(None),
+            // Sent straight to the tray's target so focus stays where it was.
+            MenuAction::PlayPause | MenuAction::NewCombination | MenuAction::NextFile => {
+                match (self.tray_target(), Action::for_menu(&action)) {
+                    (Some(window_id), Some(action)) => {
+                        Task::done(action_message(action, window_id))
+                    }
+                    _ => Task::none(),
+                }
+            }
+            _ => self.handle_menu_action(action),
+        }
+    }
+
+    /// Whether closing the last window should leave the app running in the tray.
+    pub(crate) fn keeps_running_in_tray(&self) -> bool {
+        self.tray.is_some() && self.settings.keep_running_in_tray
+    }
+
+    pub(crate) fn handle_menu_action(&mut self, action: MenuAction) -> Task<Message> {
+        // Check items toggle themselves when clicked, so resync even when the
+        // action turns out to change nothing.
+        self.native_playback_menu = None;
+
+        match action {
+            MenuAction::About => show_about_dialog(),
+            MenuAction::NewWindow => self.start_new_window_open_dialog(),
+            MenuAction::Open => self.start_open_command_dialog(),
+            MenuAction::OpenRecent(path) => self.handle_external_open_path(path),
+            MenuAction::ShowLibrary => self.show_library(),
+            MenuAction::ShowPreferences => self.show_preferences(),
+            MenuAction::ShowCommandPalette => match self.focused_window {
+                Some(window_id) => self.toggle_command_palette(window_id),
+                None => Task::none(),
+            },
+            MenuAction::ShowShortcuts => {
+                if let Some(window_id) = self.focused_window {
+                    self.toggle_shortcuts(window_id);
+                }
+                Task::none()
+            }
+            MenuAction::ShowNotifications => {
+                if let Some(window_id) = self.focused_window {
+                    self.toggle_notifications(window_id);
+                }
+                Task::none()
+            }
+            MenuAction::ShowDiagnostics => {
+                if let Some(window_id) = self.focused_window {
+                    self.toggle_diagnostics(window_id);
+                }
+                Task::none()
+            }
+            MenuAction::RevealLogs => {
+                if let Err(error) =
+                    logging::log_file_path().and_then(|path| reveal_in_file_manager(&path))
+                {
+                    self.notify(Severity::Warning, format!("Failed to reveal logs: {error}"));
+                }
+                Task::none()
+            }
+            MenuAction::ZoomIn => {
+                if let Some(window_id) = self.focused_window
+                    && let Some(window) = self.windows.get_mut(&window_id)
+                {
+                    window.zoom_factor = (window.zoom_factor + 0.1).min(MAX_ZOOM);
+                }
+                Task::none()
+            }
+            MenuAction::ZoomOut => {
+                if let Some(window_id) = self.focused_window
+                    && let Some(window) = self.windows.get_mut(&window_id)
+                {
+                    window.zoom_factor = (window.zoom_factor - 0.1).max(MIN_ZOOM);
+                }
+                Task::none()
+            }
+            MenuAction::Minimize => match self.focused_window {
+                Some(window_id) => window::minimize(window_id, true),
+                None => Task::none(),
+            },
+            MenuAction::CloseWindow => match self.focused_window {
+                Some(window_id) => {
+                    self.close_window_state(window_id);
+                    window::close(window_id)
+                }
+                None => Task::none(),
+            },
+            MenuAction::Quit => {
+                self.save_before_exit();
+                iced::exit()
+            }
+            MenuAction::BringAllToFront => {
+                // Raise the focused window last so it stays on top.
+                let mut window_ids: Vec<window::Id> = self.windows.keys().copied().collect();
+                window_ids.sort_by_key(|window_id| Some(*window_id) == self.focused_window);
+                Task::batch(window_ids.into_iter().map(raise_window))
+            }
+            MenuAction::PauseAll => {
+                for window in self.windows.values_mut() {
+                    window.playback.pause();
+                }
+                Task::none()
+            }
+            MenuAction::CloseAll => {
+                let window_ids: Vec<window::Id> = self.windows.keys().copied().collect();
+                self.close_windows_state(&window_ids);
+                Task::batch(window_ids.into_iter().map(window::close))
+            }
+            MenuAction::FocusWindow(window_id) => {
+                if self.windows.contains_key(&window_id) {
+                    self.focused_window = Some(window_id);
+                    raise_window(window_id)
+                } else {
+                    Task::none()
+                }
+            }
+            MenuAction::PlayPause
+            | MenuAction::Stop
+            | MenuAction::Restart
+            | MenuAction::NewCombination
+            | MenuAction::NextFile
+            | MenuAction::SeekForward
+            | MenuAction::SeekBackward
+            | MenuAction::VolumeUp
+            | MenuAction::VolumeDown
+            | MenuAction::Mute
+            | MenuAction::ToggleLoop
+            | MenuAction::ToggleMiniPlayer
+            | MenuAction::ToggleVisualizer => {
+                match (self.focused_window, Action::for_menu(&action)) {
+                    (Some(window_id), Some(action)) => {
+                        Task::done(action_message(action, window_id))
+                    }
+                    _ => Task::none(),
+                }
+            }
+        }
+    }
+
+    pub(crate) fn toggle_shortcuts(&mut self, window_id: window::Id) {
+        if let Some(window) = self.windows.get_mut(&window_id) {
+            window.shortcuts_open = !window.shortcuts_open;
+        }
+    }
+
+    pub(crate) fn toggle_notifications(&mut self, window_id: window::Id) {
+        if let Some(window) = self.windows.get_mut(&window_id) {
+            window.notifications_open = !window.notifications_open;
+        }
+    }
+
+    pub(crate) fn toggle_diagnostics(&mut self, window_id: window::Id) {
+        if !self.diagnostics_visible() {
+            self.memory_sampler.reset_frames();
+        }
+        if let Some(window) = self.windows.get_mut(&window_id) {
+            window.diagnostics_open = !window.diagnostics_open;
+        }
+    }
+
+    pub(crate) fn toggle_command_palette(&mut self, window_id: window::Id) -> Task<Message> {
+        let was_open_here = self
+            .command_palette
+            .take()
+            .is_some_and(|palette| palette.window_id == window_id);
+        if was_open_here || !self.windows.contains_key(&window_id) {
+            return Task::none();
+        }
+
+        let mut palette = CommandPalette::new(window_id);
+        palette.search(String::new(), self.command_palette_items());
+        self.command_palette = Some(palette);
+        iced::widget::operation::focus(COMMAND_PALETTE_INPUT)
+    }
+
+    pub(crate) fn search_command_palette(&mut self, query: String) {
+        let items = self.command_palette_items();
+        if let Some(palette) = &mut self.command_palette {
+            palette.search(query, items);
+        }
+    }
+
+    pub(crate) fn command_palette_key(&mut self, window_id: window::Id, key: iced::keyboard::Key) {
+        use iced::keyboard::{Key, key::Named};
+
+        if self
+            .command_palette
+            .as_ref()
+            .is_none_or(|palette| palette.window_id != window_id)
+        {
+            return;
+        }
+
+        match key {
+            Key::Named(Named::Escape) => self.command_palette = None,
+            Key::Named(Named::ArrowDown) => {
+                if let Some(palette) = &mut self.command_palette {
+                    palette.move_selection(true);
+                }
+            }
+            Key::Named(Named::ArrowUp) => {
+                if let Some(palette) = &mut self.command_palette {
+                    palette.move_selection(false);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Closes the palette and returns the chosen command with the window it
+    /// should run in. `None` picks the highlighted match.
+    pub(crate) fn take_command_palette_choice(
+        &mut self,
+        index: Option<usize>,
+    ) -> Option<(window::Id, PaletteCommand)> {
+        let mut palette = self.command_palette.take()?;
+        let index = index.unwrap_or(palette.selected);
+        if index >= palette.matches.len() {
+            return None;
+        }
+        let item = palette.matches.swap_remove(index);
+        Some((palette.window_id, item.command))
+    }
+
+    /// Everything the palette can run: actions first, then recent files and
+    /// the rest of the library.
+    fn command_palette_items(&self) -> Vec<PaletteItem> {
+        let actions = Action::ALL
+            .into_iter()
+            .filter(|action| *action != Action::CommandPalette)
+            .map(|action| PaletteItem {
+                title: action.label().to_owned(),
+                detail: String::new(),
+                shortcut: self
+                    .keybindings
+                    .chords(action)
+                    .first()
+                    .map(ToString::to_string),
+                command: PaletteCommand::Action(action),
+            });
+        let about = PaletteItem {
+            title: "About Proteus Player".to_owned(),
+            detail: String::new(),
+            shortcut: None,
+            command: PaletteCommand::Menu(MenuAction::About),
+        };
+        let recent_files = self.recent_files.iter().map(|path| {
+            let title = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            PaletteItem::file(format!("Open Recent: {title}"), path.clone())
+        });
+        let bookmarks = self
+            .bookmarks
+            .bookmarks
+            .iter()
+            .map(|bookmark| PaletteItem::bookmark(bookmark.path.clone(), bookmark.position));
+        let library_entries = self
+            .library
+            .index
+            .entries
+            .iter()
+            .filter(|entry| !self.recent_files.contains(&entry.path))
+            .map(|entry| PaletteItem::file(entry.title.clone(), entry.path.clone()));
+
+        actions
+            .chain(std::iter::once(about))
+            .chain(recent_files)
+            .chain(bookmarks)
+            .chain(library_entries)
+            .collect()
+    }
+
+    /// Opens a menu bar dropdown, or closes it when it is already open.
+    pub(crate) fn toggle_menu_bar(&mut self, window_id: window::Id, menu: usize) {
+        if let Some(window) = self.windows.get_mut(&window_id) {
+            window.open_menu = (window.open_menu != Some(menu)).then_some(menu);
+            window.recent_submenu_open = false;
+        }
+    }
+
+    pub(crate) fn toggle_recent_submenu(&mut self, window_id: window::Id) {
+        if let Some(window) = self.windows.get_mut(&window_id) {
+            window.recent_submenu_open = !window.recent_submenu_open;
+        }
+    }
+
+    /// Closes the window's menu bar dropdown, returning whether one was open.
+    pub(crate) fn close_menu_bar(&mut self, window_id: window::Id) -> bool {
+        let Some(window) = self.windows.get_mut(&window_id) else {
+            return false;
+        };
+
+        window.recent_submenu_open = false;
+        window.open_menu.take().is_some()
+    }
+
+    /// Closes the window's context menu or properties panel, returning
+    /// whether either was open.
+    pub(crate) fn close_window_popup(&mut self, window_id: window::Id) -> bool {
+        let Some(window) = self.windows.get_mut(&window_id) else {
+            return false;
+        };
+
+        if window.context_menu.take().is_some() {
+            return true;
+        }
+        std::mem::take(&mut window.properties_open)
+    }
+
+    /// The files listed under Open Recent, as last passed to the native menu.
+    pub(crate) fn recent_files(&self) -> &[PathBuf] {
+        &self.recent_files
+    }
+
     pub(crate) fn set_focused_window(&mut self, window_id: window::Id) {
-        self.focused_window = Some(window_id);
+        // Only player windows can be the target of menu and file-open commands.
+        if self.windows.contains_key(&window_id) {
+            self.focused_window = Some(window_id);
+        }
+    }
+
+    pub(crate) fn has_open_windows(&self) -> bool {
+        !self.windows.is_empty()
+            || self.library_window.is_some()
+            || self.preferences_window.is_some()
+    }
+
+    pub(crate) fn is_preferences_window(&self, window_id: window::Id) -> bool {
+        self.preferences_window == Some(window_id)
+    }
+
+    pub(crate) fn show_preferences(&mut self) -> Task<Message> {
+        if let Some(window_id) = self.preferences_window {
+            return window::gain_focus(window_id);
+        }
+
+        let (window_id, task) = open_preferences_window();
+        self.preferences_window = Some(window_id);
+        task.map(Message::WindowOpened)
+    }
+
+    /// Applies an edit from the preferences window to the stored settings and
+    /// to every open player window.
+    pub(crate) fn change_setting(&mut self, change: SettingChange) {
+        let mut settings = self.settings.clone();
+        settings.apply(change);
+        self.replace_settings(settings);
+    }
+
+    pub(crate) fn reset_settings(&mut self) {
+        self.replace_settings(Settings::default());
+    }
+
+    fn replace_settings(&mut self, settings: Settings) {
+        let previous = std::mem::replace(&mut self.settings, settings);
+        let settings = &self.settings;
+
+        // Default volume and zoom only apply to windows opened from now on.
+        if settings.buffer_chunks != previous.buffer_chunks {
+            for window in self.windows.values_mut() {
+                window
+                    .playback
+                    .set_max_sink_chunks(settings.buffer_chunks as usize);
+            }
+        }
+
+        if self.settings.recent_files_limit != previous.recent_files_limit {
+            self.truncate_recent_files();
+        }
+
+        if self.settings.theme != previous.theme {
+            self.refresh_theme();
+        }
+
+        self.request_settings_persist();
+    }
+
+    fn refresh_theme(&mut self) {
+        self.palette = self.themes.palette(&self.settings.theme);
+        self.theme = self.palette.iced_theme(self.settings.theme.to_string());
+    }
+
+    pub(crate) fn set_system_theme(&mut self, mode: iced::theme::Mode) {
+        if self.themes.set_system_mode(mode) {
+            self.refresh_theme();
+        }
+    }
+
+    /// Reloads user themes when the themes file has changed since the last check.
+    pub(crate) fn poll_themes_file(&mut self) -> Option<Task<Message>> {
+        if self
+            .themes_checked_at
+            .is_some_and(|checked_at| checked_at.elapsed() < THEMES_FILE_CHECK_INTERVAL)
+        {
+            return None;
+        }
+        self.themes_checked_at = Some(Instant::now());
+
+        let modified = themes_file_path()
+            .ok()
+            .and_then(|path| fs::metadata(path).ok())
+            .and_then(|metadata| metadata.modified().ok());
+        self.themes.file_changed(modified).then(load_user_themes)
+    }
+
+    /// Reloads loaded files that have been rewritten on disk since they were
+    /// opened, such as a `.prot` re-exported from Proteus Author. File events
+    /// say which files to check; if they are unavailable, every loaded file
+    /// is checked instead.
+    pub(crate) fn poll_loaded_files(&mut self) {
+        if self
+            .loaded_files_checked_at
+            .is_some_and(|checked_at| checked_at.elapsed() < LOADED_FILES_CHECK_INTERVAL)
+        {
+            return;
+        }
+        self.loaded_files_checked_at = Some(Instant::now());
+        self.ensure_loaded_files_watcher();
+
+        let changed_paths = self
+            .loaded_files_watcher
+            .as_ref()
+            .map(FolderWatcher::changed_paths);
+        let mut folders = HashSet::new();
+        let mut changed = Vec::new();
+        for (window_id, window) in &mut self.windows {
+            if window.loading.is_some() {
+                continue;
+            }
+            let Some(path) = window.playback.current_path() else {
+                continue;
+            };
+            // Events name files by the absolute path of the watched folder.
+            let Ok(watched_path) = std::path::absolute(path) else {
+                continue;
+            };
+            if let Some(folder) = watched_path.parent() {
+                folders.insert(folder.to_path_buf());
+            }
+            if changed_paths
+                .as_ref()
+                .is_none_or(|paths| paths.contains(&watched_path))
+            {
+                window.file_watch.mark_changed();
+            }
+
+            if window.file_watch.is_pending() && window.file_watch.check(FileStamp::read(path)) {
+                log::info!("file changed on disk path={path:?}");
+                let kept = KeptPlayback {
+                    position: window.playback.status().time,
+                    playing: window.playing,
+                    part_levels: window.part_levels.clone(),
+                    loop_in: window.loop_in,
+                    loop_out: window.loop_out,
+                };
+                changed.push((*window_id, path.to_path_buf(), kept));
+            }
+        }
+        if let Some(watcher) = &mut self.loaded_files_watcher {
+            watcher.watch_folders(folders);
+        }
+
+        for (window_id, path, kept) in changed {
+            self.start_load(window_id, path, LoadPurpose::Reload(kept));
+        }
+    }
+
+    fn ensure_loaded_files_watcher(&mut self) {
+        if self.loaded_files_watcher_init_attempted {
+            return;
+        }
+
+        self.loaded_files_watcher_init_attempted = true;
+        match FolderWatcher::new() {
+            Ok(watcher) => self.loaded_files_watcher = Some(watcher),
+            // Checking every loaded file each interval still works, so this
+            // is not worth a notice.
+            Err(error) => log::warn!("file events unavailable, polling instead error={error}"),
+        }
+    }
+
+    pub(crate) fn user_themes_loaded(&mut self, result: Result<Vec<UserTheme>, String>) {
+        match result {
+            Ok(themes) => {
+                self.themes.set_user_themes(themes);
+                self.refresh_theme();
+            }
+            Err(error) => {
+                self.notify(Severity::Error, format!("Failed to load themes: {error}"));
+            }
+        }
+    }
+
+    pub(crate) fn take_settings_to_persist(&mut self) -> Option<(u64, Settings)> {
+        if !self.settings_persist_requested || self.settings_persist_in_flight {
+            return None;
+        }
+
+        self.settings_persist_requested = false;
+        self.settings_persist_in_flight = true;
+        Some((self.settings_generation, self.settings.clone()))
+    }
+
+    pub(crate) fn settings_persisted(&mut self, generation: u64, result: Result<(), String>) {
+        self.settings_persist_in_flight = false;
+
+        if let Err(error) = result {
+            self.notify(
+                Severity::Warning,
+                format!("Failed to save settings: {error}"),
+            );
+        }
+
+        if generation != self.settings_generation {
+            self.settings_persist_requested = true;
+        }
+    }
+
+    fn request_settings_persist(&mut self) {
+        self.settings_generation = self.settings_generation.wrapping_add(1);
+        self.settings_persist_requested = true;
+    }
+
+    pub(crate) fn set_keybindings(&mut self, result: Result<Keybindings, String>) {
+        match result {
+            Ok(keybindings) => {
+                let conflicts = keybindings
+                    .conflicts()
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>();
+                if !conflicts.is_empty() {
+                    self.notify(
+                        Severity::Warning,
+                        format!("Conflicting shortcuts: {}", conflicts.join("; ")),
+                    );
+                }
+                self.keybindings = keybindings;
+            }
+            Err(error) => {
+                self.notify(
+                    Severity::Error,
+                    format!("Failed to load keybindings: {error}"),
+                );
+            }
+        }
+    }
+
+    pub(crate) fn start_recording_shortcut(&mut self, action: Action) {
+        self.recording_shortcut = Some(action);
+        self.shortcut_notice = None;
+    }
+
+    /// Binds the chord just pressed to the action being recorded. Escape
+    /// cancels, and chords used by another action are refused. A chord the
+    /// native menu cannot show is kept with a notice saying so.
+    pub(crate) fn record_shortcut(&mut self, chord: KeyChord) {
+        let Some(action) = self.recording_shortcut.take() else {
+            return;
+        };
+        if chord.is_escape() {
+            return;
+        }
+
+        match self.keybindings.add(action, chord.clone()) {
+            Ok(()) => {
+                self.keybindings_changed();
+                if self.native_menu.is_some() && action.in_menu() && chord.missing_from_menu() {
+                    self.shortcut_notice = Some(format!(
+                        "{chord} works in player windows, but the menu cannot show it."
+                    ));
+                }
+            }
+            Err(conflict) => {
+                self.shortcut_notice = Some(format!("{conflict}; remove it there first."));
+            }
+        }
+    }
+
+    pub(crate) fn remove_shortcut(&mut self, action: Action, chord: KeyChord) {
+        self.keybindings.remove(action, &chord);
+        self.keybindings_changed();
+    }
+
+    pub(crate) fn reset_shortcut(&mut self, action: Action) {
+        self.keybindings.reset(action);
+        self.keybindings_changed();
+    }
+
+    fn keybindings_changed(&mut self) {
+        self.shortcut_notice = None;
+        if let Some(menu) = &self.native_menu {
+            let keybindings = &self.keybindings;
+            if let Err(error) = menu.set_accelerators(|action| keybindings.menu_accelerator(action))
+            {
+                self.notify(
+                    Severity::Warning,
+                    format!("Failed to update menu shortcuts: {error}"),
+                );
+            }
+        }
+        self.keybindings_generation = self.keybindings_generation.wrapping_add(1);
+        self.keybindings_persist_requested = true;
+    }
+
+    pub(crate) fn take_keybindings_to_persist(&mut self) -> Option<(u64, KeybindingsFile)> {
+        if !self.keybindings_persist_requested || self.keybindings_persist_in_flight {
+            return None;
+        }
+
+        self.keybindings_persist_requested = false;
+        self.keybindings_persist_in_flight = true;
+        Some((self.keybindings_generation, self.keybindings.to_file()))
+    }
+
+    pub(crate) fn keybindings_persisted(&mut self, generation: u64, result: Result<(), String>) {
+        self.keybindings_persist_in_flight = false;
+
+        if let Err(error) = result {
+            self.notify(
+                Severity::Warning,
+                format!("Failed to save keybindings: {error}"),
+            );
+        }
+
+        if generation != self.keybindings_generation {
+            self.keybindings_persist_requested = true;
+        }
+    }
+
+    /// Looks up the file next to the one playing in a window, in name order.
+    pub(crate) fn open_sibling_file(
+        &mut self,
+        window_id: window::Id,
+        forward: bool,
+    ) -> Task<Message> {
+        let Some(path) = self
+            .windows
+            .get(&window_id)
+            .and_then(|window| window.playback.current_path())
+        else {
+            return Task::none();
+        };
+
+        find_sibling_file(path.to_path_buf(), forward)
+            .map(move |path| Message::SiblingFileFound { window_id, path })
+    }
+
+    /// Replaces a window's file with its sibling or a file picked from its
+    /// queue, carrying over whether it was playing.
+    pub(crate) fn sibling_file_found(
+        &mut self,
+        window_id: window::Id,
+        path: Option<PathBuf>,
+    ) -> Task<Message> {
+        let Some(path) = path else {
+            return Task::none();
+        };
+        let Some(window) = self.windows.get(&window_id) else {
+            return Task::none();
+        };
+
+        let was_playing = window.playing;
+        self.start_load(window_id, path, LoadPurpose::Step { was_playing });
+        Task::none()
+    }
+
+    pub(crate) fn is_library_window(&self, window_id: window::Id) -> bool {
+        self.library_window == Some(window_id)
+    }
+
+    pub(crate) fn show_library(&mut self) -> Task<Message> {
+        if let Some(window_id) = self.library_window {
+            return window::gain_focus(window_id);
+        }
+
+        let (window_id, task) = open_library_window();
+        self.library_window = Some(window_id);
+        task.map(Message::WindowOpened)
+    }
+
+    pub(crate) fn library_loaded(&mut self, result: Result<LibraryIndex, String>) -> Task<Message> {
+        match result {
+            Ok(index) => {
+                self.library.load(index);
+                self.rescan_library()
+            }
+            Err(error) => {
+                self.notify(Severity::Error, format!("Failed to load library: {error}"));
+                Task::none()
+            }
+        }
+    }
+
+    pub(crate) fn start_library_folder_dialog(&mut self) -> Task<Message> {
+        request_library_folder_dialog()
+    }
+
+    pub(crate) fn add_library_folder(&mut self, folder: Option<PathBuf>) -> Task<Message> {
+        log::debug!("library folder dialog closed folder={folder:?}");
+        if let Some(folder) = folder
+            && self.library.add_folder(folder)
+        {
+            self.rescan_library()
+        } else {
+            Task::none()
+        }
+    }
+
+    pub(crate) fn remove_library_folder(&mut self, folder: PathBuf) -> Task<Message> {
+        self.library.remove_folder(&folder);
+        self.rescan_library()
+    }
+
+    pub(crate) fn rescan_library(&mut self) -> Task<Message> {
+        if self.library.index.folders.is_empty() {
+            return Task::none();
+        }
+
+        let (generation, folders, previous) = self.library.begin_scan();
+        scan_library(generation, folders, previous)
+    }
+
+    pub(crate) fn library_scanned(&mut self, generation: u64, entries: Vec<LibraryEntry>) {
+        self.library.finish_scan(generation, entries);
+    }
+
+    pub(crate) fn take_library_to_persist(&mut self) -> Option<(u64, LibraryIndex)> {
+        self.library.take_index_to_persist()
+    }
+
+    pub(crate) fn library_persisted(&mut self, generation: u64, result: Result<(), String>) {
+        self.library.index_persisted(generation);
+
+        if let Err(error) = result {
+            self.notify(
+                Severity::Warning,
+                format!("Failed to save library: {error}"),
+            );
+        }
+    }
+
+    /// Adds to the notification history and shows it as a toast.
+    pub(crate) fn notify(&mut self, severity: Severity, message: String) {
+        let level = match severity {
+            Severity::Info => log::Level::Info,
+            Severity::Warning => log::Level::Warn,
+            Severity::Error => log::Level::Error,
+        };
+        log::log!(level, "notification {message}");
+        self.notifications.push(severity, message, Instant::now());
     }
 
     pub(crate) fn window_mut(&mut self, window_id: window::Id) -> Option<&mut PlayerWindowState> {
@@ -426,27 +2418,59 @@ impl ProteusApp {
         self.open_picked_path(path)
     }
 
+    /// Jumps to a bookmark chosen in the palette: a seek when the window is
+    /// playing its file, otherwise an open that stops at the bookmark.
+    pub(crate) fn go_to_bookmark(
+        &mut self,
+        window_id: window::Id,
+        path: PathBuf,
+        position: f64,
+    ) -> Task<Message> {
+        if let Some(window) = self.windows.get_mut(&window_id)
+            && window.playback.current_path() == Some(path.as_path())
+        {
+            window.resume_offer = None;
+            window.playback.seek(position);
+            return Task::none();
+        }
+
+        self.set_focused_window(window_id);
+        self.open_external_path(path, LoadPurpose::Bookmark { position })
+            .1
+    }
+
     pub(crate) fn handle_external_open_path(&mut self, path: PathBuf) -> Task<Message> {
+        self.open_external_path(path, LoadPurpose::Open).1
+    }
+
+    /// Opens a file handed over by another app in the focused window if it
+    /// is empty, or in a new window otherwise, returning the window used.
+    fn open_external_path(
+        &mut self,
+        path: PathBuf,
+        purpose: LoadPurpose,
+    ) -> (window::Id, Task<Message>) {
         self.startup_open_dialog_due_at = None;
         self.cancel_active_file_dialog();
 
         if let Some(window_id) = self.focused_window
-            && let Some(window) = self.windows.get_mut(&window_id)
-            && window.is_empty()
+            && self
+                .windows
+                .get(&window_id)
+                .is_some_and(PlayerWindowState::is_empty)
         {
-            if window.load_path(path.clone()) {
-                self.record_recent_file(path);
-            }
-            return Task::none();
+            self.start_load(window_id, path, purpose);
+            return (window_id, Task::none());
         }
 
-        self.open_window(Some(path))
+        self.insert_window(Some(path), None, purpose)
     }
 
     fn start_open_dialog(&mut self, target: FilePickTarget) -> Task<Message> {
         self.pending_file_pick_target = target;
         self.file_dialog_generation = self.file_dialog_generation.wrapping_add(1);
         self.active_file_dialog_generation = Some(self.file_dialog_generation);
+        log::debug!("open dialog shown target={target:?}");
         #[cfg(target_os = "macos")]
         {
             let (dialog, task) =
@@ -474,18 +2498,20 @@ impl ProteusApp {
         self.pending_file_pick_target = FilePickTarget::NewWindow;
 
         let Some(path) = path else {
+            log::debug!("open dialog cancelled");
             return Task::none();
         };
+        log::info!("open dialog picked path={path:?}");
 
         match target {
             FilePickTarget::NewWindow => self.open_window(Some(path)),
             FilePickTarget::OpenCommand { window_id } => {
-                if let Some(window) = self.windows.get_mut(&window_id)
-                    && window.is_empty()
+                if self
+                    .windows
+                    .get(&window_id)
+                    .is_some_and(PlayerWindowState::is_empty)
                 {
-                    if window.load_path(path.clone()) {
-                        self.record_recent_file(path);
-                    }
+                    self.start_load(window_id, path, LoadPurpose::Open);
                     Task::none()
                 } else {
                     self.open_window(Some(path))
@@ -494,7 +2520,174 @@ impl ProteusApp {
         }
     }
 
-    pub(crate) fn schedule_startup_open_dialog(&mut self, delay: Duration) -> Task<Message> {
+    /// Opens whatever the startup preference asks for when no file was passed in.
+    ///
+    /// `restore_session` forces a session restore regardless of the preference.
+    pub(crate) fn start_without_file(
+        &mut self,
+        dialog_delay: Duration,
+        restore_session: bool,
+    ) -> Task<Message> {
+        let startup = if restore_session {
+            StartupBehavior::RestoreSession
+        } else {
+            self.settings.startup
+        };
+
+        if startup != StartupBehavior::RestoreSession {
+            return self.start_fresh(startup, dialog_delay);
+        }
+
+        // Read synchronously: the saved session decides which windows open first.
+        match session_store::load() {
+            Ok(session) => {
+                let task = self.restore_session(session);
+                if self.windows.is_empty() {
+                    self.start_fresh(StartupBehavior::default(), dialog_delay)
+                } else {
+                    task
+                }
+            }
+            Err(error) => {
+                self.notify(
+                    Severity::Error,
+                    format!("Failed to load the last session: {error}"),
+                );
+                self.start_fresh(StartupBehavior::default(), dialog_delay)
+            }
+        }
+    }
+
+    fn start_fresh(&mut self, startup: StartupBehavior, dialog_delay: Duration) -> Task<Message> {
+        match startup {
+            StartupBehavior::OpenDialog if cfg!(target_os = "macos") => {
+                self.schedule_startup_open_dialog(dialog_delay)
+            }
+            StartupBehavior::OpenDialog => {
+                let task = self.open_window(None);
+                Task::batch([task, self.schedule_startup_open_dialog(dialog_delay)])
+            }
+            StartupBehavior::EmptyWindow | StartupBehavior::RestoreSession => {
+                self.open_window(None)
+            }
+        }
+    }
+
+    fn restore_session(&mut self, session: Session) -> Task<Message> {
+        let mut tasks = Vec::with_capacity(session.windows.len());
+        let mut missing = 0;
+
+        for saved in session.windows {
+            if !saved.path.is_file() {
+                missing += 1;
+                continue;
+            }
+
+            let size = saved.size.map(|size| Size::new(size.width, size.height));
+            let (_, task) =
+                self.insert_window(Some(saved.path.clone()), size, LoadPurpose::Restore(saved));
+            tasks.push(task);
+        }
+
+        if missing > 0 {
+            self.notify(
+                Severity::Warning,
+                format!("{missing} file(s) from the last session could not be found"),
+            );
+        }
+
+        Task::batch(tasks)
+    }
+
+    fn session_snapshot(&self) -> Session {
+        let windows = self
+            .windows
+            .values()
+            .filter_map(|window| {
+                let path = window.playback.current_path()?.to_path_buf();
+                Some(SessionWindow {
+                    path,
+                    position: window.current_time,
+                    volume_percent: window.volume_percent,
+                    zoom_factor: window.zoom_factor,
+                    combination: window.playback.combination(),
+                    playing: window.playing,
+                    size: window.size.map(|size| WindowSize {
+                        width: size.width,
+                        height: size.height,
+                    }),
+                })
+            })
+            .collect();
+
+        Session {
+            windows,
+            ..Session::default()
+        }
+    }
+
+    /// Records the open windows as the session to restore, along with each
+    /// file's resume position.
+    ///
+    /// Empty snapshots are ignored so the last window closed before quitting,
+    /// which on most platforms is what quits the app, stays in the session.
+    fn capture_session(&mut self) {
+        self.session_captured_at = Some(Instant::now());
+        self.record_resume_positions();
+
+        let snapshot = self.session_snapshot();
+        if !snapshot.windows.is_empty() && snapshot != self.session {
+            self.session = snapshot;
+            self.session_persist_requested = true;
+        }
+    }
+
+    pub(crate) fn take_session_to_persist(&mut self) -> Option<Session> {
+        if self
+            .session_captured_at
+            .is_none_or(|captured_at| captured_at.elapsed() >= SESSION_CAPTURE_INTERVAL)
+        {
+            self.capture_session();
+        }
+
+        if !self.session_persist_requested || self.session_persist_in_flight {
+            return None;
+        }
+
+        self.session_persist_requested = false;
+        self.session_persist_in_flight = true;
+        Some(self.session.clone())
+    }
+
+    pub(crate) fn session_persisted(&mut self, result: Result<(), String>) {
+        self.session_persist_in_flight = false;
+
+        if let Err(error) = result {
+            self.notify(
+                Severity::Warning,
+                format!("Failed to save session: {error}"),
+            );
+        }
+    }
+
+    /// Writes the session and resume positions synchronously, as the app is
+    /// about to exit and a background task would not get the chance to finish.
+    pub(crate) fn save_before_exit(&mut self) {
+        self.capture_session();
+        if !self.session.windows.is_empty() {
+            let _ = session_store::save(&self.session);
+        }
+        if self.resume_positions_persist_requested {
+            let _ = resume_store::save(&self.resume_positions);
+        }
+        if self.bookmarks_persist_requested {
+            let _ = bookmarks_store::save(&self.bookmarks);
+        }
+        // Dropping the link removes `link.json`, so clients stop finding it.
+        self.link = None;
+    }
+
+    fn schedule_startup_open_dialog(&mut self, delay: Duration) -> Task<Message> {
         self.startup_open_dialog_due_at = Some(Instant::now() + delay);
 
         Task::none()
@@ -515,27 +2708,43 @@ impl ProteusApp {
         }
     }
 
-    fn log_memory_event(&mut self, reason: &str) {
-        let windows = self.windows.len();
-        let loaded_players = self
-            .windows
-            .values()
-            .filter(|window| window.playback.is_loaded())
-            .count();
-        if let Some(sampler) = &mut self.memory_sampler {
-            sampler.log_event(reason, windows, loaded_players);
-        }
+    /// Open windows, players and their output buffers, for a diagnostics sample.
+    pub(crate) fn player_counts(&self) -> PlayerCounts {
+        PlayerCounts::from_players(self.windows.values().map(|window| &window.playback))
     }
 
-    fn log_memory_tick(&mut self) {
-        let windows = self.windows.len();
-        let loaded_players = self
-            .windows
-            .values()
-            .filter(|window| window.playback.is_loaded())
-            .count();
-        if let Some(sampler) = &mut self.memory_sampler {
-            sampler.maybe_log_periodic(windows, loaded_players);
-        }
+    fn sample_memory_event(&mut self, reason: &'static str) {
+        let counts = self.player_counts();
+        self.memory_sampler.sample_event(reason, counts);
+    }
+
+    /// Whether any window shows the diagnostics panel, which is when frame
+    /// timing is measured.
+    pub(crate) fn diagnostics_visible(&self) -> bool {
+        self.windows.values().any(|window| window.diagnostics_open)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newer_loads_replace_pending_ones_and_cancelled_ones_are_dropped() {
+        let mut window = PlayerWindowState::new(&Settings::default());
+
+        window.begin_load(1, PathBuf::from("/music/first.prot"), LoadPurpose::Open);
+        window.begin_load(2, PathBuf::from("/music/second.prot"), LoadPurpose::Open);
+        assert!(window.take_pending_load(1).is_none());
+        let pending = window
+            .take_pending_load(2)
+            .expect("the newer load should be pending");
+        assert_eq!(pending.path, Path::new("/music/second.prot"));
+        assert!(window.take_pending_load(2).is_none());
+
+        window.begin_load(3, PathBuf::from("/music/third.prot"), LoadPurpose::Open);
+        window.cancel_load();
+        assert!(window.loading.is_none());
+        assert!(window.take_pending_load(3).is_none());
     }
 }
@@ -0,0 +1,84 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+pub(crate) const APP_DIRECTORY: &str = "proteus-player";
+
+pub(crate) fn data_file_path(file_name: &str) -> Result<PathBuf, String> {
+    dirs::data_local_dir()
+        .map(|directory| directory.join(APP_DIRECTORY).join(file_name))
+        .ok_or_else(|| "could not determine the app data directory".to_owned())
+}
+
+/// Returns the path of a user-editable file in the app's config directory.
+pub(crate) fn config_file_path(file_name: &str) -> Result<PathBuf, String> {
+    dirs::config_dir()
+        .map(|directory| directory.join(APP_DIRECTORY).join(file_name))
+        .ok_or_else(|| "could not determine the app config directory".to_owned())
+}
+
+/// Reads a JSON document, treating a missing file as the type's default.
+pub(crate) fn read_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T, String> {
+    read_document(path, |contents| {
+        serde_json::from_slice(contents).map_err(|error| error.to_string())
+    })
+}
+
+pub(crate) fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), String> {
+    write_document(path, || {
+        serde_json::to_vec(value).map_err(|error| error.to_string())
+    })
+}
+
+/// Reads a TOML document, treating a missing file as the type's default.
+pub(crate) fn read_toml<T: DeserializeOwned + Default>(path: &Path) -> Result<T, String> {
+    read_document(path, |contents| {
+        let contents = std::str::from_utf8(contents).map_err(|error| error.to_string())?;
+        toml::from_str(contents).map_err(|error| error.to_string())
+    })
+}
+
+pub(crate) fn write_toml<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), String> {
+    write_document(path, || {
+        toml::to_string_pretty(value)
+            .map(String::into_bytes)
+            .map_err(|error| error.to_string())
+    })
+}
+
+fn read_document<T: Default>(
+    path: &Path,
+    parse: impl FnOnce(&[u8]) -> Result<T, String>,
+) -> Result<T, String> {
+    match fs::read(path) {
+        Ok(contents) => {
+            parse(&contents).map_err(|error| format!("could not parse {}: {error}", path.display()))
+        }
+        Err(error) if error.kind() == ErrorKind::NotFound => {
+            log::debug!("read path={path:?} missing=true");
+            Ok(T::default())
+        }
+        Err(error) => Err(format!("could not read {}: {error}", path.display())),
+    }
+}
+
+fn write_document(
+    path: &Path,
+    serialize: impl FnOnce() -> Result<Vec<u8>, String>,
+) -> Result<(), String> {
+    let directory = path
+        .parent()
+        .expect("app storage paths always have a parent directory");
+
+    fs::create_dir_all(directory)
+        .map_err(|error| format!("could not create {}: {error}", directory.display()))?;
+
+    let contents =
+        serialize().map_err(|error| format!("could not serialize {}: {error}", path.display()))?;
+    log::debug!("write path={path:?} bytes={}", contents.len());
+    fs::write(path, contents)
+        .map_err(|error| format!("could not write {}: {error}", path.display()))
+}
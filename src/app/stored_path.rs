@@ -0,0 +1,130 @@
+//! Serde for paths in the app's JSON files.
+//!
+//! `serde_json` refuses to write a path that is not valid Unicode, and one
+//! such name would stop a whole file saving. Valid paths are still written
+//! as plain strings, so existing files load unchanged. Others are written as
+//! `{"native": [...]}`, the path's raw OS encoding.
+
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(windows)]
+type NativeUnit = u16;
+#[cfg(not(windows))]
+type NativeUnit = u8;
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum StoredPath {
+    Text(String),
+    Native { native: Vec<NativeUnit> },
+}
+
+impl StoredPath {
+    fn new(path: &Path) -> Self {
+        match path.to_str() {
+            Some(text) => Self::Text(text.to_owned()),
+            None => Self::Native {
+                native: native_units(path.as_os_str()),
+            },
+        }
+    }
+
+    fn into_path(self) -> PathBuf {
+        match self {
+            Self::Text(text) => PathBuf::from(text),
+            Self::Native { native } => PathBuf::from(from_native_units(native)),
+        }
+    }
+}
+
+pub(crate) fn serialize<S: Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+    StoredPath::new(path).serialize(serializer)
+}
+
+pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+    StoredPath::deserialize(deserializer).map(StoredPath::into_path)
+}
+
+/// The same, for a list of paths.
+pub(crate) mod list {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        paths: &[PathBuf],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(paths.iter().map(|path| StoredPath::new(path)))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<PathBuf>, D::Error> {
+        Vec::<StoredPath>::deserialize(deserializer)
+            .map(|paths| paths.into_iter().map(StoredPath::into_path).collect())
+    }
+}
+
+#[cfg(unix)]
+fn native_units(path: &OsStr) -> Vec<NativeUnit> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_bytes().to_vec()
+}
+
+#[cfg(unix)]
+fn from_native_units(units: Vec<NativeUnit>) -> OsString {
+    use std::os::unix::ffi::OsStringExt;
+    OsString::from_vec(units)
+}
+
+#[cfg(windows)]
+fn native_units(path: &OsStr) -> Vec<NativeUnit> {
+    use std::os::windows::ffi::OsStrExt;
+    path.encode_wide().collect()
+}
+
+#[cfg(windows)]
+fn from_native_units(units: Vec<NativeUnit>) -> OsString {
+    use std::os::windows::ffi::OsStringExt;
+    OsString::from_wide(&units)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn native_units(path: &OsStr) -> Vec<NativeUnit> {
+    path.to_string_lossy().into_owned().into_bytes()
+}
+
+#[cfg(not(any(unix, windows)))]
+fn from_native_units(units: Vec<NativeUnit>) -> OsString {
+    String::from_utf8_lossy(&units).into_owned().into()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::ffi::OsStrExt;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Stored {
+        #[serde(with = "super::list")]
+        paths: Vec<PathBuf>,
+    }
+
+    #[test]
+    fn paths_that_are_not_unicode_round_trip() {
+        // "café" as Latin-1, as found in old archives.
+        let latin1 = PathBuf::from(OsStr::from_bytes(b"/music/caf\xe9.prot"));
+        let stored = Stored {
+            paths: vec![PathBuf::from("/music/first.prot"), latin1],
+        };
+
+        let json = serde_json::to_string(&stored).expect("paths should serialize");
+        assert!(json.starts_with(r#"{"paths":["/music/first.prot",{"native":[47,"#));
+
+        let restored: Stored = serde_json::from_str(&json).expect("paths should deserialize");
+        assert_eq!(restored, stored);
+    }
+}
@@ -1,5 +1,7 @@
-use iced::widget::{container, slider};
-use iced::{Color, Theme};
+use iced::widget::{container, progress_bar, slider, svg};
+use iced::{Color, Size, Theme};
+
+use crate::app::theme::Palette;
 
 pub(crate) const WINDOW_WIDTH: f32 = 350.0;
 #[cfg(target_os = "macos")]
@@ -7,85 +9,124 @@ pub(crate) const WINDOW_HEIGHT: f32 = 110.0;
 #[cfg(not(target_os = "macos"))]
 pub(crate) const WINDOW_HEIGHT: f32 = 110.0;
 
-pub(crate) const WINDOW_BG: Color = Color::from_rgb(31.0 / 255.0, 31.0 / 255.0, 31.0 / 255.0);
-pub(crate) const ACCENT_TEXT: Color = Color::from_rgb(158.0 / 255.0, 158.0 / 255.0, 158.0 / 255.0);
-pub(crate) const ERROR_TEXT: Color = Color::from_rgb(1.0, 120.0 / 255.0, 120.0 / 255.0);
+/// The borderless mini player: title, play/pause and a progress bar.
+pub(crate) const MINI_PLAYER_SIZE: Size = Size::new(260.0, 44.0);
 
-const RAIL_BG: Color = Color::from_rgb(121.0 / 255.0, 121.0 / 255.0, 121.0 / 255.0);
-const RAIL_FILL: Color = Color::from_rgb(93.0 / 255.0, 93.0 / 255.0, 93.0 / 255.0);
+/// Height of the in-window menu bar on platforms without a global one.
+pub(crate) const MENU_BAR_HEIGHT: f32 = 24.0;
 
-pub(crate) fn timeline_slider_style(_theme: &Theme, status: slider::Status) -> slider::Style {
-    let rail = slider::Rail {
-        backgrounds: (RAIL_FILL.into(), RAIL_BG.into()),
-        width: 4.0,
-        border: iced::border::rounded(0),
-    };
-
-    let hidden_handle = slider::Handle {
-        shape: slider::HandleShape::Circle { radius: 0.0 },
-        background: Color::TRANSPARENT.into(),
-        border_width: 0.0,
-        border_color: Color::TRANSPARENT,
-    };
-
-    let visible_handle = slider::Handle {
-        shape: slider::HandleShape::Circle { radius: 5.0 },
-        background: Color::from_rgb(196.0 / 255.0, 196.0 / 255.0, 196.0 / 255.0).into(),
-        border_width: 0.0,
-        border_color: Color::TRANSPARENT,
-    };
-
-    slider::Style {
-        rail,
-        handle: if matches!(status, slider::Status::Hovered | slider::Status::Dragged) {
-            visible_handle
-        } else {
-            hidden_handle
-        },
+pub(crate) const LIBRARY_WINDOW_WIDTH: f32 = 640.0;
+pub(crate) const LIBRARY_WINDOW_HEIGHT: f32 = 460.0;
+
+pub(crate) const PREFERENCES_WINDOW_WIDTH: f32 = 440.0;
+pub(crate) const PREFERENCES_WINDOW_HEIGHT: f32 = 520.0;
+
+pub(crate) fn timeline_slider_style(
+    palette: Palette,
+) -> impl Fn(&Theme, slider::Status) -> slider::Style {
+    move |_theme, status| {
+        let rail = slider::Rail {
+            backgrounds: (palette.rail_fill.into(), palette.rail.into()),
+            width: 4.0,
+            border: iced::border::rounded(0),
+        };
+
+        let hidden_handle = slider::Handle {
+            shape: slider::HandleShape::Circle { radius: 0.0 },
+            background: Color::TRANSPARENT.into(),
+            border_width: 0.0,
+            border_color: Color::TRANSPARENT,
+        };
+
+        let visible_handle = slider::Handle {
+            shape: slider::HandleShape::Circle { radius: 5.0 },
+            background: palette.handle.into(),
+            border_width: 0.0,
+            border_color: Color::TRANSPARENT,
+        };
+
+        slider::Style {
+            rail,
+            handle: if matches!(status, slider::Status::Hovered | slider::Status::Dragged) {
+                visible_handle
+            } else {
+                hidden_handle
+            },
+        }
     }
 }
 
-pub(crate) fn volume_slider_style(_theme: &Theme, _status: slider::Status) -> slider::Style {
-    slider::Style {
+pub(crate) fn volume_slider_style(
+    palette: Palette,
+) -> impl Fn(&Theme, slider::Status) -> slider::Style {
+    move |_theme, _status| slider::Style {
         rail: slider::Rail {
-            backgrounds: (RAIL_FILL.into(), RAIL_BG.into()),
+            backgrounds: (palette.rail_fill.into(), palette.rail.into()),
             width: 4.0,
             border: iced::border::rounded(0),
         },
         handle: slider::Handle {
             shape: slider::HandleShape::Circle { radius: 5.0 },
-            background: Color::from_rgb(196.0 / 255.0, 196.0 / 255.0, 196.0 / 255.0).into(),
+            background: palette.handle.into(),
             border_width: 0.0,
             border_color: Color::TRANSPARENT,
         },
     }
 }
 
-pub(crate) fn background_style(_theme: &Theme) -> container::Style {
-    container::Style::default()
-        .background(WINDOW_BG)
-        .color(ACCENT_TEXT)
+pub(crate) fn progress_style(palette: Palette) -> impl Fn(&Theme) -> progress_bar::Style {
+    move |_theme| progress_bar::Style {
+        background: palette.rail.into(),
+        bar: palette.rail_fill.into(),
+        border: iced::border::rounded(0),
+    }
+}
+
+pub(crate) fn background_style(palette: Palette) -> impl Fn(&Theme) -> container::Style {
+    move |_theme| {
+        container::Style::default()
+            .background(palette.background)
+            .color(palette.accent_text)
+    }
+}
+
+pub(crate) fn menu_surface_style(palette: Palette) -> impl Fn(&Theme) -> container::Style {
+    move |_theme| {
+        container::Style::default()
+            .background(palette.surface)
+            .border(iced::border::rounded(6))
+            .color(palette.accent_text)
+    }
 }
 
-pub(crate) fn _menu_surface_style(_theme: &Theme) -> container::Style {
-    container::Style::default()
-        .background(Color::from_rgba(
-            42.0 / 255.0,
-            42.0 / 255.0,
-            42.0 / 255.0,
-            0.96,
-        ))
-        .border(iced::border::rounded(6))
-        .color(ACCENT_TEXT)
+pub(crate) fn library_row_style(
+    palette: Palette,
+    selected: bool,
+) -> impl Fn(&Theme) -> container::Style {
+    move |_theme| {
+        let style = container::Style::default().color(palette.accent_text);
+        if selected {
+            style.background(palette.selection)
+        } else {
+            style
+        }
+    }
+}
+
+pub(crate) fn level_bar_style(palette: Palette) -> impl Fn(&Theme) -> container::Style {
+    move |_theme| container::Style::default().background(palette.rail_fill)
 }
 
-pub(crate) fn menu_header_style(_theme: &Theme) -> container::Style {
-    container::Style::default()
-        .background(Color::from_rgba(
-            42.0 / 255.0,
-            42.0 / 255.0,
-            42.0 / 255.0,
-            0.9,
-        ))
-        .color(ACCENT_TEXT)
+pub(crate) fn menu_header_style(palette: Palette) -> impl Fn(&Theme) -> container::Style {
+    move |_theme| {
+        container::Style::default()
+            .background(palette.header)
+            .color(palette.accent_text)
+    }
+}
+
+pub(crate) fn icon_style(palette: Palette) -> impl Fn(&Theme, svg::Status) -> svg::Style {
+    move |_theme, _status| svg::Style {
+        color: Some(palette.icon),
+    }
 }
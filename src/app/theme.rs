@@ -0,0 +1,331 @@
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use iced::theme::Mode;
+use iced::{Color, Theme};
+use serde::{Deserialize, Serialize};
+
+use crate::app::storage;
+
+const THEMES_NAME: &str = "themes.toml";
+
+/// Colors used by the player's own widgets.
+///
+/// Built-in widgets such as buttons and text inputs are styled through the
+/// Iced theme generated from the same palette by [`Palette::iced_theme`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Palette {
+    pub(crate) background: Color,
+    pub(crate) text: Color,
+    pub(crate) accent_text: Color,
+    pub(crate) error_text: Color,
+    pub(crate) rail: Color,
+    pub(crate) rail_fill: Color,
+    pub(crate) handle: Color,
+    pub(crate) selection: Color,
+    pub(crate) surface: Color,
+    pub(crate) header: Color,
+    pub(crate) icon: Color,
+    pub(crate) primary: Color,
+}
+
+impl Palette {
+    pub(crate) const DARK: Self = Self {
+        background: Color::from_rgb8(31, 31, 31),
+        text: Color::from_rgb8(230, 230, 230),
+        accent_text: Color::from_rgb8(158, 158, 158),
+        error_text: Color::from_rgb8(255, 120, 120),
+        rail: Color::from_rgb8(121, 121, 121),
+        rail_fill: Color::from_rgb8(93, 93, 93),
+        handle: Color::from_rgb8(196, 196, 196),
+        selection: Color::from_rgb8(93, 93, 93),
+        surface: Color::from_rgba8(42, 42, 42, 0.96),
+        header: Color::from_rgba8(42, 42, 42, 0.9),
+        icon: Color::WHITE,
+        primary: Color::from_rgb8(93, 126, 214),
+    };
+
+    pub(crate) const LIGHT: Self = Self {
+        background: Color::from_rgb8(245, 245, 245),
+        text: Color::from_rgb8(31, 31, 31),
+        accent_text: Color::from_rgb8(60, 60, 60),
+        error_text: Color::from_rgb8(179, 38, 30),
+        rail: Color::from_rgb8(196, 196, 196),
+        rail_fill: Color::from_rgb8(110, 110, 110),
+        handle: Color::from_rgb8(60, 60, 60),
+        selection: Color::from_rgb8(210, 210, 210),
+        surface: Color::from_rgba8(232, 232, 232, 0.96),
+        header: Color::from_rgba8(228, 228, 228, 0.9),
+        icon: Color::from_rgb8(43, 43, 43),
+        primary: Color::from_rgb8(59, 111, 216),
+    };
+
+    pub(crate) const HIGH_CONTRAST: Self = Self {
+        background: Color::BLACK,
+        text: Color::WHITE,
+        accent_text: Color::WHITE,
+        error_text: Color::from_rgb8(255, 140, 140),
+        rail: Color::WHITE,
+        rail_fill: Color::from_rgb8(255, 214, 0),
+        handle: Color::from_rgb8(255, 214, 0),
+        selection: Color::from_rgb8(40, 40, 140),
+        surface: Color::BLACK,
+        header: Color::from_rgb8(40, 40, 40),
+        icon: Color::WHITE,
+        primary: Color::from_rgb8(255, 214, 0),
+    };
+
+    pub(crate) fn iced_theme(&self, name: String) -> Theme {
+        Theme::custom(
+            name,
+            iced::theme::Palette {
+                background: self.background,
+                text: self.text,
+                primary: self.primary,
+                success: iced::theme::Palette::DARK.success,
+                warning: iced::theme::Palette::DARK.warning,
+                danger: self.error_text,
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ThemeChoice {
+    /// Dark or light, following the operating system preference.
+    System,
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+    /// A palette from the user's `themes.toml`, by name.
+    Custom(String),
+}
+
+impl fmt::Display for ThemeChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::System => f.write_str("Follow System"),
+            Self::Dark => f.write_str("Dark"),
+            Self::Light => f.write_str("Light"),
+            Self::HighContrast => f.write_str("High Contrast"),
+            Self::Custom(name) => f.write_str(name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct UserTheme {
+    pub(crate) name: String,
+    pub(crate) palette: Palette,
+}
+
+/// Built-in and user palettes, plus what is needed to resolve the active one.
+pub(crate) struct Themes {
+    user: Vec<UserTheme>,
+    system_mode: Mode,
+    file_modified: Option<SystemTime>,
+}
+
+impl Themes {
+    pub(crate) fn new() -> Self {
+        Self {
+            user: Vec::new(),
+            system_mode: Mode::None,
+            file_modified: None,
+        }
+    }
+
+    pub(crate) fn choices(&self) -> Vec<ThemeChoice> {
+        [
+            ThemeChoice::System,
+            ThemeChoice::Dark,
+            ThemeChoice::Light,
+            ThemeChoice::HighContrast,
+        ]
+        .into_iter()
+        .chain(
+            self.user
+                .iter()
+                .map(|theme| ThemeChoice::Custom(theme.name.clone())),
+        )
+        .collect()
+    }
+
+    /// Resolves a choice to its palette, falling back to the dark palette for
+    /// user themes that are no longer defined.
+    pub(crate) fn palette(&self, choice: &ThemeChoice) -> Palette {
+        match choice {
+            ThemeChoice::System if self.system_mode == Mode::Light => Palette::LIGHT,
+            ThemeChoice::System | ThemeChoice::Dark => Palette::DARK,
+            ThemeChoice::Light => Palette::LIGHT,
+            ThemeChoice::HighContrast => Palette::HIGH_CONTRAST,
+            ThemeChoice::Custom(name) => self
+                .user
+                .iter()
+                .find(|theme| &theme.name == name)
+                .map(|theme| theme.palette)
+                .unwrap_or(Palette::DARK),
+        }
+    }
+
+    pub(crate) fn set_system_mode(&mut self, mode: Mode) -> bool {
+        let changed = self.system_mode != mode;
+        self.system_mode = mode;
+        changed
+    }
+
+    pub(crate) fn set_user_themes(&mut self, themes: Vec<UserTheme>) {
+        self.user = themes;
+    }
+
+    /// Records the themes file's modification time, returning `true` when it
+    /// differs from the last check and the file should be reloaded.
+    pub(crate) fn file_changed(&mut self, modified: Option<SystemTime>) -> bool {
+        let changed = self.file_modified != modified;
+        self.file_modified = modified;
+        changed
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ThemesFile {
+    theme: Vec<ThemeDefinition>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum ThemeBase {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+}
+
+/// A user palette as written in `themes.toml`. Unset colors come from `base`.
+#[derive(Debug, Deserialize)]
+struct ThemeDefinition {
+    name: String,
+    #[serde(default)]
+    base: ThemeBase,
+    background: Option<String>,
+    text: Option<String>,
+    accent_text: Option<String>,
+    error_text: Option<String>,
+    rail: Option<String>,
+    rail_fill: Option<String>,
+    handle: Option<String>,
+    selection: Option<String>,
+    surface: Option<String>,
+    header: Option<String>,
+    icon: Option<String>,
+    primary: Option<String>,
+}
+
+impl ThemeDefinition {
+    fn into_user_theme(self) -> Result<UserTheme, String> {
+        let mut palette = match self.base {
+            ThemeBase::Dark => Palette::DARK,
+            ThemeBase::Light => Palette::LIGHT,
+            ThemeBase::HighContrast => Palette::HIGH_CONTRAST,
+        };
+
+        let overrides = [
+            ("background", self.background, &mut palette.background),
+            ("text", self.text, &mut palette.text),
+            ("accent_text", self.accent_text, &mut palette.accent_text),
+            ("error_text", self.error_text, &mut palette.error_text),
+            ("rail", self.rail, &mut palette.rail),
+            ("rail_fill", self.rail_fill, &mut palette.rail_fill),
+            ("handle", self.handle, &mut palette.handle),
+            ("selection", self.selection, &mut palette.selection),
+            ("surface", self.surface, &mut palette.surface),
+            ("header", self.header, &mut palette.header),
+            ("icon", self.icon, &mut palette.icon),
+            ("primary", self.primary, &mut palette.primary),
+        ];
+
+        for (field, value, color) in overrides {
+            if let Some(value) = value {
+                *color = value.parse().map_err(|error| {
+                    format!(
+                        "theme \"{}\" has an invalid {field} color: {error}",
+                        self.name
+                    )
+                })?;
+            }
+        }
+
+        Ok(UserTheme {
+            name: self.name,
+            palette,
+        })
+    }
+}
+
+pub(crate) fn themes_file_path() -> Result<PathBuf, String> {
+    storage::config_file_path(THEMES_NAME)
+}
+
+/// Reads user palettes from a TOML file, treating a missing file as empty.
+pub(crate) fn load_user_themes(path: &Path) -> Result<Vec<UserTheme>, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(format!("could not read {}: {error}", path.display())),
+    };
+
+    parse_user_themes(&contents)
+        .map_err(|error| format!("could not parse {}: {error}", path.display()))
+}
+
+fn parse_user_themes(contents: &str) -> Result<Vec<UserTheme>, String> {
+    let file: ThemesFile = toml::from_str(contents).map_err(|error| error.to_string())?;
+    file.theme
+        .into_iter()
+        .map(ThemeDefinition::into_user_theme)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_themes_override_their_base_palette() {
+        let themes = parse_user_themes(
+            r##"
+            [[theme]]
+            name = "Paper"
+            base = "light"
+            accent_text = "#000000"
+            "##,
+        )
+        .expect("the theme file should parse");
+
+        assert_eq!(themes.len(), 1);
+        assert_eq!(themes[0].name, "Paper");
+        assert_eq!(themes[0].palette.accent_text, Color::BLACK);
+        assert_eq!(themes[0].palette.background, Palette::LIGHT.background);
+    }
+
+    #[test]
+    fn invalid_colors_name_the_theme_and_field() {
+        let error = parse_user_themes(
+            r#"
+            [[theme]]
+            name = "Broken"
+            rail = "not-a-color"
+            "#,
+        )
+        .expect_err("an invalid color should be rejected");
+
+        assert!(error.contains("Broken"));
+        assert!(error.contains("rail"));
+    }
+}
@@ -1,25 +1,56 @@
-use iced::widget::{button, column, container, row, slider, svg, text};
-use iced::{Alignment, Element, Length, Padding, window};
+use iced::widget::{
+    Column, button, column, container, mouse_area, opaque, progress_bar, responsive, row, slider,
+    stack, svg, text,
+};
+use iced::{Alignment, Element, Length, window};
 
+use crate::app::command_palette_view::command_palette_overlay;
+use crate::app::context_menu::ContextMenuKind;
+use crate::app::context_menu_view::context_menu_overlay;
+use crate::app::diagnostics_view::diagnostics_overlay;
+use crate::app::error_view::error_overlay;
+use crate::app::expanded_view::expanded_view;
 use crate::app::helpers::format_time;
+use crate::app::keybindings::{Action, Keybindings};
+use crate::app::layout::LayoutMode;
+use crate::app::library_view::library_view;
+use crate::app::menu_bar_view::{menu_bar, menu_dropdown};
 use crate::app::messages::Message;
-use crate::app::state::{PlayerWindowState, ProteusApp};
+use crate::app::mini_player_view::mini_player_view;
+use crate::app::notifications_view::{notifications_overlay, toasts};
+use crate::app::preferences_view::preferences_view;
+use crate::app::properties_view::properties_overlay;
+use crate::app::shortcuts_view::shortcuts_overlay;
+use crate::app::state::{PendingLoad, PlayerWindowState, ProteusApp};
 use crate::app::styles::{
-    _menu_surface_style, ACCENT_TEXT, ERROR_TEXT, background_style, menu_header_style,
-    timeline_slider_style, volume_slider_style,
+    background_style, icon_style, menu_header_style, progress_style, timeline_slider_style,
+    volume_slider_style,
 };
+use crate::app::theme::Palette;
+use crate::app::visualizer_view::visualizer_view;
 use crate::app::widgets::slider_with_handle_cursor;
-use crate::native_menu::MenuAction;
+use crate::playback::LoadStage;
+
+/// Widest the transport grows as the window is resized.
+const TRANSPORT_MAX_WIDTH: f32 = 560.0;
 
 pub(crate) fn view(state: &ProteusApp, window_id: window::Id) -> Element<'_, Message> {
+    if state.is_library_window(window_id) {
+        return library_view(state);
+    }
+
+    if state.is_preferences_window(window_id) {
+        return preferences_view(state);
+    }
+
     if let Some(window) = state.windows.get(&window_id) {
         return window_view(state, window, window_id);
     }
 
-    container(text("Loading...").color(ACCENT_TEXT))
+    container(text("Loading...").color(state.palette.accent_text))
         .width(Length::Fill)
         .height(Length::Fill)
-        .style(background_style)
+        .style(background_style(state.palette))
         .center_x(Length::Fill)
         .center_y(Length::Fill)
         .into()
@@ -30,21 +61,184 @@ fn window_view<'a>(
     window: &'a PlayerWindowState,
     window_id: window::Id,
 ) -> Element<'a, Message> {
-    const ROW_WIDTH: f32 = 304.0;
-    const TIMELINE_SLIDER_WIDTH: f32 = 232.0;
+    if window.mini {
+        return mini_player_view(state, window, window_id);
+    }
+
+    if window.visualizer {
+        return visualizer_view(state, window, window_id);
+    }
+
+    let palette = state.palette;
+
+    if let Some(pending) = &window.loading
+        && window.is_empty()
+    {
+        return loading_view(state, window, pending, window_id);
+    }
+
+    let main_content = responsive(move |size| match LayoutMode::for_size(size) {
+        LayoutMode::Compact => container(transport(state, window, window_id))
+            .padding(12)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center_x(Length::Fill)
+            .into(),
+        LayoutMode::Expanded => expanded_view(
+            state,
+            window,
+            window_id,
+            transport(state, window, window_id),
+        ),
+    });
+
+    // Platforms without a global menu bar get one inside each player window.
+    let in_window_menu = !cfg!(target_os = "macos");
+    let mut content = column![];
+    if in_window_menu {
+        content = content.push(menu_bar(state, window, window_id));
+    }
+    let mut content = content.push(main_content);
+    // Stepping to another file keeps the current one playing until the new
+    // one is ready, so only a strip shows the load.
+    if let Some(pending) = &window.loading {
+        content = content.push(
+            container(loading_progress(state.palette, window, pending, window_id))
+                .padding([4, 12])
+                .center_x(Length::Fill),
+        );
+    }
+    let mut content = content.push(platform_footer(state));
+
+    if let Some(toasts) = toasts(state) {
+        content = content.push(toasts);
+    }
+
+    let content = mouse_area(
+        container(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(background_style(palette)),
+    )
+    .on_move(move |position| Message::CursorMoved {
+        window_id,
+        position,
+    })
+    .on_right_press(Message::ContextMenuOpened {
+        window_id,
+        kind: ContextMenuKind::Surface,
+    });
+
+    if let Some(palette) = state
+        .command_palette
+        .as_ref()
+        .filter(|palette| palette.window_id == window_id)
+    {
+        stack![content, opaque(command_palette_overlay(state, palette))].into()
+    } else if let Some(report) = &window.error_report {
+        stack![content, opaque(error_overlay(state, report, window_id))].into()
+    } else if window.shortcuts_open {
+        stack![content, opaque(shortcuts_overlay(state, window_id))].into()
+    } else if window.notifications_open {
+        stack![content, opaque(notifications_overlay(state, window_id))].into()
+    } else if window.diagnostics_open {
+        stack![content, opaque(diagnostics_overlay(state, window_id))].into()
+    } else if window.properties_open {
+        stack![
+            content,
+            opaque(properties_overlay(state, window, window_id))
+        ]
+        .into()
+    } else if let Some(menu) = window.context_menu {
+        stack![
+            content,
+            context_menu_overlay(state, window, window_id, menu)
+        ]
+        .into()
+    } else if let Some(dropdown) =
+        menu_dropdown(state, window, window_id).filter(|_| in_window_menu)
+    {
+        stack![content, dropdown].into()
+    } else {
+        content.into()
+    }
+}
+
+/// An empty window opening its first file.
+fn loading_view<'a>(
+    state: &'a ProteusApp,
+    window: &'a PlayerWindowState,
+    pending: &'a PendingLoad,
+    window_id: window::Id,
+) -> Element<'a, Message> {
+    container(loading_progress(state.palette, window, pending, window_id))
+        .padding(24)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center(Length::Fill)
+        .style(background_style(state.palette))
+        .into()
+}
+
+/// The file being opened, the stage its player thread has reached and how
+/// long it has taken so far.
+fn loading_progress<'a>(
+    palette: Palette,
+    window: &'a PlayerWindowState,
+    pending: &'a PendingLoad,
+    window_id: window::Id,
+) -> Element<'a, Message> {
+    let name = pending
+        .path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let stage = window.load_stage();
+    let done = stage.map_or(0, LoadStage::index) as f32 / LoadStage::COUNT as f32;
+    let elapsed = pending.started_at.elapsed().as_secs_f32();
+    let status = match stage {
+        Some(stage) => format!("Loading {name}… {} ({elapsed:.1} s)", stage.label()),
+        None => format!("Loading {name}… ({elapsed:.1} s)"),
+    };
+
+    column![
+        text(status).size(12),
+        row![
+            progress_bar(0.0..=1.0, done)
+                .girth(4)
+                .style(progress_style(palette)),
+            button(text("Cancel").size(11))
+                .padding([2, 8])
+                .on_press(Message::CancelLoadPressed(window_id)),
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center),
+    ]
+    .spacing(6)
+    .max_width(TRANSPORT_MAX_WIDTH)
+    .into()
+}
+
+/// Timeline, playback controls and volume, shared by both layouts.
+pub(crate) fn transport<'a>(
+    state: &'a ProteusApp,
+    window: &'a PlayerWindowState,
+    window_id: window::Id,
+) -> Column<'a, Message> {
+    let palette = state.palette;
 
     let timeline = row![
         text(format_time(window.current_time))
             .size(12)
             .width(Length::Fixed(30.0))
-            .color(ACCENT_TEXT),
+            .color(palette.accent_text),
         slider_with_handle_cursor(
             slider(0.0..=100.0, window.current_time_percent, move |percent| {
                 Message::TimelineChanged { window_id, percent }
             })
             .step(0.1)
-            .width(Length::Fixed(TIMELINE_SLIDER_WIDTH))
-            .style(timeline_slider_style),
+            .width(Length::Fill)
+            .style(timeline_slider_style(palette)),
             window.current_time_percent,
             0.0..=100.0,
             5.0,
@@ -52,11 +246,11 @@ fn window_view<'a>(
         text(format_time(window.duration.unwrap_or(0.0)))
             .size(12)
             .width(Length::Fixed(30.0))
-            .color(ACCENT_TEXT),
+            .color(palette.accent_text),
     ]
     .align_y(Alignment::Center)
     .spacing(6)
-    .width(Length::Fixed(ROW_WIDTH));
+    .width(Length::Fill);
 
     let play_icon = if window.playing {
         state.icons.pause.clone()
@@ -66,45 +260,61 @@ fn window_view<'a>(
 
     let controls = row![
         container(
-            button(svg(state.icons.reset.clone()).width(15).height(15))
-                .style(button::text)
-                .padding(0)
-                .on_press(Message::ResetPressed(window_id)),
+            button(
+                svg(state.icons.reset.clone())
+                    .width(15)
+                    .height(15)
+                    .style(icon_style(palette))
+            )
+            .style(button::text)
+            .padding(0)
+            .on_press(Message::ResetPressed(window_id)),
         )
         .width(Length::Fill)
         .align_x(Alignment::End),
         container(
-            button(svg(play_icon).width(30).height(30))
-                .style(button::text)
-                .padding(0)
-                .on_press(Message::PlayPausePressed(window_id)),
+            button(
+                svg(play_icon)
+                    .width(30)
+                    .height(30)
+                    .style(icon_style(palette))
+            )
+            .style(button::text)
+            .padding(0)
+            .on_press(Message::PlayPausePressed(window_id)),
         )
         .center_x(Length::Fill)
         .width(Length::Fixed(130.0)),
         container(
-            button(svg(state.icons.shuffle.clone()).width(15).height(15))
-                .style(button::text)
-                .padding(0)
-                .on_press(Message::ShufflePressed(window_id)),
+            button(
+                svg(state.icons.shuffle.clone())
+                    .width(15)
+                    .height(15)
+                    .style(icon_style(palette))
+            )
+            .style(button::text)
+            .padding(0)
+            .on_press(Message::ShufflePressed(window_id)),
         )
         .width(Length::Fill)
         .align_x(Alignment::Start),
     ]
     .align_y(Alignment::Center)
-    .width(Length::Fixed(ROW_WIDTH))
+    .width(Length::Fill)
     .spacing(0);
 
     let volume = row![
         svg(state.icons.volume_icon(window.volume_percent))
             .width(16)
-            .height(16),
+            .height(16)
+            .style(icon_style(palette)),
         slider_with_handle_cursor(
             slider(0.0..=100.0, window.volume_percent, move |percent| {
                 Message::VolumeChanged { window_id, percent }
             })
             .step(1.0)
-            .width(Length::Fixed(ROW_WIDTH - 22.0))
-            .style(volume_slider_style),
+            .width(Length::Fill)
+            .style(volume_slider_style(palette)),
             f64::from(window.volume_percent),
             0.0..=100.0,
             5.0,
@@ -112,39 +322,69 @@ fn window_view<'a>(
     ]
     .align_y(Alignment::Center)
     .spacing(6)
-    .width(Length::Fixed(ROW_WIDTH));
+    .width(Length::Fill);
 
-    let main_content = column![
-        container(timeline)
-            .width(Length::Fill)
-            .center_x(Length::Fill),
-        container(controls)
-            .width(Length::Fill)
-            .center_x(Length::Fill),
-        container(volume).width(Length::Fill).center_x(Length::Fill)
+    // The resume prompt takes the volume row's place until it is answered.
+    let bottom_row = match &window.resume_offer {
+        Some(offer) => {
+            // Takes cannot be chosen when a file loads, so the saved
+            // combination may not be the one playing now.
+            let same_takes =
+                offer.combination.is_empty() || offer.combination == window.playback.combination();
+            resume_row(palette, offer.position, same_takes, window_id)
+        }
+        None => volume.into(),
+    };
+
+    column![
+        mouse_area(timeline).on_right_press(Message::ContextMenuOpened {
+            window_id,
+            kind: ContextMenuKind::Timeline,
+        }),
+        controls,
+        bottom_row,
     ]
     .align_x(Alignment::Center)
     .spacing(6)
-    .padding(12)
     .width(Length::Fill)
-    .height(Length::Fill);
-
-    let mut content = column![main_content, platform_footer(),];
+    .max_width(TRANSPORT_MAX_WIDTH)
+}
 
-    if let Some(error) = &window.last_error {
-        content = content.push(text(error.clone()).size(11).color(ERROR_TEXT));
-    } else if let Some(error) = &state.global_error {
-        content = content.push(text(error.clone()).size(11).color(ERROR_TEXT));
-    }
+fn resume_row<'a>(
+    palette: Palette,
+    position: f64,
+    same_takes: bool,
+    window_id: window::Id,
+) -> Element<'a, Message> {
+    let prompt = if same_takes {
+        format!("Resume at {}?", format_time(position))
+    } else {
+        format!(
+            "Resume at {}? Different takes are playing this time.",
+            format_time(position)
+        )
+    };
 
-    container(content)
-        .width(Length::Fill)
-        .height(Length::Fill)
-        .style(background_style)
-        .into()
+    row![
+        text(prompt)
+            .size(12)
+            .color(palette.accent_text)
+            .width(Length::Fill),
+        button(text("Resume").size(12))
+            .padding([2, 8])
+            .on_press(Message::ResumePressed(window_id)),
+        button(text("Start from Beginning").size(12))
+            .style(button::text)
+            .padding([2, 4])
+            .on_press(Message::StartFromBeginningPressed(window_id)),
+    ]
+    .align_y(Alignment::Center)
+    .spacing(6)
+    .width(Length::Fill)
+    .into()
 }
 
-fn platform_footer<'a>() -> Element<'a, Message> {
+fn platform_footer(state: &ProteusApp) -> Element<'_, Message> {
     if cfg!(target_os = "macos") {
         return container(column![])
             .width(Length::Shrink)
@@ -155,16 +395,16 @@ fn platform_footer<'a>() -> Element<'a, Message> {
     let content = column![
         container(
             row![
-                text("Ctrl+O to open, Ctrl+N for new window")
+                text(footer_hint(&state.keybindings))
                     .size(11)
-                    .color(ACCENT_TEXT)
+                    .color(state.palette.accent_text)
             ]
             .spacing(8)
             .align_y(Alignment::Center)
         )
         .width(Length::Fill)
         .padding([6, 23])
-        .style(menu_header_style)
+        .style(menu_header_style(state.palette))
     ]
     .spacing(4)
     .width(Length::Fill);
@@ -172,93 +412,20 @@ fn platform_footer<'a>() -> Element<'a, Message> {
     container(content).width(Length::Fill).into()
 }
 
-fn _platform_menu<'a>(
-    _state: &'a ProteusApp,
-    window: &'a PlayerWindowState,
-    window_id: window::Id,
-) -> Element<'a, Message> {
-    if cfg!(target_os = "macos") {
-        return container(column![])
-            .width(Length::Shrink)
-            .height(Length::Shrink)
-            .into();
-    }
-
-    let menu_button_label = if window.menu_open {
-        "Menu ▴"
-    } else {
-        "Menu ▾"
-    };
-
-    let mut content = column![
-        container(
-            row![
-                button(text(menu_button_label).size(12))
-                    .style(button::text)
-                    .padding([2, 4])
-                    .on_press(Message::_ToggleWindowMenu(window_id)),
-                text("Ctrl+O to open, Ctrl+N for new window")
-                    .size(11)
-                    .color(ACCENT_TEXT)
-            ]
-            .spacing(8)
-            .align_y(Alignment::Center)
-        )
-        .width(Length::Fill)
-        .padding([0, 2])
-        .style(menu_header_style)
+/// Names the bindings a new user needs first, read from the keybinding table.
+fn footer_hint(keybindings: &Keybindings) -> String {
+    [
+        (Action::Open, "to open"),
+        (Action::NewWindow, "for new window"),
+        (Action::ShowShortcuts, "for all shortcuts"),
     ]
-    .padding(Padding {
-        top: 0.0,
-        right: 2.0,
-        bottom: 16.0,
-        left: 0.0,
+    .into_iter()
+    .filter_map(|(action, purpose)| {
+        keybindings
+            .chords(action)
+            .first()
+            .map(|chord| format!("{chord} {purpose}"))
     })
-    .spacing(4)
-    .width(Length::Fill);
-
-    if window.menu_open {
-        let menu_panel = column![
-            text("File").size(11).color(ACCENT_TEXT),
-            _menu_item("Open…", "Ctrl+O", window_id, MenuAction::Open),
-            _menu_item("New Window", "Ctrl+N", window_id, MenuAction::NewWindow),
-            text("View").size(11).color(ACCENT_TEXT),
-            _menu_item("Zoom In", "Ctrl+=", window_id, MenuAction::ZoomIn),
-            _menu_item("Zoom Out", "Ctrl+-", window_id, MenuAction::ZoomOut),
-            text("Help").size(11).color(ACCENT_TEXT),
-            _menu_item("About Proteus Player", "", window_id, MenuAction::About),
-        ]
-        .spacing(2)
-        .width(Length::Fill);
-
-        content = content.push(
-            container(menu_panel)
-                .padding(8)
-                .width(Length::Fill)
-                .style(_menu_surface_style),
-        );
-    }
-
-    container(content).width(Length::Fill).into()
-}
-
-fn _menu_item<'a>(
-    label: &'a str,
-    shortcut: &'a str,
-    window_id: window::Id,
-    action: MenuAction,
-) -> Element<'a, Message> {
-    button(
-        row![
-            text(label).size(12).width(Length::Fill),
-            text(shortcut).size(11).color(ACCENT_TEXT)
-        ]
-        .align_y(Alignment::Center)
-        .width(Length::Fill),
-    )
-    .style(button::text)
-    .padding([2, 4])
-    .width(Length::Fill)
-    .on_press(Message::_WindowMenuAction { window_id, action })
-    .into()
+    .collect::<Vec<_>>()
+    .join(", ")
 }
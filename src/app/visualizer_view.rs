@@ -0,0 +1,115 @@
+use std::collections::VecDeque;
+use std::iter;
+
+use iced::widget::{Column, column, container, mouse_area, progress_bar, row, text};
+use iced::{Alignment, Element, Length, window};
+
+use crate::app::helpers::format_time;
+use crate::app::messages::Message;
+use crate::app::state::{LEVEL_HISTORY_LEN, PlayerWindowState, ProteusApp};
+use crate::app::styles::{background_style, progress_style};
+use crate::app::theme::Palette;
+use crate::app::widgets::level_graph;
+use crate::waveform::Level;
+
+const LABEL_WIDTH: f32 = 200.0;
+
+/// The fullscreen visualizer: the title and combination code above a lane
+/// for each part of the combination. proteus-lib only meters the mixed
+/// output, so each lane shows the output level scaled by the part's share of
+/// the file's measured levels at the playhead. Double-click to leave.
+pub(crate) fn visualizer_view<'a>(
+    state: &'a ProteusApp,
+    window: &'a PlayerWindowState,
+    window_id: window::Id,
+) -> Element<'a, Message> {
+    let palette = state.palette;
+
+    let header = column![
+        text(window.window_title.clone()).size(40),
+        text(
+            window
+                .playback
+                .combination_code()
+                .map(|code| format!("Combination {code}"))
+                .unwrap_or_default()
+        )
+        .size(20)
+        .color(palette.accent_text),
+    ]
+    .spacing(6);
+
+    let ids = window.playback.combination();
+    let body: Element<'a, Message> = if ids.is_empty() {
+        container(text("Nothing playing").size(20).color(palette.accent_text))
+            .center(Length::Fill)
+            .into()
+    } else {
+        ids.into_iter()
+            .enumerate()
+            .fold(
+                Column::new().spacing(12).height(Length::Fill),
+                |lanes, (slot, id)| lanes.push(lane(palette, window, slot, id)),
+            )
+            .into()
+    };
+
+    let progress = row![
+        text(format_time(window.current_time))
+            .size(14)
+            .color(palette.accent_text),
+        progress_bar(0.0..=100.0, window.current_time_percent as f32)
+            .girth(4)
+            .style(progress_style(palette)),
+        text(format_time(window.duration.unwrap_or(0.0)))
+            .size(14)
+            .color(palette.accent_text),
+    ]
+    .spacing(12)
+    .align_y(Alignment::Center);
+
+    mouse_area(
+        container(column![header, body, progress].spacing(24))
+            .padding(48)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(background_style(palette)),
+    )
+    .on_double_click(Message::ToggleVisualizer(window_id))
+    .into()
+}
+
+/// One part's take and mixer level, or that it has played to its end, beside
+/// its recent share of the output, newest on the right.
+fn lane<'a>(
+    palette: Palette,
+    window: &'a PlayerWindowState,
+    slot: usize,
+    id: String,
+) -> Element<'a, Message> {
+    let state = if window.finished_parts.contains(&slot) {
+        "Finished".to_owned()
+    } else {
+        format!("{:.0}%", window.part_level(slot) * 100.0)
+    };
+    let label = column![
+        text(format!("Part {}", slot + 1)).size(16),
+        text(format!("Take {id} · {state}"))
+            .size(13)
+            .color(palette.accent_text),
+    ]
+    .spacing(2)
+    .width(Length::Fixed(LABEL_WIDTH));
+
+    let history = window.part_history.get(slot);
+    let padding = LEVEL_HISTORY_LEN.saturating_sub(history.map_or(0, VecDeque::len));
+    let levels = iter::repeat_n(Level::default(), padding)
+        .chain(history.into_iter().flatten().copied())
+        .collect();
+
+    row![label, level_graph(levels, None, palette)]
+        .spacing(24)
+        .align_y(Alignment::Center)
+        .height(Length::Fill)
+        .into()
+}
@@ -1,11 +1,14 @@
 use iced::advanced::layout;
 use iced::advanced::overlay;
 use iced::advanced::renderer;
+use iced::advanced::renderer::Renderer as _;
 use iced::advanced::widget::tree::{self, Tree};
 use iced::advanced::{Clipboard, Layout, Shell, Widget};
 use iced::{Element, Event, Length, Rectangle, Renderer, Size, Theme, Vector, mouse};
 
 use crate::app::messages::Message;
+use crate::app::theme::Palette;
+use crate::waveform::Level;
 
 pub(crate) fn slider_with_handle_cursor<'a>(
     slider: impl Into<Element<'a, Message>>,
@@ -166,3 +169,113 @@ impl SliderHandleCursor<'_> {
         }
     }
 }
+
+/// Levels drawn as bars mirrored about the middle, the peak faint behind the
+/// RMS. Bars before `playhead`, a fraction of the width, are drawn in the
+/// accent colour, and the playhead itself as a line. Without a playhead, as
+/// for levels that have already played, every bar is in the accent colour.
+pub(crate) fn level_graph<'a>(
+    levels: Vec<Level>,
+    playhead: Option<f32>,
+    palette: Palette,
+) -> Element<'a, Message> {
+    Element::new(LevelGraph {
+        levels,
+        playhead,
+        palette,
+    })
+}
+
+struct LevelGraph {
+    levels: Vec<Level>,
+    playhead: Option<f32>,
+    palette: Palette,
+}
+
+impl Widget<Message, Theme, Renderer> for LevelGraph {
+    fn size(&self) -> Size<Length> {
+        Size::new(Length::Fill, Length::Fill)
+    }
+
+    fn layout(
+        &mut self,
+        _tree: &mut Tree,
+        _renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        layout::atomic(limits, Length::Fill, Length::Fill)
+    }
+
+    fn draw(
+        &self,
+        _tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        if self.levels.is_empty() || bounds.width < 1.0 {
+            return;
+        }
+
+        // One bar per pixel column at most, each the loudest of the levels
+        // it covers.
+        let columns = self.levels.len().min(bounds.width as usize);
+        let column_width = bounds.width / columns as f32;
+        let middle = bounds.center_y();
+        let played = self
+            .playhead
+            .map_or(bounds.width, |playhead| playhead * bounds.width);
+
+        for column in 0..columns {
+            let start = column * self.levels.len() / columns;
+            let end = ((column + 1) * self.levels.len() / columns).max(start + 1);
+            let level = self.levels[start..end]
+                .iter()
+                .fold(Level::default(), |loudest, level| Level {
+                    peak: loudest.peak.max(level.peak),
+                    rms: loudest.rms.max(level.rms),
+                });
+
+            let x = bounds.x + column as f32 * column_width;
+            let color = if x - bounds.x < played {
+                self.palette.primary
+            } else {
+                self.palette.rail
+            };
+            for (amplitude, alpha) in [(level.peak, 0.45), (level.rms, 1.0)] {
+                let half_height = (amplitude * bounds.height / 2.0).max(0.5);
+                renderer.fill_quad(
+                    renderer::Quad {
+                        bounds: Rectangle {
+                            x,
+                            y: middle - half_height,
+                            width: (column_width - 1.0).max(1.0),
+                            height: half_height * 2.0,
+                        },
+                        ..renderer::Quad::default()
+                    },
+                    color.scale_alpha(alpha),
+                );
+            }
+        }
+
+        if self.playhead.is_some() {
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: Rectangle {
+                        x: bounds.x + played.clamp(0.0, bounds.width - 1.0),
+                        y: bounds.y,
+                        width: 1.0,
+                        height: bounds.height,
+                    },
+                    ..renderer::Quad::default()
+                },
+                self.palette.text,
+            );
+        }
+    }
+}
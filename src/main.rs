@@ -1,13 +1,22 @@
 mod app;
 mod native_menu;
 mod playback;
+mod tray;
+mod waveform;
 
 use std::path::PathBuf;
 
+use crate::app::LaunchOptions;
+
 fn main() -> iced::Result {
+    let options = parse_launch_options();
+    if let Some(script) = &options.perf_script {
+        std::process::exit(app::run_perf_script(script, options.log_level));
+    }
+
     set_app_menu_name();
     app::install_startup_integrations();
-    app::run(parse_initial_path())
+    app::run(options)
 }
 
 #[cfg(target_os = "macos")]
@@ -29,21 +38,53 @@ fn set_app_menu_name() {
 #[cfg(not(target_os = "macos"))]
 fn set_app_menu_name() {}
 
-fn parse_initial_path() -> Option<PathBuf> {
+fn parse_launch_options() -> LaunchOptions {
+    let mut options = LaunchOptions::default();
     let mut args = std::env::args_os();
     let _ = args.next();
 
     while let Some(arg) = args.next() {
+        if arg == "--restore" {
+            options.restore_session = true;
+            continue;
+        }
+
+        if arg == "--log-level" {
+            let level = args.next().unwrap_or_default();
+            match level.to_string_lossy().parse() {
+                Ok(level) => options.log_level = Some(level),
+                Err(_) => eprintln!(
+                    "Unknown log level {}; expected off, error, warn, info, debug or trace",
+                    level.to_string_lossy()
+                ),
+            }
+            continue;
+        }
+
+        if arg == "--perf-script" {
+            let Some(path) = args.next() else {
+                eprintln!("Usage: proteus-player --perf-script <file>");
+                std::process::exit(2);
+            };
+            options.perf_script = Some(PathBuf::from(path));
+            continue;
+        }
+
+        if options.initial_path.is_some() {
+            continue;
+        }
+
         if arg == "--open"
             && let Some(path) = args.next()
         {
-            return Some(PathBuf::from(path));
+            options.initial_path = Some(PathBuf::from(path));
+            continue;
         }
 
         if !arg.to_string_lossy().starts_with('-') {
-            return Some(PathBuf::from(arg));
+            options.initial_path = Some(PathBuf::from(arg));
         }
     }
 
-    None
+    options
 }
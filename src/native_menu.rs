@@ -2,8 +2,11 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 
 use anyhow::{Result, anyhow};
+use iced::window;
 use muda::accelerator::Accelerator;
-use muda::{Menu, MenuEvent, MenuId, MenuItem, PredefinedMenuItem, Submenu};
+use muda::{
+    CheckMenuItem, IsMenuItem, Menu, MenuEvent, MenuId, MenuItem, PredefinedMenuItem, Submenu,
+};
 
 #[derive(Debug, Clone)]
 pub enum MenuAction {
@@ -11,40 +14,331 @@ pub enum MenuAction {
     NewWindow,
     Open,
     OpenRecent(PathBuf),
+    ShowLibrary,
+    ShowPreferences,
+    ShowShortcuts,
+    ShowNotifications,
+    ShowDiagnostics,
+    RevealLogs,
+    ShowCommandPalette,
     ZoomIn,
     ZoomOut,
+    ToggleMiniPlayer,
+    ToggleVisualizer,
+    PlayPause,
+    Stop,
+    Restart,
+    NewCombination,
+    NextFile,
+    SeekForward,
+    SeekBackward,
+    VolumeUp,
+    VolumeDown,
+    Mute,
+    ToggleLoop,
+    // The native menu uses predefined items for these; only the in-window
+    // menu bar sends them.
+    Minimize,
+    CloseWindow,
+    Quit,
+    BringAllToFront,
+    PauseAll,
+    CloseAll,
+    FocusWindow(window::Id),
+}
+
+impl MenuAction {
+    /// Items shown with a check mark that follows the focused window.
+    fn is_toggle(&self) -> bool {
+        matches!(
+            self,
+            Self::Mute | Self::ToggleLoop | Self::ToggleMiniPlayer | Self::ToggleVisualizer
+        )
+    }
+
+    /// Items enabled only while a player window is focused.
+    fn follows_focus(&self) -> bool {
+        matches!(
+            self,
+            Self::PlayPause
+                | Self::Stop
+                | Self::Restart
+                | Self::NewCombination
+                | Self::NextFile
+                | Self::SeekForward
+                | Self::SeekBackward
+                | Self::VolumeUp
+                | Self::VolumeDown
+        )
+    }
+}
+
+/// One entry in a menu of `MENUS`.
+pub enum MenuEntry {
+    Item(&'static str, MenuAction),
+    /// The recent files from `set_recent_files`, as a submenu.
+    OpenRecent,
+    /// One entry per open player window, from `set_windows`.
+    OpenWindows,
+    Separator,
+}
+
+/// The menus shared by the native menu bar and the in-window one. The
+/// native menu moves About, Settings and Quit to the app menu and uses the
+/// platform's own Minimize and Close Window items.
+pub const MENUS: [(&str, &[MenuEntry]); 5] = [
+    (
+        "File",
+        &[
+            MenuEntry::Item("New Window", MenuAction::NewWindow),
+            MenuEntry::Separator,
+            MenuEntry::Item("Open…", MenuAction::Open),
+            MenuEntry::OpenRecent,
+            MenuEntry::Separator,
+            MenuEntry::Item("Library", MenuAction::ShowLibrary),
+            MenuEntry::Item("Settings…", MenuAction::ShowPreferences),
+            MenuEntry::Separator,
+            MenuEntry::Item("Quit", MenuAction::Quit),
+        ],
+    ),
+    (
+        "View",
+        &[
+            MenuEntry::Item("Command Palette…", MenuAction::ShowCommandPalette),
+            MenuEntry::Separator,
+            MenuEntry::Item("Zoom In", MenuAction::ZoomIn),
+            MenuEntry::Item("Zoom Out", MenuAction::ZoomOut),
+            MenuEntry::Separator,
+            MenuEntry::Item("Mini Player", MenuAction::ToggleMiniPlayer),
+            MenuEntry::Item("Visualizer", MenuAction::ToggleVisualizer),
+        ],
+    ),
+    (
+        "Playback",
+        &[
+            MenuEntry::Item("Play", MenuAction::PlayPause),
+            MenuEntry::Item("Stop", MenuAction::Stop),
+            MenuEntry::Item("Restart", MenuAction::Restart),
+            MenuEntry::Separator,
+            MenuEntry::Item("New Combination", MenuAction::NewCombination),
+            MenuEntry::Separator,
+            MenuEntry::Item("Seek Forward", MenuAction::SeekForward),
+            MenuEntry::Item("Seek Backward", MenuAction::SeekBackward),
+            MenuEntry::Separator,
+            MenuEntry::Item("Volume Up", MenuAction::VolumeUp),
+            MenuEntry::Item("Volume Down", MenuAction::VolumeDown),
+            MenuEntry::Item("Mute", MenuAction::Mute),
+            MenuEntry::Separator,
+            MenuEntry::Item("Loop", MenuAction::ToggleLoop),
+        ],
+    ),
+    (
+        "Window",
+        &[
+            MenuEntry::Item("Minimize", MenuAction::Minimize),
+            MenuEntry::Separator,
+            MenuEntry::Item("Close Window", MenuAction::CloseWindow),
+            MenuEntry::Separator,
+            MenuEntry::Item("Bring All to Front", MenuAction::BringAllToFront),
+            MenuEntry::Item("Pause All", MenuAction::PauseAll),
+            MenuEntry::Item("Close All", MenuAction::CloseAll),
+            MenuEntry::Separator,
+            MenuEntry::OpenWindows,
+        ],
+    ),
+    (
+        "Help",
+        &[
+            MenuEntry::Item("Keyboard Shortcuts", MenuAction::ShowShortcuts),
+            MenuEntry::Item("Notifications", MenuAction::ShowNotifications),
+            MenuEntry::Separator,
+            MenuEntry::Item("Diagnostics", MenuAction::ShowDiagnostics),
+            MenuEntry::Item("Reveal Logs", MenuAction::RevealLogs),
+            MenuEntry::Separator,
+            MenuEntry::Item("About Proteus Player", MenuAction::About),
+        ],
+    ),
+];
+
+/// An open player window as listed in the Window menu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowMenuEntry {
+    pub id: window::Id,
+    pub title: String,
+    pub playing: bool,
+}
+
+impl WindowMenuEntry {
+    pub fn label(&self) -> String {
+        if self.playing {
+            format!("▶ {}", self.title)
+        } else {
+            self.title.clone()
+        }
+    }
+}
+
+/// What the Playback menu reflects about the focused player window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlaybackMenuState {
+    /// Whether a player window is focused at all.
+    pub focused: bool,
+    /// Whether the focused window has a file loaded.
+    pub loaded: bool,
+    pub playing: bool,
+    pub muted: bool,
+    pub looping: bool,
+    /// Whether the focused window is shrunk to the mini player.
+    pub mini: bool,
+    /// Whether the focused window fills the screen with the visualizer.
+    pub visualizer: bool,
+}
+
+impl PlaybackMenuState {
+    /// Transport items need a loaded file. Volume, mute and loop also apply
+    /// to an empty window, as they carry over to the next file.
+    pub fn is_enabled(&self, action: &MenuAction) -> bool {
+        match action {
+            MenuAction::PlayPause
+            | MenuAction::Stop
+            | MenuAction::Restart
+            | MenuAction::NewCombination
+            | MenuAction::NextFile
+            | MenuAction::SeekForward
+            | MenuAction::SeekBackward => self.loaded,
+            _ => self.focused,
+        }
+    }
+
+    pub fn is_checked(&self, action: &MenuAction) -> bool {
+        match action {
+            MenuAction::Mute => self.muted,
+            MenuAction::ToggleLoop => self.looping,
+            MenuAction::ToggleMiniPlayer => self.mini,
+            MenuAction::ToggleVisualizer => self.visualizer,
+            _ => false,
+        }
+    }
+
+    pub fn play_pause_label(&self) -> &'static str {
+        if self.playing { "Pause" } else { "Play" }
+    }
 }
 
 pub struct NativeMenu {
     _menu: Menu,
     actions: HashMap<MenuId, MenuAction>,
     recent_menu: Submenu,
+    window_menu: Submenu,
+    /// Items at the top of the Window menu, before the list of open windows.
+    window_menu_fixed_items: usize,
+    window_item_ids: Vec<MenuId>,
     recent_item_ids: Vec<MenuId>,
+    /// Items whose accelerators come from the keybinding table.
+    shortcut_items: Vec<(MenuItem, MenuAction)>,
+    /// Playback items, whose state follows the focused window.
+    playback_items: Vec<(MenuItem, MenuAction)>,
+    check_items: Vec<(CheckMenuItem, MenuAction)>,
+    play_pause: MenuItem,
 }
 
 impl NativeMenu {
-    pub fn install() -> Result<Self> {
+    /// Builds and installs the menu bar from `MENUS`. `accelerator` supplies
+    /// the shortcut shown for each action, in the format `muda` parses.
+    pub fn install(accelerator: impl Fn(&MenuAction) -> Option<String>) -> Result<Self> {
         let menu = Menu::new();
         let mut actions = HashMap::new();
+        let mut shortcut_items = Vec::new();
+        let mut playback_items = Vec::new();
+        let mut check_items = Vec::new();
+        let mut app_items = HashMap::new();
+        let mut play_pause = None;
+        let mut window_menu = None;
+        let mut window_menu_fixed_items = 0;
+        let recent_menu = Submenu::new("Open Recent", false);
+        let mut submenus = Vec::new();
 
-        let about_id = MenuId::new("about");
-        let new_window_id = MenuId::new("new_window");
-        let open_id = MenuId::new("open");
-        let zoom_in_id = MenuId::new("zoom_in");
-        let zoom_out_id = MenuId::new("zoom_out");
+        for (menu_index, (title, entries)) in MENUS.iter().enumerate() {
+            let mut submenu = SubmenuBuilder::new(title);
+            for (entry_index, entry) in entries.iter().enumerate() {
+                let (label, action) = match entry {
+                    MenuEntry::Item(label, action) => (label, action),
+                    MenuEntry::Separator => {
+                        submenu.separator();
+                        continue;
+                    }
+                    MenuEntry::OpenRecent => {
+                        submenu.append(&recent_menu)?;
+                        continue;
+                    }
+                    MenuEntry::OpenWindows => {
+                        submenu.end_fixed_items()?;
+                        window_menu = Some(submenu.submenu.clone());
+                        window_menu_fixed_items = submenu.len;
+                        continue;
+                    }
+                };
+                let id = MenuId::new(format!("menu_{menu_index}_{entry_index}"));
 
-        let about = MenuItem::with_id(
-            about_id.clone(),
-            "About Proteus Player",
-            true,
-            None::<Accelerator>,
-        );
+                match action {
+                    MenuAction::Minimize => submenu.append(&PredefinedMenuItem::minimize(None))?,
+                    MenuAction::CloseWindow => {
+                        submenu.append(&PredefinedMenuItem::close_window(None))?
+                    }
+                    MenuAction::Quit => {}
+                    MenuAction::About | MenuAction::ShowPreferences => {
+                        let item = MenuItem::with_id(id.clone(), label, true, None::<Accelerator>);
+                        app_items.insert(std::mem::discriminant(action), item.clone());
+                        shortcut_items.push((item, action.clone()));
+                        actions.insert(id, action.clone());
+                    }
+                    action if action.is_toggle() => {
+                        let item = CheckMenuItem::with_id(
+                            id.clone(),
+                            label,
+                            false,
+                            false,
+                            None::<Accelerator>,
+                        );
+                        submenu.append(&item)?;
+                        check_items.push((item, action.clone()));
+                        actions.insert(id, action.clone());
+                    }
+                    action if action.follows_focus() => {
+                        let item = MenuItem::with_id(id.clone(), label, false, None::<Accelerator>);
+                        submenu.append(&item)?;
+                        if matches!(action, MenuAction::PlayPause) {
+                            play_pause = Some(item.clone());
+                        }
+                        playback_items.push((item, action.clone()));
+                        actions.insert(id, action.clone());
+                    }
+                    action => {
+                        let item = MenuItem::with_id(id.clone(), label, true, None::<Accelerator>);
+                        submenu.append(&item)?;
+                        shortcut_items.push((item, action.clone()));
+                        actions.insert(id, action.clone());
+                    }
+                }
+            }
+            submenus.push((*title, submenu.submenu));
+        }
+
+        let mut app_item = |action: MenuAction| {
+            app_items
+                .remove(&std::mem::discriminant(&action))
+                .ok_or_else(|| anyhow!("the menu table has no {action:?} item"))
+        };
+        let about = app_item(MenuAction::About)?;
+        let preferences = app_item(MenuAction::ShowPreferences)?;
         let app_menu = Submenu::with_items(
             "Proteus Author",
             true,
             &[
                 &about,
                 &PredefinedMenuItem::separator(),
+                &preferences,
+                &PredefinedMenuItem::separator(),
                 &PredefinedMenuItem::services(None),
                 &PredefinedMenuItem::separator(),
                 &PredefinedMenuItem::hide(None),
@@ -56,31 +350,6 @@ impl NativeMenu {
         )
         .map_err(|e| anyhow!(e.to_string()))?;
 
-        let recent_menu = Submenu::new("Open Recent", false);
-
-        let file_menu = Submenu::with_items(
-            "File",
-            true,
-            &[
-                &MenuItem::with_id(
-                    new_window_id.clone(),
-                    "New Window",
-                    true,
-                    parse_accelerator("CmdOrCtrl+N"),
-                ),
-                &PredefinedMenuItem::separator(),
-                &MenuItem::with_id(
-                    open_id.clone(),
-                    "Open",
-                    true,
-                    parse_accelerator("CmdOrCtrl+O"),
-                ),
-                &recent_menu,
-                &PredefinedMenuItem::separator(),
-            ],
-        )
-        .map_err(|e| anyhow!(e.to_string()))?;
-
         let edit_menu = Submenu::with_items(
             "Edit",
             true,
@@ -95,55 +364,75 @@ impl NativeMenu {
         )
         .map_err(|e| anyhow!(e.to_string()))?;
 
-        let view_menu = Submenu::with_items(
-            "View",
-            true,
-            &[
-                &MenuItem::with_id(
-                    zoom_in_id.clone(),
-                    "Zoom In",
-                    true,
-                    parse_accelerator("CmdOrCtrl+="),
-                ),
-                &MenuItem::with_id(
-                    zoom_out_id.clone(),
-                    "Zoom Out",
-                    true,
-                    parse_accelerator("CmdOrCtrl+-"),
-                ),
-            ],
-        )
-        .map_err(|e| anyhow!(e.to_string()))?;
-
-        let window_menu = Submenu::with_items(
-            "Window",
-            true,
-            &[
-                &PredefinedMenuItem::minimize(None),
-                &PredefinedMenuItem::separator(),
-                &PredefinedMenuItem::close_window(None),
-            ],
-        )
-        .map_err(|e| anyhow!(e.to_string()))?;
-
-        menu.append_items(&[&app_menu, &file_menu, &edit_menu, &view_menu, &window_menu])
-            .map_err(|e| anyhow!(e.to_string()))?;
+        menu.append(&app_menu).map_err(|e| anyhow!(e.to_string()))?;
+        for (title, submenu) in &submenus {
+            menu.append(submenu).map_err(|e| anyhow!(e.to_string()))?;
+            // Edit sits after File, where every macOS app puts it.
+            if *title == "File" {
+                menu.append(&edit_menu)
+                    .map_err(|e| anyhow!(e.to_string()))?;
+            }
+        }
 
         #[cfg(target_os = "macos")]
-        menu.init_for_nsapp();
-
-        actions.insert(about_id, MenuAction::About);
-        actions.insert(new_window_id, MenuAction::NewWindow);
-        actions.insert(open_id, MenuAction::Open);
-        actions.insert(zoom_in_id, MenuAction::ZoomIn);
-        actions.insert(zoom_out_id, MenuAction::ZoomOut);
+        {
+            menu.init_for_nsapp();
+            if let Some((_, help_menu)) = submenus.iter().find(|(title, _)| *title == "Help") {
+                help_menu.set_as_help_menu_for_nsapp();
+            }
+        }
 
-        Ok(Self {
+        let native_menu = Self {
             _menu: menu,
             actions,
             recent_menu,
             recent_item_ids: Vec::new(),
-        })
+            window_menu: window_menu
+                .ok_or_else(|| anyhow!("the menu table has no list of open windows"))?,
+            window_menu_fixed_items,
+            window_item_ids: Vec::new(),
+            shortcut_items,
+            playback_items,
+            check_items,
+            play_pause: play_pause.ok_or_else(|| anyhow!("the menu table has no Play item"))?,
+        };
+        native_menu.set_playback_state(PlaybackMenuState::default());
+        native_menu.set_accelerators(accelerator)?;
+        Ok(native_menu)
+    }
+
+    /// Updates item accelerators after the keybinding table changes. Chords
+    /// the platform menu cannot express are left off the item but still work
+    /// through the window's key handler.
+    pub fn set_accelerators(
+        &self,
+        accelerator: impl Fn(&MenuAction) -> Option<String>,
+    ) -> Result<()> {
+        for (item, action) in &self.shortcut_items {
+            item.set_accelerator(accelerator(action).as_deref().and_then(parse_accelerator))
+                .map_err(|e| anyhow!(e.to_string()))?;
+        }
+        for (item, action) in &self.playback_items {
+            item.set_accelerator(accelerator(action).as_deref().and_then(parse_accelerator))
+                .map_err(|e| anyhow!(e.to_string()))?;
+        }
+        for (item, action) in &self.check_items {
+            item.set_accelerator(accelerator(action).as_deref().and_then(parse_accelerator))
+                .map_err(|e| anyhow!(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Enables, checks and relabels the Playback items for the focused window.
+    pub fn set_playback_state(&self, state: PlaybackMenuState) {
+        for (item, action) in &self.playback_items {
+            item.set_enabled(state.is_enabled(action));
+        }
+        for (item, action) in &self.check_items {
+            item.set_enabled(state.is_enabled(action));
+            item.set_checked(state.is_checked(action));
+        }
+        self.play_pause.set_text(state.play_pause_label());
     }
 
     pub fn set_recent_files(&mut self, files: &[PathBuf]) -> Result<()> {
@@ -173,12 +462,139 @@ impl NativeMenu {
         Ok(())
     }
 
-    pub fn poll_action(&self) -> Option<MenuAction> {
-        let event = MenuEvent::receiver().try_recv().ok()?;
-        self.actions.get(event.id()).cloned()
+    /// Replaces the list of open windows at the end of the Window menu.
+    pub fn set_windows(&mut self, windows: &[WindowMenuEntry]) -> Result<()> {
+        for id in self.window_item_ids.drain(..) {
+            self.actions.remove(&id);
+        }
+        while self
+            .window_menu
+            .remove_at(self.window_menu_fixed_items)
+            .is_some()
+        {}
+
+        for (index, entry) in windows.iter().enumerate() {
+            let id = MenuId::new(format!("window_{index}"));
+            let item = MenuItem::with_id(id.clone(), entry.label(), true, None::<Accelerator>);
+
+            self.window_menu
+                .append(&item)
+                .map_err(|e| anyhow!(e.to_string()))?;
+            self.actions
+                .insert(id.clone(), MenuAction::FocusWindow(entry.id));
+            self.window_item_ids.push(id);
+        }
+
+        Ok(())
+    }
+
+    /// The action behind a clicked item, if the item belongs to this menu.
+    pub fn action_for(&self, id: &MenuId) -> Option<MenuAction> {
+        self.actions.get(id).cloned()
+    }
+}
+
+/// Appends a menu's items, dropping separators left next to each other or
+/// at either end by items the native menu keeps elsewhere.
+struct SubmenuBuilder {
+    submenu: Submenu,
+    len: usize,
+    separate: bool,
+}
+
+impl SubmenuBuilder {
+    fn new(title: &str) -> Self {
+        Self {
+            submenu: Submenu::new(title, true),
+            len: 0,
+            separate: false,
+        }
+    }
+
+    fn separator(&mut self) {
+        self.separate = self.len > 0;
+    }
+
+    fn append(&mut self, item: &dyn IsMenuItem) -> Result<()> {
+        self.end_fixed_items()?;
+        self.push(item)
+    }
+
+    /// Adds a pending separator before items appended later, at run time.
+    fn end_fixed_items(&mut self) -> Result<()> {
+        if std::mem::take(&mut self.separate) {
+            self.push(&PredefinedMenuItem::separator())?;
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, item: &dyn IsMenuItem) -> Result<()> {
+        self.submenu
+            .append(item)
+            .map_err(|e| anyhow!(e.to_string()))?;
+        self.len += 1;
+        Ok(())
     }
 }
 
+/// The next clicked menu item. The menu bar and the tray share one event
+/// channel, so each owner looks the id up in its own table.
+pub fn next_menu_event() -> Option<MenuId> {
+    MenuEvent::receiver()
+        .try_recv()
+        .ok()
+        .map(|event| event.id().clone())
+}
+
+/// Whether menu items can show `value` as their accelerator. Some keys that
+/// the window handles, such as `Plus`, have no muda equivalent.
+pub fn accelerator_supported(value: &str) -> bool {
+    parse_accelerator(value).is_some()
+}
+
 fn parse_accelerator(value: &str) -> Option<Accelerator> {
     value.parse().ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn playback_menu_follows_the_focused_window() {
+        let none = PlaybackMenuState::default();
+        assert!(!none.is_enabled(&MenuAction::PlayPause));
+        assert!(!none.is_enabled(&MenuAction::Mute));
+
+        let empty = PlaybackMenuState {
+            focused: true,
+            ..PlaybackMenuState::default()
+        };
+        assert!(!empty.is_enabled(&MenuAction::PlayPause));
+        assert!(!empty.is_enabled(&MenuAction::SeekForward));
+        assert!(empty.is_enabled(&MenuAction::VolumeUp));
+        assert!(empty.is_enabled(&MenuAction::ToggleLoop));
+        assert!(!empty.is_checked(&MenuAction::Mute));
+        assert!(!empty.is_checked(&MenuAction::ToggleLoop));
+
+        let paused = PlaybackMenuState {
+            loaded: true,
+            ..empty
+        };
+        assert!(paused.is_enabled(&MenuAction::PlayPause));
+        assert!(paused.is_enabled(&MenuAction::NewCombination));
+        assert_eq!(paused.play_pause_label(), "Play");
+
+        let looping_muted = PlaybackMenuState {
+            playing: true,
+            muted: true,
+            looping: true,
+            ..paused
+        };
+        assert!(looping_muted.is_checked(&MenuAction::Mute));
+        assert!(looping_muted.is_checked(&MenuAction::ToggleLoop));
+        assert!(!looping_muted.is_checked(&MenuAction::ToggleMiniPlayer));
+        assert!(!looping_muted.is_checked(&MenuAction::PlayPause));
+        assert_eq!(looping_muted.play_pause_label(), "Pause");
+    }
+}
@@ -1,31 +1,158 @@
 use std::fmt;
 use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 
+#[cfg(feature = "with-player")]
 use anyhow::{Error, anyhow};
 #[cfg(feature = "with-player")]
-use proteus_lib::playback::player::Player;
+use proteus_lib::container::info::try_get_durations;
+#[cfg(feature = "with-player")]
+use proteus_lib::container::prot::Prot;
+#[cfg(feature = "with-player")]
+use proteus_lib::playback::player::{EndOfStreamAction, Player, PlayerInitOptions, PlayerSource};
 #[cfg(feature = "with-player")]
-use proteus_lib::tools::decode::check_audio_file_supported;
+use proteus_lib::tools::decode::{DecoderOpenError, try_check_audio_file_supported};
+
+#[cfg(feature = "with-player")]
+use crate::app::storage;
+
+/// File extensions the player can open, without the leading dot.
+pub const SUPPORTED_EXTENSIONS: &[&str] = &["prot", "mka", "wav", "mp3", "ogg", "aiff", "aif"];
 
-#[derive(Debug)]
+/// Why a file could not be opened or played, with enough detail for the
+/// error panel to explain it.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PlaybackLoadError {
-    UnsupportedFormat { file_name: String },
-    Other(Error),
+    NotFound {
+        file_name: String,
+    },
+    PermissionDenied {
+        file_name: String,
+    },
+    UnsupportedFormat {
+        file_name: String,
+        detail: Option<String>,
+    },
+    /// A `.prot` or `.mka` container that could not be parsed.
+    CorruptContainer {
+        file_name: String,
+        detail: String,
+    },
+    /// A file without a single audio stream to play.
+    MissingTracks {
+        file_name: String,
+    },
+    /// Audio streams in a codec the decoder cannot handle.
+    DecoderFailure {
+        file_name: String,
+        detail: String,
+    },
+    /// No audio output device could be opened to play through.
+    OutputUnavailable,
+    Other(String),
 }
 
 impl PlaybackLoadError {
+    #[cfg(feature = "with-player")]
     fn other(error: impl Into<Error>) -> Self {
-        Self::Other(error.into())
+        Self::Other(format!("{:#}", error.into()))
+    }
+
+    fn from_io(path: &Path, error: &std::io::Error) -> Self {
+        let file_name = display_file_name(path);
+        match error.kind() {
+            std::io::ErrorKind::NotFound => Self::NotFound { file_name },
+            std::io::ErrorKind::PermissionDenied => Self::PermissionDenied { file_name },
+            _ => Self::Other(format!("could not open {file_name}: {error}")),
+        }
+    }
+
+    /// A short name for the kind of failure, for bug reports.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::NotFound { .. } => "not found",
+            Self::PermissionDenied { .. } => "permission denied",
+            Self::UnsupportedFormat { .. } => "unsupported format",
+            Self::CorruptContainer { .. } => "corrupt container",
+            Self::MissingTracks { .. } => "missing tracks",
+            Self::DecoderFailure { .. } => "decoder failure",
+            Self::OutputUnavailable => "output unavailable",
+            Self::Other(_) => "other",
+        }
+    }
+
+    /// What the underlying library reported, when there is more to say than
+    /// the message itself.
+    pub fn detail(&self) -> Option<&str> {
+        match self {
+            Self::UnsupportedFormat { detail, .. } => detail.as_deref(),
+            Self::CorruptContainer { detail, .. } | Self::DecoderFailure { detail, .. } => {
+                Some(detail)
+            }
+            _ => None,
+        }
+    }
+
+    /// Things the user can try, most likely first.
+    pub fn suggestions(&self) -> &'static [&'static str] {
+        match self {
+            Self::NotFound { .. } => &[
+                "Check that the file has not been moved, renamed or deleted.",
+                "If it is on a removable or network drive, make sure the drive is connected.",
+            ],
+            Self::PermissionDenied { .. } => &[
+                "Check that your account is allowed to read the file.",
+                "Copy the file somewhere you own and open the copy.",
+            ],
+            Self::UnsupportedFormat { .. } => &[
+                "Convert the file to WAV, MP3, Ogg, AIFF or a Proteus container.",
+                "Check that the file extension matches its contents.",
+            ],
+            Self::CorruptContainer { .. } => &[
+                "Export the container again from Proteus Author.",
+                "If the file was downloaded or copied, fetch a fresh copy.",
+            ],
+            Self::MissingTracks { .. } => &[
+                "Check that the file contains audio and not only metadata.",
+                "Export the container again with at least one track per part.",
+            ],
+            Self::DecoderFailure { .. } => &[
+                "Convert the audio streams to a supported codec such as FLAC, PCM, Vorbis or MP3.",
+            ],
+            Self::OutputUnavailable => &[
+                "Connect or switch on an output device such as speakers or headphones.",
+                "Close other applications that may hold the device exclusively.",
+                "Check the system sound settings, then press Play again.",
+            ],
+            Self::Other(_) => &[],
+        }
     }
 }
 
 impl fmt::Display for PlaybackLoadError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::UnsupportedFormat { file_name } => {
+            Self::NotFound { file_name } => write!(f, "{file_name} could not be found"),
+            Self::PermissionDenied { file_name } => {
+                write!(f, "You do not have permission to open {file_name}")
+            }
+            Self::UnsupportedFormat { file_name, .. } => {
                 write!(f, "{file_name} is in an unsupported format")
             }
-            Self::Other(error) => error.fmt(f),
+            Self::CorruptContainer { file_name, .. } => {
+                write!(f, "{file_name} is damaged and could not be read")
+            }
+            Self::MissingTracks { file_name } => {
+                write!(f, "{file_name} does not contain any audio tracks")
+            }
+            Self::DecoderFailure { file_name, .. } => {
+                write!(f, "The audio in {file_name} could not be decoded")
+            }
+            Self::OutputUnavailable => write!(f, "No audio output device is available"),
+            Self::Other(message) => write!(f, "Failed to load file: {message}"),
         }
     }
 }
@@ -40,127 +167,316 @@ pub struct PlaybackStatus {
     pub playing: bool,
 }
 
+impl PlaybackStatus {
+    /// Whether there is nothing to play: no file loaded, or the player could
+    /// not be built for it.
+    pub fn is_empty(&self) -> bool {
+        self.duration.is_none()
+    }
+}
+
+/// The state of a player's output buffer, for diagnostics.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BufferStats {
+    /// Chunks waiting in the output sink.
+    pub queued_chunks: usize,
+    /// Audio waiting in the output sink, in milliseconds.
+    pub queued_ms: f64,
+    /// Times the output ran dry since the player was built.
+    pub underruns: u64,
+    /// Longest time a mix cycle ran past its deadline, in milliseconds.
+    pub max_overrun_ms: f64,
+}
+
+/// Metadata gathered without starting playback.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MediaInfo {
+    pub duration: Option<f64>,
+    pub part_count: Option<usize>,
+}
+
+/// What opening a file is busy with, for the loading indicator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadStage {
+    /// Checking that the file exists and is in a format that can be played.
+    Checking,
+    /// Building the player, which reads every part of a container.
+    Building,
+}
+
+impl LoadStage {
+    pub const COUNT: usize = 2;
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Checking => "Checking file",
+            Self::Building => "Reading parts",
+        }
+    }
+
+    /// How many stages are done before this one starts.
+    pub fn index(self) -> usize {
+        match self {
+            Self::Checking => 0,
+            Self::Building => 1,
+        }
+    }
+}
+
+/// How long the player thread waits for a command before reporting again.
+const REPORT_INTERVAL: Duration = Duration::from_millis(16);
+
+/// What the player thread last reported about its player.
+#[derive(Debug, Clone, Default)]
+struct PlayerReport {
+    loaded: bool,
+    duration: Option<f64>,
+    time: f64,
+    playing: bool,
+    combination: Vec<String>,
+    finished_parts: Vec<usize>,
+    output_level: f32,
+    buffer_stats: Option<BufferStats>,
+    /// Times playback stopped without producing any sound.
+    output_failures: u64,
+}
+
+/// Written by the player thread and read by its controller.
+#[derive(Debug, Default)]
+struct SharedReport {
+    /// Commands the thread has run. A report is only taken once the thread
+    /// has run every command sent, so it never undoes a change made since.
+    applied: u64,
+    report: PlayerReport,
+    load_stage: Option<(u64, LoadStage)>,
+}
+
+// Without a player the thread only loads, so transport and settings
+// commands go unread.
+#[cfg_attr(not(feature = "with-player"), allow(dead_code))]
+enum PlayerCommand {
+    Load { generation: u64, path: PathBuf },
+    Commit(u64),
+    Discard,
+    PlayPause,
+    Stop,
+    Pause,
+    Rewind,
+    Shutdown,
+    Shuffle,
+    Seek(f64),
+    StartAt { position: f64, play: bool },
+    SetPartLevel { slot: usize, level: f32 },
+    SetVolume(f32),
+    SetLooping(bool),
+    SetLoopRegion(Option<(f64, f64)>),
+    SetMaxSinkChunks(usize),
+}
+
+/// A build finished by the player thread.
+struct LoadResult {
+    generation: u64,
+    path: PathBuf,
+    result: Result<LoadedFile, PlaybackLoadError>,
+}
+
+/// What is known about a built player before it is committed.
+#[derive(Debug, Clone, Default)]
+struct LoadedFile {
+    duration: Option<f64>,
+    combination: Vec<String>,
+}
+
+/// Plays one window's file. proteus-lib's `Player` is not `Send`, and
+/// building one reads every part of a container, so each controller keeps
+/// its player on a thread of its own. Commands are sent to that thread and
+/// take effect in the controller's state straight away; the thread's
+/// reports are picked up by [`Self::refresh`].
 pub struct PlaybackController {
-    #[cfg(feature = "with-player")]
-    player: Option<Player>,
-    #[cfg(not(feature = "with-player"))]
-    player: Option<()>,
+    commands: Sender<PlayerCommand>,
+    loads: Receiver<LoadResult>,
+    shared: Arc<Mutex<SharedReport>>,
+    /// Commands sent to the player thread.
+    sent: u64,
+    report: PlayerReport,
+    load_stage: Option<(u64, LoadStage)>,
+    /// The load the thread is building, if any.
+    building: Option<u64>,
+    /// A built player waiting for [`Self::commit_load`].
+    ready: Option<(u64, PathBuf, LoadedFile)>,
     current_path: Option<PathBuf>,
+    volume: f32,
+    looping: bool,
+    output_failures_seen: u64,
 }
 
 impl PlaybackController {
     pub fn new() -> Self {
+        let (commands, command_receiver) = mpsc::channel();
+        let (load_sender, loads) = mpsc::channel();
+        let shared = Arc::new(Mutex::new(SharedReport::default()));
+        let thread_shared = Arc::clone(&shared);
+        // Without the thread every command is dropped and loads fail, which
+        // `poll_load` reports.
+        if let Err(error) = thread::Builder::new()
+            .name("player".to_owned())
+            .spawn(move || PlayerThread::new(thread_shared, load_sender).run(&command_receiver))
+        {
+            log::error!("could not start the player thread: {error}");
+        }
+
         Self {
-            player: None,
+            commands,
+            loads,
+            shared,
+            sent: 0,
+            report: PlayerReport::default(),
+            load_stage: None,
+            building: None,
+            ready: None,
             current_path: None,
+            volume: 1.0,
+            looping: false,
+            output_failures_seen: 0,
         }
     }
 
-    pub fn load(&mut self, path: &Path) -> Result<(), PlaybackLoadError> {
-        preflight_supported_format(path)?;
+    /// Starts checking `path` and building its player on the player thread.
+    /// Whatever is loaded keeps playing until [`Self::commit_load`] swaps the
+    /// new player in.
+    pub fn load(&mut self, generation: u64, path: PathBuf) {
+        log::debug!("player build generation={generation} path={path:?}");
+        self.building = Some(generation);
+        self.ready = None;
+        self.send(PlayerCommand::Load { generation, path });
+    }
 
-        // Drop any existing player before replacing it.
-        self.shutdown();
+    /// A build that has finished since the last call, with its generation.
+    /// Called every tick.
+    pub fn poll_load(&mut self) -> Option<(u64, Result<(), PlaybackLoadError>)> {
+        let LoadResult {
+            generation,
+            path,
+            result,
+        } = match self.loads.try_recv() {
+            Ok(finished) => finished,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => {
+                let generation = self.building.take()?;
+                let error = PlaybackLoadError::Other("the player thread stopped".to_owned());
+                return Some((generation, Err(error)));
+            }
+        };
 
-        #[cfg(feature = "with-player")]
-        {
-            let path_string = path
-                .to_str()
-                .ok_or_else(|| PlaybackLoadError::other(anyhow!("path contains invalid UTF-8")))?
-                .to_owned();
-
-            let extension = path
-                .extension()
-                .and_then(|ext| ext.to_str())
-                .map(str::to_ascii_lowercase);
-            let player = match extension.as_deref() {
-                Some("prot") | Some("mka") => Player::new(&path_string),
-                _ => Player::new_from_file_paths_legacy(vec![vec![path_string.clone()]]),
-            };
+        if self.building == Some(generation) {
+            self.building = None;
+        }
+        let result = result.map(|file| self.ready = Some((generation, path, file)));
+        Some((generation, result))
+    }
 
-            player.set_max_sink_chunks(30);
+    /// Swaps in the player built for `generation`, shutting down the one it
+    /// replaces. Does nothing when no such player is waiting.
+    pub fn commit_load(&mut self, generation: u64) {
+        let Some((_, path, file)) = self
+            .ready
+            .take_if(|(ready_generation, _, _)| *ready_generation == generation)
+        else {
+            return;
+        };
 
-            self.player = Some(player);
-            self.current_path = Some(path.to_path_buf());
-            Ok(())
-        }
+        self.send(PlayerCommand::Commit(generation));
+        self.current_path = Some(path);
+        self.report = PlayerReport {
+            loaded: cfg!(feature = "with-player"),
+            duration: file.duration,
+            combination: file.combination,
+            output_failures: self.report.output_failures,
+            ..PlayerReport::default()
+        };
+    }
 
-        #[cfg(not(feature = "with-player"))]
-        {
-            let _ = path
-                .to_str()
-                .ok_or_else(|| PlaybackLoadError::other(anyhow!("path contains invalid UTF-8")))?;
-            self.player = None;
-            self.current_path = Some(path.to_path_buf());
-            Ok(())
-        }
+    /// Drops the build under way, or the one waiting to be committed.
+    pub fn discard_load(&mut self) {
+        self.building = None;
+        self.ready = None;
+        self.send(PlayerCommand::Discard);
     }
 
-    pub fn status(&self) -> PlaybackStatus {
-        #[cfg(feature = "with-player")]
-        {
-            match &self.player {
-                Some(player) => PlaybackStatus {
-                    duration: Some(player.get_duration()),
-                    time: player.get_time(),
-                    volume: player.get_volume(),
-                    playing: player.is_playing(),
-                },
-                None => PlaybackStatus {
-                    duration: None,
-                    time: 0.0,
-                    volume: 1.0,
-                    playing: false,
-                },
-            }
+    /// What the build for `generation` is busy with, while it runs.
+    pub fn load_stage(&self, generation: u64) -> Option<LoadStage> {
+        self.load_stage
+            .filter(|(stage_generation, _)| *stage_generation == generation)
+            .map(|(_, stage)| stage)
+    }
+
+    /// Takes the player thread's latest report. Called every tick.
+    pub fn refresh(&mut self) {
+        let Ok(shared) = self.shared.lock() else {
+            return;
+        };
+
+        self.load_stage = shared.load_stage;
+        if shared.applied == self.sent {
+            self.report.clone_from(&shared.report);
         }
+    }
 
-        #[cfg(not(feature = "with-player"))]
-        {
-            PlaybackStatus {
-                duration: None,
-                time: 0.0,
-                volume: 1.0,
-                playing: false,
-            }
+    pub fn status(&self) -> PlaybackStatus {
+        PlaybackStatus {
+            duration: self.report.duration,
+            time: self.report.time,
+            volume: self.volume,
+            playing: self.report.playing,
         }
     }
 
     pub fn play_pause(&mut self) {
-        #[cfg(feature = "with-player")]
-        {
-            let Some(player) = &mut self.player else {
-                return;
-            };
-
-            if player.is_playing() {
-                player.pause();
-            } else {
-                player.play();
-            }
+        if !self.report.loaded {
+            return;
         }
+
+        self.report.playing = !self.report.playing;
+        self.send(PlayerCommand::PlayPause);
     }
 
     pub fn stop(&mut self) {
-        #[cfg(feature = "with-player")]
-        {
-            let Some(player) = &mut self.player else {
-                return;
-            };
+        if !self.report.loaded {
+            return;
+        }
+
+        self.report.playing = false;
+        self.report.time = 0.0;
+        self.send(PlayerCommand::Stop);
+    }
 
-            player.stop();
-            player.refresh_tracks();
+    pub fn pause(&mut self) {
+        if !self.report.loaded {
+            return;
         }
+
+        self.report.playing = false;
+        self.send(PlayerCommand::Pause);
     }
 
-    pub fn shutdown(&mut self) {
-        #[cfg(feature = "with-player")]
-        if let Some(player) = &self.player {
-            player.stop();
+    /// Pauses and returns to the beginning, keeping the current combination.
+    pub fn rewind(&mut self) {
+        if !self.report.loaded {
+            return;
         }
 
-        self.player = None;
+        self.report.playing = false;
+        self.report.time = 0.0;
+        self.send(PlayerCommand::Rewind);
+    }
+
+    pub fn shutdown(&mut self) {
+        self.send(PlayerCommand::Shutdown);
         self.current_path = None;
+        self.report = PlayerReport {
+            output_failures: self.report.output_failures,
+            ..PlayerReport::default()
+        };
     }
 
     pub fn reset(&mut self) {
@@ -168,24 +484,65 @@ impl PlaybackController {
     }
 
     pub fn shuffle(&mut self) {
-        #[cfg(feature = "with-player")]
-        {
-            if let Some(player) = &mut self.player {
-                player.refresh_tracks();
-            }
+        if self.report.loaded {
+            self.send(PlayerCommand::Shuffle);
         }
     }
 
     pub fn seek(&mut self, position_seconds: f64) {
-        #[cfg(not(feature = "with-player"))]
-        let _ = position_seconds;
+        if !self.report.loaded {
+            return;
+        }
 
-        #[cfg(feature = "with-player")]
-        {
-            if let Some(player) = &mut self.player {
-                player.seek(position_seconds.max(0.0));
-            }
+        self.report.time = position_seconds.max(0.0);
+        self.send(PlayerCommand::Seek(position_seconds));
+    }
+
+    /// Seeks to `position_seconds`, starting playback there when `play` is set.
+    pub fn start_at(&mut self, position_seconds: f64, play: bool) {
+        if !self.report.loaded {
+            return;
         }
+
+        self.report.time = position_seconds.max(0.0);
+        self.report.playing |= play;
+        self.send(PlayerCommand::StartAt {
+            position: position_seconds,
+            play,
+        });
+    }
+
+    /// Returns the ids of the tracks in the current combination.
+    pub fn combination(&self) -> Vec<String> {
+        self.report.combination.clone()
+    }
+
+    /// The combination's track ids joined into a code that can be shared and
+    /// pasted, or `None` when nothing is loaded.
+    pub fn combination_code(&self) -> Option<String> {
+        let ids = self.combination();
+        (!ids.is_empty()).then(|| ids.join("-"))
+    }
+
+    /// Sets the level of one part of the combination, `0.0` to `1.0`, while it
+    /// plays. Returns `false` when nothing is loaded or there is no such part.
+    pub fn set_part_level(&mut self, slot: usize, level: f32) -> bool {
+        if !self.report.loaded || slot >= self.report.combination.len() {
+            return false;
+        }
+
+        self.send(PlayerCommand::SetPartLevel { slot, level });
+        true
+    }
+
+    /// Slots of the combination whose parts have played to their end.
+    pub fn finished_parts(&self) -> Vec<usize> {
+        self.report.finished_parts.clone()
+    }
+
+    /// The loudest channel's most recent output peak, `0.0` to `1.0`.
+    pub fn output_level(&self) -> f32 {
+        self.report.output_level
     }
 
     pub fn seek_by(&mut self, offset_seconds: f64) {
@@ -195,20 +552,442 @@ impl PlaybackController {
         self.seek(next);
     }
 
+    /// Sets the volume, which is also carried over to files loaded later.
     pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
+        self.send(PlayerCommand::SetVolume(self.volume));
+    }
+
+    /// Sets whether playback restarts from the beginning at the end of the file.
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+        self.send(PlayerCommand::SetLooping(looping));
+    }
+
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
+
+    /// Sets a region to repeat while playing, or clears it with `None`. The
+    /// player thread jumps back to its start once playback passes its end.
+    pub fn set_loop_region(&mut self, region: Option<(f64, f64)>) {
+        let region = region.filter(|(start, end)| end > start);
+        self.send(PlayerCommand::SetLoopRegion(region));
+    }
+
+    /// Reports, once, a player that was asked to play but stopped without
+    /// producing any sound, which is how it fails when no output device can
+    /// be opened. Called every tick.
+    pub fn take_output_error(&mut self) -> Option<PlaybackLoadError> {
+        if self.report.output_failures == self.output_failures_seen {
+            return None;
+        }
+
+        self.output_failures_seen = self.report.output_failures;
+        Some(PlaybackLoadError::OutputUnavailable)
+    }
+
+    /// Output buffer statistics, or `None` when nothing is loaded.
+    pub fn buffer_stats(&self) -> Option<BufferStats> {
+        self.report.buffer_stats
+    }
+
+    /// Sets how many decoded chunks the output sink may queue ahead.
+    pub fn set_max_sink_chunks(&mut self, chunks: usize) {
+        self.send(PlayerCommand::SetMaxSinkChunks(chunks));
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.report.loaded
+    }
+
+    pub fn current_path(&self) -> Option<&Path> {
+        self.current_path.as_deref()
+    }
+
+    fn send(&mut self, command: PlayerCommand) {
+        self.sent += 1;
+        if self.commands.send(command).is_err() {
+            log::warn!("player thread is not running");
+        }
+    }
+}
+
+/// The player thread behind a [`PlaybackController`]. It owns the player,
+/// runs commands as they arrive and reports in between. It ends, shutting
+/// the player down, once the controller is dropped.
+struct PlayerThread {
+    shared: Arc<Mutex<SharedReport>>,
+    loads: Sender<LoadResult>,
+    applied: u64,
+    staged: Option<StagedPlayer>,
+    output_failures: u64,
+    #[cfg(feature = "with-player")]
+    player: Option<Player>,
+    /// Keeps a file whose name is not valid Unicode reachable by the player.
+    #[cfg(feature = "with-player")]
+    alias: Option<PathAlias>,
+    /// Set when playback is started and cleared once audio is heard, to
+    /// tell a player that could not reach an output device.
+    #[cfg(feature = "with-player")]
+    awaiting_output: bool,
+    #[cfg(feature = "with-player")]
+    volume: f32,
+    #[cfg(feature = "with-player")]
+    max_sink_chunks: usize,
+    #[cfg(feature = "with-player")]
+    looping: bool,
+    /// Start and end of an A-B loop within the file, in seconds.
+    #[cfg(feature = "with-player")]
+    loop_region: Option<(f64, f64)>,
+}
+
+/// A player built for a load, waiting to be committed.
+struct StagedPlayer {
+    generation: u64,
+    #[cfg(feature = "with-player")]
+    player: Player,
+    #[cfg(feature = "with-player")]
+    alias: Option<PathAlias>,
+}
+
+impl PlayerThread {
+    fn new(shared: Arc<Mutex<SharedReport>>, loads: Sender<LoadResult>) -> Self {
+        Self {
+            shared,
+            loads,
+            applied: 0,
+            staged: None,
+            output_failures: 0,
+            #[cfg(feature = "with-player")]
+            player: None,
+            #[cfg(feature = "with-player")]
+            alias: None,
+            #[cfg(feature = "with-player")]
+            awaiting_output: false,
+            #[cfg(feature = "with-player")]
+            volume: 1.0,
+            #[cfg(feature = "with-player")]
+            max_sink_chunks: 30,
+            #[cfg(feature = "with-player")]
+            looping: false,
+            #[cfg(feature = "with-player")]
+            loop_region: None,
+        }
+    }
+
+    fn run(mut self, commands: &Receiver<PlayerCommand>) {
+        loop {
+            match commands.recv_timeout(REPORT_INTERVAL) {
+                Ok(command) => {
+                    self.apply(command);
+                    self.applied += 1;
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            #[cfg(feature = "with-player")]
+            {
+                self.continue_loop();
+                self.check_output();
+            }
+            self.publish();
+        }
+
+        self.shutdown();
+    }
+
+    fn apply(&mut self, command: PlayerCommand) {
+        match command {
+            PlayerCommand::Load { generation, path } => {
+                let result = self.build(generation, &path);
+                self.set_load_stage(None);
+                let _ = self.loads.send(LoadResult {
+                    generation,
+                    path,
+                    result,
+                });
+            }
+            PlayerCommand::Commit(generation) => self.commit(generation),
+            PlayerCommand::Discard => self.staged = None,
+            PlayerCommand::Shutdown => self.shutdown(),
+            #[cfg(feature = "with-player")]
+            command => self.control(&command),
+            #[cfg(not(feature = "with-player"))]
+            _ => {}
+        }
+    }
+
+    /// Checks `path` and builds a player for it, staged until it is
+    /// committed. The current player keeps playing meanwhile.
+    fn build(&mut self, generation: u64, path: &Path) -> Result<LoadedFile, PlaybackLoadError> {
+        self.staged = None;
+        self.set_load_stage(Some((generation, LoadStage::Checking)));
+        // Opening the file first tells a missing or unreadable file apart
+        // from one the decoder rejects.
+        std::fs::File::open(path).map_err(|error| PlaybackLoadError::from_io(path, &error))?;
+        preflight_supported_format(path)?;
+        self.set_load_stage(Some((generation, LoadStage::Building)));
+
+        #[cfg(feature = "with-player")]
+        {
+            let (path_string, alias) = library_path(path)?;
+            let mut player = if is_container_path(path) {
+                Player::try_from_source_with_options(
+                    PlayerSource::ContainerPath(path_string),
+                    PlayerInitOptions::default(),
+                )
+                .map_err(|error| PlaybackLoadError::CorruptContainer {
+                    file_name: display_file_name(path),
+                    detail: error.to_string(),
+                })?
+            } else {
+                Player::new_from_file_paths_legacy(vec![vec![path_string]])
+            };
+
+            let combination = player.get_ids();
+            if is_container_path(path) && combination.is_empty() {
+                return Err(PlaybackLoadError::MissingTracks {
+                    file_name: display_file_name(path),
+                });
+            }
+
+            player.set_max_sink_chunks(self.max_sink_chunks);
+            player.set_volume(self.volume);
+            player.set_end_of_stream_action(end_of_stream_action(self.looping));
+
+            let file = LoadedFile {
+                duration: Some(player.get_duration()),
+                combination,
+            };
+            self.staged = Some(StagedPlayer {
+                generation,
+                player,
+                alias,
+            });
+            Ok(file)
+        }
+
         #[cfg(not(feature = "with-player"))]
-        let _ = volume;
+        {
+            self.staged = Some(StagedPlayer { generation });
+            Ok(LoadedFile::default())
+        }
+    }
 
+    fn commit(&mut self, generation: u64) {
+        let Some(staged) = self
+            .staged
+            .take_if(|staged| staged.generation == generation)
+        else {
+            log::debug!("player build not staged generation={generation}");
+            return;
+        };
+
+        self.shutdown();
         #[cfg(feature = "with-player")]
         {
-            if let Some(player) = &mut self.player {
-                player.set_volume(volume.clamp(0.0, 1.0));
+            self.player = Some(staged.player);
+            self.alias = staged.alias;
+            self.loop_region = None;
+        }
+        #[cfg(not(feature = "with-player"))]
+        let _ = staged;
+    }
+
+    /// Runs a transport or settings command. Settings are kept for the next
+    /// player as well.
+    #[cfg(feature = "with-player")]
+    fn control(&mut self, command: &PlayerCommand) {
+        match *command {
+            PlayerCommand::SetVolume(volume) => self.volume = volume,
+            PlayerCommand::SetLooping(looping) => self.looping = looping,
+            PlayerCommand::SetLoopRegion(region) => self.loop_region = region,
+            PlayerCommand::SetMaxSinkChunks(chunks) => self.max_sink_chunks = chunks,
+            _ => {}
+        }
+
+        let Some(player) = &mut self.player else {
+            return;
+        };
+
+        match *command {
+            PlayerCommand::PlayPause => {
+                if player.is_playing() {
+                    log::debug!("player pause time={:.3}", player.get_time());
+                    player.pause();
+                    self.awaiting_output = false;
+                } else {
+                    log::debug!("player play time={:.3}", player.get_time());
+                    player.play();
+                    self.awaiting_output = true;
+                }
             }
+            PlayerCommand::Stop => {
+                log::debug!("player stop");
+                player.stop();
+                player.refresh_tracks();
+                self.awaiting_output = false;
+            }
+            PlayerCommand::Pause => {
+                player.pause();
+                self.awaiting_output = false;
+            }
+            PlayerCommand::Rewind => {
+                player.pause();
+                player.seek(0.0);
+                self.awaiting_output = false;
+            }
+            PlayerCommand::Shuffle => player.refresh_tracks(),
+            PlayerCommand::Seek(position) => {
+                log::debug!("player seek time={position:.3}");
+                player.seek(position.max(0.0));
+            }
+            PlayerCommand::StartAt { position, play } => {
+                log::debug!("player start time={position:.3} play={play}");
+                if play {
+                    player.play_at(position.max(0.0));
+                    self.awaiting_output = true;
+                } else {
+                    player.seek(position.max(0.0));
+                }
+            }
+            PlayerCommand::SetPartLevel { slot, level } => {
+                player.set_track_mix_inline(slot, level.clamp(0.0, 1.0), 0.0);
+            }
+            PlayerCommand::SetVolume(volume) => player.set_volume(volume),
+            PlayerCommand::SetLooping(looping) => {
+                player.set_end_of_stream_action(end_of_stream_action(looping));
+            }
+            PlayerCommand::SetMaxSinkChunks(chunks) => player.set_max_sink_chunks(chunks),
+            PlayerCommand::Load { .. }
+            | PlayerCommand::Commit(_)
+            | PlayerCommand::Discard
+            | PlayerCommand::Shutdown
+            | PlayerCommand::SetLoopRegion(_) => {}
         }
     }
 
-    pub fn is_loaded(&self) -> bool {
-        self.player.is_some()
+    /// Jumps back to the start of the loop region once playback passes its
+    /// end, and restarts a looping file that has played to its end.
+    #[cfg(feature = "with-player")]
+    fn continue_loop(&mut self) {
+        let Some(player) = &mut self.player else {
+            return;
+        };
+
+        if let Some((start, end)) = self.loop_region
+            && player.is_playing()
+            && player.get_time() >= end
+        {
+            player.seek(start);
+            return;
+        }
+
+        if !self.looping {
+            return;
+        }
+
+        // Looping players pause at the end instead of stopping, so the
+        // position stays at the duration until playback is restarted.
+        let duration = player.get_duration();
+        if duration > 0.0 && !player.is_playing() && player.get_time() >= duration - 0.25 {
+            player.play_at(0.0);
+        }
+    }
+
+    /// Counts a player that was asked to play but stopped without producing
+    /// any sound.
+    #[cfg(feature = "with-player")]
+    fn check_output(&mut self) {
+        if !self.awaiting_output {
+            return;
+        }
+        let Some(player) = &self.player else {
+            return;
+        };
+
+        let (thread_alive, _, audio_heard) = player.debug_playback_state();
+        if audio_heard {
+            log::debug!("player output heard");
+            self.awaiting_output = false;
+        } else if !thread_alive {
+            self.awaiting_output = false;
+            self.output_failures += 1;
+        }
+    }
+
+    fn shutdown(&mut self) {
+        #[cfg(feature = "with-player")]
+        {
+            if let Some(player) = self.player.take() {
+                log::debug!("player shutdown");
+                player.stop();
+            }
+            self.alias = None;
+            self.awaiting_output = false;
+        }
+    }
+
+    fn report(&self) -> PlayerReport {
+        #[cfg(feature = "with-player")]
+        if let Some(player) = &self.player {
+            let (_, _, queued_chunks) = player.debug_sink_state();
+            let metrics = player.get_dsp_metrics();
+            return PlayerReport {
+                loaded: true,
+                duration: Some(player.get_duration()),
+                time: player.get_time(),
+                playing: player.is_playing(),
+                combination: player.get_ids(),
+                finished_parts: player
+                    .finished_track_indices()
+                    .into_iter()
+                    .filter_map(|index| usize::try_from(index).ok())
+                    .collect(),
+                output_level: player
+                    .get_levels()
+                    .into_iter()
+                    .fold(0.0, f32::max)
+                    .clamp(0.0, 1.0),
+                buffer_stats: Some(BufferStats {
+                    queued_chunks,
+                    queued_ms: metrics.queued_sink_ms,
+                    underruns: metrics.underrun_count,
+                    max_overrun_ms: metrics.max_overrun_ms,
+                }),
+                output_failures: self.output_failures,
+            };
+        }
+
+        PlayerReport {
+            output_failures: self.output_failures,
+            ..PlayerReport::default()
+        }
+    }
+
+    fn publish(&self) {
+        let report = self.report();
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.applied = self.applied;
+            shared.report = report;
+        }
+    }
+
+    fn set_load_stage(&self, stage: Option<(u64, LoadStage)>) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.load_stage = stage;
+        }
+    }
+}
+
+#[cfg(feature = "with-player")]
+fn end_of_stream_action(looping: bool) -> EndOfStreamAction {
+    if looping {
+        EndOfStreamAction::Pause
+    } else {
+        EndOfStreamAction::Stop
     }
 }
 
@@ -221,29 +1000,218 @@ fn preflight_supported_format(path: &Path) -> Result<(), PlaybackLoadError> {
 
     #[cfg(feature = "with-player")]
     {
-        let path_string = path
-            .to_str()
-            .ok_or_else(|| PlaybackLoadError::other(anyhow!("path contains invalid UTF-8")))?;
+        let (path_string, _alias) = library_path(path)?;
+        let file_name = display_file_name(path);
 
-        if check_audio_file_supported(path_string).supported {
+        let check = match try_check_audio_file_supported(&path_string) {
+            Ok(check) => check,
+            Err(DecoderOpenError::Io(error)) => {
+                return Err(PlaybackLoadError::from_io(path, &error));
+            }
+            Err(DecoderOpenError::UnsupportedFormat(error)) if is_container_path(path) => {
+                return Err(PlaybackLoadError::CorruptContainer {
+                    file_name,
+                    detail: error.to_string(),
+                });
+            }
+            Err(DecoderOpenError::UnsupportedFormat(error)) => {
+                return Err(PlaybackLoadError::UnsupportedFormat {
+                    file_name,
+                    detail: Some(error.to_string()),
+                });
+            }
+            Err(DecoderOpenError::NoSupportedAudioTrack) => {
+                return Err(PlaybackLoadError::MissingTracks { file_name });
+            }
+            Err(DecoderOpenError::UnsupportedCodec(error)) => {
+                return Err(PlaybackLoadError::DecoderFailure {
+                    file_name,
+                    detail: error.to_string(),
+                });
+            }
+        };
+
+        if check.supported {
             Ok(())
+        } else if check.audio_track_count == 0 {
+            Err(PlaybackLoadError::MissingTracks { file_name })
         } else {
-            Err(PlaybackLoadError::UnsupportedFormat {
-                file_name: display_file_name(path),
+            Err(PlaybackLoadError::DecoderFailure {
+                file_name,
+                detail: check.reason.unwrap_or_default(),
             })
         }
     }
 }
 
+pub fn is_supported_path(path: &Path) -> bool {
+    extension_of(path).is_some_and(|extension| SUPPORTED_EXTENSIONS.contains(&extension.as_str()))
+}
+
+/// Reads duration and part count for the library index.
+///
+/// Containers are parsed to resolve their part layout; plain audio files count
+/// as a single part. Failures produce empty metadata rather than an error so a
+/// single unreadable file does not abort a folder scan.
+pub fn probe_media(path: &Path) -> MediaInfo {
+    #[cfg(not(feature = "with-player"))]
+    {
+        let _ = path;
+        MediaInfo::default()
+    }
+
+    #[cfg(feature = "with-player")]
+    {
+        let Ok((path_string, _alias)) = library_path(path) else {
+            return MediaInfo::default();
+        };
+        let path_string = path_string.as_str();
+
+        if is_container_path(path) {
+            match Prot::try_new(path_string) {
+                Ok(prot) => MediaInfo {
+                    duration: Some(*prot.get_duration()),
+                    part_count: Some(prot.get_length()),
+                },
+                Err(_) => MediaInfo::default(),
+            }
+        } else {
+            MediaInfo {
+                duration: try_get_durations(path_string)
+                    .ok()
+                    .and_then(|durations| durations.into_values().reduce(f64::max)),
+                part_count: Some(1),
+            }
+        }
+    }
+}
+
+/// `path` as proteus-lib takes it, which is as a `&str`. A name that is not
+/// valid Unicode is reached through an alias instead, which must be kept for
+/// as long as the library may open the file.
+#[cfg(feature = "with-player")]
+fn library_path(path: &Path) -> Result<(String, Option<PathAlias>), PlaybackLoadError> {
+    if let Some(path_string) = path.to_str() {
+        return Ok((path_string.to_owned(), None));
+    }
+
+    let alias = PathAlias::new(path).map_err(|error| {
+        PlaybackLoadError::other(anyhow!("could not open {}: {error}", path.display()))
+    })?;
+    let path_string = alias
+        .link
+        .to_str()
+        .ok_or_else(|| {
+            PlaybackLoadError::other(anyhow!("the app data directory is not valid Unicode"))
+        })?
+        .to_owned();
+    Ok((path_string, Some(alias)))
+}
+
+/// Where path aliases are made, inside the user's own app data directory so
+/// other users cannot see or replace them.
+#[cfg(feature = "with-player")]
+const ALIAS_DIRECTORY: &str = "aliases";
+
+/// A symlink with a UTF-8 name pointing at a file whose own name is not,
+/// removed again on drop. Aliases left by a process that did not exit
+/// cleanly are removed by [`remove_stale_path_aliases`].
+#[cfg(feature = "with-player")]
+struct PathAlias {
+    link: PathBuf,
+}
+
+#[cfg(feature = "with-player")]
+impl PathAlias {
+    #[cfg(unix)]
+    fn new(target: &Path) -> std::io::Result<Self> {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        static NEXT_ALIAS: AtomicU64 = AtomicU64::new(0);
+
+        let directory = storage::data_file_path(ALIAS_DIRECTORY).map_err(std::io::Error::other)?;
+        std::fs::create_dir_all(&directory)?;
+
+        // Keep the extension, which is how the library tells formats apart.
+        // The process id tells whose alias it is when cleaning up.
+        let extension = extension_of(target).unwrap_or_default();
+        let link = directory.join(format!(
+            "{}-{}.{extension}",
+            std::process::id(),
+            NEXT_ALIAS.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_file(&link);
+        std::os::unix::fs::symlink(std::path::absolute(target)?, &link)?;
+
+        Ok(Self { link })
+    }
+
+    /// Windows paths are only invalid Unicode with unpaired surrogates, and
+    /// creating symlinks needs extra privileges there.
+    #[cfg(not(unix))]
+    fn new(_target: &Path) -> std::io::Result<Self> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "the file name is not valid Unicode",
+        ))
+    }
+}
+
+#[cfg(feature = "with-player")]
+impl Drop for PathAlias {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.link);
+    }
+}
+
+/// Removes path aliases whose process is no longer running, as after a
+/// crash. Aliases of other running instances are left alone.
+pub fn remove_stale_path_aliases() {
+    #[cfg(all(feature = "with-player", unix))]
+    {
+        use sysinfo::{Pid, ProcessesToUpdate, System};
+
+        let Ok(directory) = storage::data_file_path(ALIAS_DIRECTORY) else {
+            return;
+        };
+        let Ok(entries) = std::fs::read_dir(&directory) else {
+            return;
+        };
+
+        let mut system = System::new();
+        for entry in entries.flatten() {
+            let pid = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.split_once('-'))
+                .and_then(|(pid, _)| pid.parse().ok())
+                .map(Pid::from_u32);
+            let running = pid.is_some_and(|pid| {
+                system.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+                system.process(pid).is_some()
+            });
+            if !running {
+                log::debug!("removing stale alias path={:?}", entry.path());
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+    }
+}
+
+#[cfg(feature = "with-player")]
+fn is_container_path(path: &Path) -> bool {
+    matches!(extension_of(path).as_deref(), Some("prot") | Some("mka"))
+}
+
+pub(crate) fn extension_of(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+}
+
 fn display_file_name(path: &Path) -> String {
     path.file_name()
         .and_then(|name| name.to_str())
         .map(str::to_owned)
         .unwrap_or_else(|| path.display().to_string())
 }
-
-impl Drop for PlaybackController {
-    fn drop(&mut self) {
-        self.shutdown();
-    }
-}
@@ -0,0 +1,377 @@
+use anyhow::{Result, anyhow};
+use muda::MenuId;
+
+use crate::native_menu::{MenuAction, WindowMenuEntry};
+
+const TRAY_TITLE: &str = "Proteus Player";
+
+/// What the tray menu reflects: every open player window, and whether the
+/// window its transport items control is playing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrayState {
+    pub windows: Vec<WindowMenuEntry>,
+    pub playing: bool,
+}
+
+impl TrayState {
+    fn tooltip(&self) -> String {
+        let playing: Vec<&str> = self
+            .windows
+            .iter()
+            .filter(|entry| entry.playing)
+            .map(|entry| entry.title.as_str())
+            .collect();
+
+        match playing.as_slice() {
+            [] => "Nothing playing".to_owned(),
+            [title] => format!("Playing {title}"),
+            titles => format!("Playing {} files", titles.len()),
+        }
+    }
+
+    fn items(&self) -> Vec<TrayItem> {
+        let mut items: Vec<TrayItem> = self
+            .windows
+            .iter()
+            .map(|entry| TrayItem::action(entry.label(), MenuAction::FocusWindow(entry.id)))
+            .collect();
+        if items.is_empty() {
+            items.push(TrayItem::Action {
+                label: "No open windows".to_owned(),
+                action: MenuAction::BringAllToFront,
+                enabled: false,
+            });
+        }
+
+        let has_windows = !self.windows.is_empty();
+        let play_pause = if self.playing { "Pause" } else { "Play" };
+        items.push(TrayItem::Separator);
+        for (label, action) in [
+            (play_pause, MenuAction::PlayPause),
+            ("New Combination", MenuAction::NewCombination),
+            ("Next File", MenuAction::NextFile),
+        ] {
+            items.push(TrayItem::Action {
+                label: label.to_owned(),
+                action,
+                enabled: has_windows,
+            });
+        }
+
+        items.push(TrayItem::Separator);
+        items.push(TrayItem::action(
+            "Show Proteus Player".to_owned(),
+            MenuAction::BringAllToFront,
+        ));
+        items.push(TrayItem::action("Quit".to_owned(), MenuAction::Quit));
+        items
+    }
+}
+
+/// One line of the tray menu, before it is built for the platform.
+#[derive(Debug, Clone)]
+enum TrayItem {
+    Action {
+        label: String,
+        action: MenuAction,
+        enabled: bool,
+    },
+    Separator,
+}
+
+impl TrayItem {
+    fn action(label: String, action: MenuAction) -> Self {
+        Self::Action {
+            label,
+            action,
+            enabled: true,
+        }
+    }
+}
+
+/// The tray icon, shown while the setting is on. Removing it from the
+/// system tray happens on drop.
+pub struct SystemTray {
+    platform: platform::Platform,
+    state: TrayState,
+}
+
+impl SystemTray {
+    pub fn install() -> Result<Self> {
+        let icon =
+            iced::window::icon::from_file_data(include_bytes!("../assets/app-icon-32.png"), None)
+                .map_err(|err| anyhow!(err.to_string()))?;
+        let (rgba, size) = icon.into_raw();
+        let state = TrayState::default();
+        let platform = platform::Platform::install(rgba, size.width, size.height, &state)?;
+
+        Ok(Self { platform, state })
+    }
+
+    /// Rebuilds the menu and tooltip, touching the tray only on change.
+    pub fn set_state(&mut self, state: TrayState) -> Result<()> {
+        if state == self.state {
+            return Ok(());
+        }
+
+        self.platform.set_state(&state)?;
+        self.state = state;
+        Ok(())
+    }
+
+    /// An action chosen from the tray on platforms that deliver its clicks
+    /// on a channel of its own.
+    pub fn poll_action(&self) -> Option<MenuAction> {
+        self.platform.poll_action()
+    }
+
+    /// The action behind a clicked menu item, on platforms where the tray
+    /// menu shares the menu bar's event channel.
+    pub fn action_for(&self, id: &MenuId) -> Option<MenuAction> {
+        self.platform.action_for(id)
+    }
+}
+
+/// Linux desktops show tray icons through the StatusNotifierItem D-Bus
+/// protocol, which needs no toolkit on our side.
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::sync::mpsc::{self, Receiver, Sender};
+
+    use anyhow::{Result, anyhow};
+    use ksni::blocking::{Handle, TrayMethods};
+    use ksni::menu::StandardItem;
+    use ksni::{Icon, MenuItem, ToolTip};
+    use muda::MenuId;
+
+    use super::{TRAY_TITLE, TrayItem, TrayState};
+    use crate::native_menu::MenuAction;
+
+    struct StatusNotifier {
+        state: TrayState,
+        icon: Icon,
+        actions: Sender<MenuAction>,
+    }
+
+    impl ksni::Tray for StatusNotifier {
+        fn id(&self) -> String {
+            "proteus-player".to_owned()
+        }
+
+        fn title(&self) -> String {
+            TRAY_TITLE.to_owned()
+        }
+
+        fn icon_pixmap(&self) -> Vec<Icon> {
+            vec![self.icon.clone()]
+        }
+
+        fn tool_tip(&self) -> ToolTip {
+            ToolTip {
+                title: TRAY_TITLE.to_owned(),
+                description: self.state.tooltip(),
+                ..Default::default()
+            }
+        }
+
+        fn activate(&mut self, _x: i32, _y: i32) {
+            let _ = self.actions.send(MenuAction::BringAllToFront);
+        }
+
+        fn menu(&self) -> Vec<MenuItem<Self>> {
+            self.state
+                .items()
+                .into_iter()
+                .map(|item| match item {
+                    TrayItem::Action {
+                        label,
+                        action,
+                        enabled,
+                    } => StandardItem {
+                        label,
+                        enabled,
+                        activate: Box::new(move |tray: &mut Self| {
+                            let _ = tray.actions.send(action.clone());
+                        }),
+                        ..Default::default()
+                    }
+                    .into(),
+                    TrayItem::Separator => MenuItem::Separator,
+                })
+                .collect()
+        }
+    }
+
+    pub(super) struct Platform {
+        handle: Handle<StatusNotifier>,
+        actions: Receiver<MenuAction>,
+    }
+
+    impl Platform {
+        pub(super) fn install(
+            rgba: Vec<u8>,
+            width: u32,
+            height: u32,
+            state: &TrayState,
+        ) -> Result<Self> {
+            // The protocol wants ARGB32 in network byte order.
+            let data = rgba
+                .chunks_exact(4)
+                .flat_map(|pixel| [pixel[3], pixel[0], pixel[1], pixel[2]])
+                .collect();
+            let (sender, actions) = mpsc::channel();
+            let tray = StatusNotifier {
+                state: state.clone(),
+                icon: Icon {
+                    width: width as i32,
+                    height: height as i32,
+                    data,
+                },
+                actions: sender,
+            };
+            let handle = tray.spawn().map_err(|err| anyhow!(err.to_string()))?;
+
+            Ok(Self { handle, actions })
+        }
+
+        pub(super) fn set_state(&mut self, state: &TrayState) -> Result<()> {
+            self.handle
+                .update(|tray| tray.state = state.clone())
+                .ok_or_else(|| anyhow!("the tray service has stopped"))
+        }
+
+        pub(super) fn poll_action(&self) -> Option<MenuAction> {
+            self.actions.try_recv().ok()
+        }
+
+        pub(super) fn action_for(&self, _id: &MenuId) -> Option<MenuAction> {
+            None
+        }
+    }
+
+    impl Drop for Platform {
+        fn drop(&mut self) {
+            let _ = self.handle.shutdown();
+        }
+    }
+}
+
+/// Elsewhere the tray menu is a `muda` menu, so its clicks arrive with the
+/// menu bar's and are told apart by id.
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use std::collections::HashMap;
+
+    use anyhow::{Result, anyhow};
+    use muda::accelerator::Accelerator;
+    use muda::{Menu, MenuId, MenuItem, PredefinedMenuItem};
+    use tray_icon::{Icon, TrayIcon, TrayIconBuilder};
+
+    use super::{TRAY_TITLE, TrayItem, TrayState};
+    use crate::native_menu::MenuAction;
+
+    pub(super) struct Platform {
+        tray: TrayIcon,
+        actions: HashMap<MenuId, MenuAction>,
+    }
+
+    impl Platform {
+        pub(super) fn install(
+            rgba: Vec<u8>,
+            width: u32,
+            height: u32,
+            state: &TrayState,
+        ) -> Result<Self> {
+            let icon =
+                Icon::from_rgba(rgba, width, height).map_err(|err| anyhow!(err.to_string()))?;
+            let tray = TrayIconBuilder::new()
+                .with_icon(icon)
+                .with_tooltip(TRAY_TITLE)
+                .build()
+                .map_err(|err| anyhow!(err.to_string()))?;
+
+            let mut platform = Self {
+                tray,
+                actions: HashMap::new(),
+            };
+            platform.set_state(state)?;
+            Ok(platform)
+        }
+
+        pub(super) fn set_state(&mut self, state: &TrayState) -> Result<()> {
+            let menu = Menu::new();
+            self.actions.clear();
+
+            for (index, item) in state.items().into_iter().enumerate() {
+                match item {
+                    TrayItem::Action {
+                        label,
+                        action,
+                        enabled,
+                    } => {
+                        let id = MenuId::new(format!("tray_{index}"));
+                        let item =
+                            MenuItem::with_id(id.clone(), label, enabled, None::<Accelerator>);
+                        menu.append(&item).map_err(|err| anyhow!(err.to_string()))?;
+                        self.actions.insert(id, action);
+                    }
+                    TrayItem::Separator => menu
+                        .append(&PredefinedMenuItem::separator())
+                        .map_err(|err| anyhow!(err.to_string()))?,
+                }
+            }
+
+            self.tray.set_menu(Some(Box::new(menu)));
+            self.tray
+                .set_tooltip(Some(format!("{TRAY_TITLE} — {}", state.tooltip())))
+                .map_err(|err| anyhow!(err.to_string()))
+        }
+
+        pub(super) fn poll_action(&self) -> Option<MenuAction> {
+            None
+        }
+
+        pub(super) fn action_for(&self, id: &MenuId) -> Option<MenuAction> {
+            self.actions.get(id).cloned()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tray_menu_lists_windows_before_the_transport() {
+        let state = TrayState {
+            windows: vec![WindowMenuEntry {
+                id: iced::window::Id::unique(),
+                title: "Etude.prot".to_owned(),
+                playing: true,
+            }],
+            playing: true,
+        };
+
+        let labels: Vec<String> = state
+            .items()
+            .into_iter()
+            .filter_map(|item| match item {
+                TrayItem::Action { label, .. } => Some(label),
+                TrayItem::Separator => None,
+            })
+            .collect();
+
+        assert_eq!(
+            labels,
+            [
+                "▶ Etude.prot",
+                "Pause",
+                "New Combination",
+                "Next File",
+                "Show Proteus Player",
+                "Quit"
+            ]
+        );
+        assert_eq!(state.tooltip(), "Playing Etude.prot");
+    }
+}
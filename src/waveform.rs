@@ -0,0 +1,401 @@
+use std::path::Path;
+
+#[cfg(feature = "with-player")]
+use std::collections::HashMap;
+#[cfg(feature = "with-player")]
+use std::sync::OnceLock;
+
+#[cfg(feature = "with-player")]
+use symphonia::core::audio::SampleBuffer;
+#[cfg(feature = "with-player")]
+use symphonia::core::codecs::{CODEC_TYPE_NULL, CodecRegistry, Decoder, DecoderOptions};
+#[cfg(feature = "with-player")]
+use symphonia::core::errors::Error as DecodeError;
+#[cfg(feature = "with-player")]
+use symphonia::core::formats::FormatOptions;
+#[cfg(feature = "with-player")]
+use symphonia::core::io::MediaSourceStream;
+#[cfg(feature = "with-player")]
+use symphonia::core::meta::MetadataOptions;
+#[cfg(feature = "with-player")]
+use symphonia::core::probe::Hint;
+
+#[cfg(feature = "with-player")]
+use crate::playback::extension_of;
+
+/// How many buckets an overview spans, whatever the file's length.
+pub const BUCKET_COUNT: usize = 600;
+
+/// Length of audio measured at a time before it is folded into buckets.
+#[cfg(feature = "with-player")]
+const WINDOW_SECONDS: f64 = 0.05;
+
+/// Peak and RMS level of a stretch of audio, `0.0` to `1.0`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Level {
+    pub peak: f32,
+    pub rms: f32,
+}
+
+/// Levels across a whole file for each of its tracks, in
+/// [`BUCKET_COUNT`] equal buckets from the start to `duration`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Waveform {
+    pub duration: f64,
+    /// Buckets by the id the player reports for the track's part: the
+    /// container's track id, or the path of a plain audio file.
+    tracks: Vec<(String, Vec<Level>)>,
+}
+
+impl Waveform {
+    /// The buckets of the track playing as `id`. A plain audio file has a
+    /// single track, which is what any id of it refers to.
+    pub fn track(&self, id: &str) -> Option<&[Level]> {
+        self.tracks
+            .iter()
+            .find(|(track_id, _)| track_id == id)
+            .or(match self.tracks.as_slice() {
+                [only] => Some(only),
+                _ => None,
+            })
+            .map(|(_, levels)| levels.as_slice())
+    }
+
+    /// How much of the output each part in `ids` makes up `fraction` of the
+    /// way through the file, mixed at the level `level_of` gives each slot.
+    /// The peak is the part's share of the output's amplitude, the RMS that
+    /// share scaled by how far the part's RMS sits below its peak. Parts that
+    /// have not been measured share evenly by mixer level.
+    pub fn part_shares(
+        &self,
+        ids: &[String],
+        fraction: f64,
+        level_of: impl Fn(usize) -> f32,
+    ) -> Vec<Level> {
+        let bucket =
+            ((fraction.clamp(0.0, 1.0) * BUCKET_COUNT as f64) as usize).min(BUCKET_COUNT - 1);
+        let levels: Vec<(Level, f32)> = ids
+            .iter()
+            .enumerate()
+            .map(|(slot, id)| {
+                let level = self
+                    .track(id)
+                    .and_then(|levels| levels.get(bucket).copied())
+                    .unwrap_or(Level {
+                        peak: 1.0,
+                        rms: 1.0,
+                    });
+                (level, level_of(slot))
+            })
+            .collect();
+        let total: f32 = levels
+            .iter()
+            .map(|(level, gain)| (level.rms * gain).powi(2))
+            .sum();
+        if total <= 0.0 {
+            return vec![Level::default(); levels.len()];
+        }
+
+        levels
+            .into_iter()
+            .map(|(level, gain)| {
+                let share = ((level.rms * gain).powi(2) / total).sqrt();
+                Level {
+                    peak: share,
+                    rms: share * (level.rms / level.peak.max(f32::EPSILON)).min(1.0),
+                }
+            })
+            .collect()
+    }
+
+    /// The buckets of the parts in `ids` mixed at the level `level_of` gives
+    /// each slot.
+    pub fn mix(&self, ids: &[String], level_of: impl Fn(usize) -> f32) -> Vec<Level> {
+        let mut peaks = vec![0.0; BUCKET_COUNT];
+        let mut energy = vec![0.0; BUCKET_COUNT];
+        for (slot, id) in ids.iter().enumerate() {
+            let Some(levels) = self.track(id) else {
+                continue;
+            };
+            let gain = level_of(slot);
+            for (bucket, level) in levels.iter().enumerate().take(BUCKET_COUNT) {
+                peaks[bucket] += level.peak * gain;
+                energy[bucket] += (level.rms * gain).powi(2);
+            }
+        }
+
+        peaks
+            .into_iter()
+            .zip(energy)
+            .map(|(peak, energy): (f32, f32)| Level {
+                peak: peak.min(1.0),
+                rms: energy.sqrt().min(1.0),
+            })
+            .collect()
+    }
+}
+
+/// Decodes every track of the file at `path` to measure its levels.
+/// Blocking, and about as slow as reading the whole file; run it off the UI
+/// thread.
+pub fn read(path: &Path) -> Result<Waveform, String> {
+    #[cfg(not(feature = "with-player"))]
+    {
+        let _ = path;
+        Err("this build cannot decode audio".to_owned())
+    }
+
+    #[cfg(feature = "with-player")]
+    {
+        let file = std::fs::File::open(path)
+            .map_err(|error| format!("could not open {}: {error}", path.display()))?;
+        let mut hint = Hint::new();
+        if let Some(extension) = extension_of(path) {
+            // Containers are Matroska under another name.
+            hint.with_extension(if extension == "prot" {
+                "mka"
+            } else {
+                &extension
+            });
+        }
+        let mut format = symphonia::default::get_probe()
+            .format(
+                &hint,
+                MediaSourceStream::new(Box::new(file), Default::default()),
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .map_err(|error| format!("could not read {}: {error}", path.display()))?
+            .format;
+
+        let container = matches!(extension_of(path).as_deref(), Some("prot") | Some("mka"));
+        let mut tracks = HashMap::new();
+        for track in format.tracks() {
+            if track.codec_params.codec == CODEC_TYPE_NULL {
+                continue;
+            }
+            let Some(sample_rate) = track.codec_params.sample_rate else {
+                continue;
+            };
+            let Ok(decoder) = codecs().make(&track.codec_params, &DecoderOptions::default()) else {
+                continue;
+            };
+            let id = if container {
+                track.id.to_string()
+            } else {
+                path.to_string_lossy().into_owned()
+            };
+            tracks.insert(track.id, TrackLevels::new(id, decoder, sample_rate));
+            if !container {
+                break;
+            }
+        }
+        if tracks.is_empty() {
+            return Err(format!("{} has no tracks to measure", path.display()));
+        }
+
+        loop {
+            let packet = match format.next_packet() {
+                Ok(packet) => packet,
+                Err(DecodeError::IoError(error))
+                    if error.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    break;
+                }
+                // What has been measured so far is still worth drawing.
+                Err(DecodeError::ResetRequired) => break,
+                Err(error) => return Err(format!("could not read {}: {error}", path.display())),
+            };
+            if let Some(track) = tracks.get_mut(&packet.track_id()) {
+                track.decode(&packet);
+            }
+        }
+
+        let tracks: Vec<_> = tracks.into_values().map(TrackLevels::finish).collect();
+        let windows = tracks
+            .iter()
+            .map(|(_, windows)| windows.len())
+            .max()
+            .unwrap_or(0);
+        Ok(Waveform {
+            duration: windows as f64 * WINDOW_SECONDS,
+            tracks: tracks
+                .into_iter()
+                .map(|(id, levels)| (id, into_buckets(&levels, windows)))
+                .collect(),
+        })
+    }
+}
+
+/// The decoders proteus-lib plays with, Opus included.
+#[cfg(feature = "with-player")]
+fn codecs() -> &'static CodecRegistry {
+    static CODECS: OnceLock<CodecRegistry> = OnceLock::new();
+    CODECS.get_or_init(|| {
+        let mut registry = CodecRegistry::new();
+        symphonia::default::register_enabled_codecs(&mut registry);
+        registry.register_all::<symphonia_adapter_libopus::OpusDecoder>();
+        registry
+    })
+}
+
+/// One track being decoded, measured in windows of [`WINDOW_SECONDS`].
+#[cfg(feature = "with-player")]
+struct TrackLevels {
+    id: String,
+    decoder: Box<dyn Decoder>,
+    samples: Option<SampleBuffer<f32>>,
+    frames_per_window: usize,
+    frames: usize,
+    peak: f32,
+    energy: f64,
+    windows: Vec<Level>,
+}
+
+#[cfg(feature = "with-player")]
+impl TrackLevels {
+    fn new(id: String, decoder: Box<dyn Decoder>, sample_rate: u32) -> Self {
+        Self {
+            id,
+            decoder,
+            samples: None,
+            frames_per_window: ((f64::from(sample_rate) * WINDOW_SECONDS) as usize).max(1),
+            frames: 0,
+            peak: 0.0,
+            energy: 0.0,
+            windows: Vec::new(),
+        }
+    }
+
+    fn decode(&mut self, packet: &symphonia::core::formats::Packet) {
+        // A packet that fails to decode is skipped, as the player skips it.
+        let Ok(decoded) = self.decoder.decode(packet) else {
+            return;
+        };
+        let channels = decoded.spec().channels.count().max(1);
+        let mut samples = match self.samples.take() {
+            Some(samples) if samples.capacity() >= decoded.capacity() => samples,
+            _ => SampleBuffer::new(decoded.capacity() as u64, *decoded.spec()),
+        };
+        samples.copy_interleaved_ref(decoded);
+
+        for frame in samples.samples().chunks(channels) {
+            for sample in frame {
+                self.peak = self.peak.max(sample.abs());
+                self.energy += f64::from(sample * sample) / channels as f64;
+            }
+            self.frames += 1;
+            if self.frames == self.frames_per_window {
+                self.end_window();
+            }
+        }
+        self.samples = Some(samples);
+    }
+
+    fn end_window(&mut self) {
+        self.windows.push(Level {
+            peak: self.peak.min(1.0),
+            rms: ((self.energy / self.frames as f64).sqrt() as f32).min(1.0),
+        });
+        self.frames = 0;
+        self.peak = 0.0;
+        self.energy = 0.0;
+    }
+
+    fn finish(mut self) -> (String, Vec<Level>) {
+        if self.frames > 0 {
+            self.end_window();
+        }
+        (self.id, self.windows)
+    }
+}
+
+/// Folds a track's windows into [`BUCKET_COUNT`] buckets, where the longest
+/// track has `total` windows. A file shorter than that many windows spreads
+/// each window over several buckets; buckets past the end of a shorter track
+/// are silent.
+#[cfg_attr(not(feature = "with-player"), allow(dead_code))]
+fn into_buckets(windows: &[Level], total: usize) -> Vec<Level> {
+    (0..BUCKET_COUNT)
+        .map(|bucket| {
+            let start = bucket * total / BUCKET_COUNT;
+            let end = ((bucket + 1) * total / BUCKET_COUNT).max(start + 1);
+            let covered = windows.get(start..end.min(windows.len())).unwrap_or(&[]);
+            if covered.is_empty() {
+                return Level::default();
+            }
+
+            let energy: f32 = covered.iter().map(|window| window.rms * window.rms).sum();
+            Level {
+                peak: covered
+                    .iter()
+                    .fold(0.0, |peak, window| peak.max(window.peak)),
+                rms: (energy / covered.len() as f32).sqrt(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_keep_the_loudest_peak_and_average_the_power() {
+        let windows: Vec<Level> = (0..BUCKET_COUNT * 2)
+            .map(|index| Level {
+                peak: if index % 2 == 0 { 0.2 } else { 0.8 },
+                rms: if index % 2 == 0 { 0.0 } else { 0.5 },
+            })
+            .collect();
+
+        let buckets = into_buckets(&windows, windows.len());
+        assert_eq!(buckets.len(), BUCKET_COUNT);
+        assert_eq!(buckets[0].peak, 0.8);
+        assert!((buckets[0].rms - 0.125_f32.sqrt()).abs() < 1e-6);
+
+        // A track half as long as the longest one leaves the rest silent.
+        let short = into_buckets(&windows[..BUCKET_COUNT], windows.len());
+        assert!(short[BUCKET_COUNT / 2 - 1].peak > 0.0);
+        assert_eq!(short[BUCKET_COUNT / 2], Level::default());
+
+        // A file of fewer windows than buckets still fills every bucket.
+        let brief = into_buckets(&windows[..10], 10);
+        assert!(brief.iter().all(|level| level.peak > 0.0));
+    }
+
+    #[test]
+    fn parts_share_the_output_by_measured_level_and_mixer_level() {
+        let steady = |rms| {
+            vec![
+                Level {
+                    peak: rms * 2.0,
+                    rms
+                };
+                BUCKET_COUNT
+            ]
+        };
+        let waveform = Waveform {
+            duration: 60.0,
+            tracks: vec![("1".to_owned(), steady(0.3)), ("2".to_owned(), steady(0.4))],
+        };
+        let ids = ["1".to_owned(), "2".to_owned(), "3".to_owned()];
+
+        let shares = waveform.part_shares(&ids[..2], 0.5, |_| 1.0);
+        assert!((shares[0].peak - 0.6).abs() < 1e-6);
+        assert!((shares[1].peak - 0.8).abs() < 1e-6);
+        assert!((shares[1].rms - 0.4).abs() < 1e-6);
+
+        let muted = waveform.part_shares(&ids[..2], 0.5, |slot| if slot == 0 { 0.0 } else { 1.0 });
+        assert_eq!(muted[0], Level::default());
+        assert!((muted[1].peak - 1.0).abs() < 1e-6);
+
+        // Without measurements the parts split the output evenly.
+        let unmeasured = Waveform::default().part_shares(&ids, 0.5, |_| 1.0);
+        assert!(
+            unmeasured
+                .iter()
+                .all(|level| (level.peak - 1.0 / 3.0_f32.sqrt()).abs() < 1e-6)
+        );
+    }
+}